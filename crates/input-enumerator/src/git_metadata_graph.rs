@@ -586,6 +586,87 @@ impl GitMetadataGraph {
     }
 }
 
+impl GitMetadataGraph {
+    /// Get the blobs that are present in the trees of the tip commits of the graph, i.e., the
+    /// commits that have no child commits.
+    ///
+    /// Each blob is attributed to a single tip commit: the first one, in ascending order of
+    /// `tip_key`, whose tree contains the blob. The blobs attributed to each tip commit are given
+    /// in `introduced_blobs`; blobs that are not present in any tip commit are not included.
+    pub(crate) fn get_tip_blobs<K: Ord>(
+        &self,
+        repo_index: &RepositoryIndex,
+        repo: &gix::Repository,
+        tip_key: impl Fn(&ObjectId) -> K,
+    ) -> Result<Vec<CommitBlobMetadata>> {
+        let _span =
+            error_span!("get_tip_blobs", path = repo.path().display().to_string()).entered();
+
+        let t1 = Instant::now();
+        let cg = &self.commits;
+
+        let mut tips: Vec<(K, &CommitMetadata)> = cg
+            .node_indices()
+            .filter(|idx| cg.neighbors_directed(*idx, Outgoing).next().is_none())
+            .map(|idx| {
+                let md = self.get_commit_metadata(idx);
+                (tip_key(&md.oid), md)
+            })
+            .collect();
+        tips.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+        // A single seen set is shared among all the tip commits: a tree or blob that has already
+        // been seen is present in an earlier tip commit, to which its blobs are attributed.
+        let mut seen = SeenObjectSet::new();
+        let mut symbols = BStringTable::with_capacity(32 * 1024, 1024 * 1024);
+        let mut tree_worklist = TreeWorklist::with_capacity(32 * 1024);
+        let mut tree_buf = Vec::with_capacity(1024 * 1024);
+        let mut blobs_encountered = Vec::with_capacity(16 * 1024);
+        let mut num_trees = 0;
+        let mut num_blobs = 0;
+
+        let mut tip_blobs = Vec::with_capacity(tips.len());
+        for (_key, commit_md) in tips.iter() {
+            let tree_idx = unwrap_some_or_continue!(commit_md.tree_idx, || {
+                warn!("Failed to find tree for tip commit {}", commit_md.oid);
+            });
+            if !seen.insert_tree(tree_idx)? {
+                continue;
+            }
+            let mut blobs = IntroducedBlobs::new();
+            tree_worklist
+                .push((SmallVec::new(), repo_index.get_tree_oid(tree_idx).unwrap().to_owned()));
+            visit_tree(
+                repo,
+                &mut symbols,
+                repo_index,
+                &mut num_trees,
+                &mut num_blobs,
+                &mut seen,
+                &mut blobs,
+                &mut tree_buf,
+                &mut tree_worklist,
+                &mut blobs_encountered,
+            )?;
+            tip_blobs.push(CommitBlobMetadata {
+                commit_oid: commit_md.oid,
+                introduced_blobs: blobs,
+            });
+        }
+
+        debug!(
+            "{} tip commits visited; \
+              {num_trees} trees visited; \
+              {num_blobs} blobs found; \
+              {:.6}s",
+            tips.len(),
+            t1.elapsed().as_secs_f64()
+        );
+
+        Ok(tip_blobs)
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn visit_tree(
     repo: &gix::Repository,
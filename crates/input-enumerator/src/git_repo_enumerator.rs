@@ -31,6 +31,12 @@ pub struct GitRepoResult {
 pub struct BlobMetadata {
     pub blob_oid: ObjectId,
     pub first_seen: BlobAppearanceSet,
+
+    /// The most recent branch tip commit in which the blob is present, if any.
+    ///
+    /// This is `None` when the blob is no longer present at the tip of any branch, or when latest
+    /// commits were not requested.
+    pub latest_seen: Option<BlobAppearance>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -40,6 +46,9 @@ pub struct GitRepoWithMetadataEnumerator<'a> {
     path: &'a Path,
     repo: Repository,
     gitignore: &'a Gitignore,
+
+    /// Should the latest branch tip commit containing each blob be determined?
+    collect_latest_commits: bool,
 }
 
 impl<'a> GitRepoWithMetadataEnumerator<'a> {
@@ -48,9 +57,18 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
             path,
             repo,
             gitignore,
+            collect_latest_commits: false,
         }
     }
 
+    /// Enable or disable whether the latest branch tip commit containing each blob is determined.
+    ///
+    /// This requires walking the tree of each tip commit, and is disabled by default.
+    pub fn collect_latest_commits(mut self, collect_latest_commits: bool) -> Self {
+        self.collect_latest_commits = collect_latest_commits;
+        self
+    }
+
     pub fn run(self) -> Result<GitRepoResult> {
         let t1 = Instant::now();
 
@@ -114,6 +132,39 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
 
         debug!("Built metadata graph in {:.6}s", t1.elapsed().as_secs_f64());
 
+        // Attribute each blob present in a tip commit to the most recently committed one
+        let mut blob_to_latest: HashMap<ObjectId, BlobAppearance> = Default::default();
+        if self.collect_latest_commits {
+            let tip_key = |oid: &ObjectId| {
+                let seconds = commit_metadata
+                    .get(oid)
+                    .map(|md| md.committer_timestamp.seconds);
+                std::cmp::Reverse(seconds)
+            };
+            match metadata_graph.get_tip_blobs(&object_index, &self.repo, tip_key) {
+                Err(e) => {
+                    error!("Failed to compute blobs in tip commits; ignoring: {e}");
+                }
+                Ok(tip_blobs) => {
+                    for e in tip_blobs.into_iter() {
+                        let commit_metadata =
+                            unwrap_some_or_continue!(commit_metadata.get(&e.commit_oid), || {
+                                error!("Failed to find commit metadata for {}", e.commit_oid);
+                            });
+                        for (blob_oid, path) in e.introduced_blobs.into_iter() {
+                            blob_to_latest.insert(
+                                blob_oid,
+                                BlobAppearance {
+                                    commit_metadata: commit_metadata.clone(),
+                                    path,
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
         match metadata_graph.get_repo_metadata(&object_index, &self.repo) {
             Err(e) => {
                 error!("Failed to compute reachable blobs; ignoring metadata: {e}");
@@ -123,6 +174,7 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                     .map(|blob_oid| BlobMetadata {
                         blob_oid,
                         first_seen: Default::default(),
+                        latest_seen: None,
                     })
                     .collect();
                 Ok(GitRepoResult {
@@ -173,11 +225,13 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                 let blobs: Vec<BlobMetadata> = blob_to_appearance
                     .into_iter()
                     .filter_map(|(blob_oid, first_seen)| {
+                        let latest_seen = blob_to_latest.remove(&blob_oid);
                         if first_seen.is_empty() {
                             // no commit metadata at all for blob
                             Some(BlobMetadata {
                                 blob_oid,
                                 first_seen,
+                                latest_seen,
                             })
                         } else {
                             // filter out path-ignored provenance entries; suppress blob if all
@@ -211,6 +265,7 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                                 Some(BlobMetadata {
                                     blob_oid,
                                     first_seen,
+                                    latest_seen,
                                 })
                             }
                         }
@@ -270,6 +325,7 @@ impl<'a> GitRepoEnumerator<'a> {
             .map(|blob_oid| BlobMetadata {
                 blob_oid,
                 first_seen: Default::default(),
+                latest_seen: None,
             })
             .collect();
         Ok(GitRepoResult {
@@ -6,8 +6,10 @@ pub use gix::{Repository, ThreadSafeRepository};
 
 use anyhow::{bail, Result};
 use crossbeam_channel::Sender;
+pub use globset::{Glob, GlobSet, GlobSetBuilder};
 pub use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::{DirEntry, WalkBuilder, WalkState};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
@@ -52,6 +54,7 @@ pub enum FoundInput {
     File(FileResult),
     Directory(DirectoryResult),
     EnumeratorFile(EnumeratorFileResult),
+    Skipped(SkippedResult),
 }
 
 pub struct FileResult {
@@ -67,6 +70,59 @@ pub struct DirectoryResult {
     pub path: PathBuf,
 }
 
+/// A filesystem entry that was not enumerated, and why.
+///
+/// These are only produced when `FilesystemEnumerator::report_skipped` is enabled.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedResult {
+    pub path: PathBuf,
+
+    /// The size of the entry, if it is a file
+    pub num_bytes: Option<u64>,
+
+    pub reason: SkipReason,
+}
+
+/// Why a filesystem entry was not enumerated.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "message")]
+pub enum SkipReason {
+    /// The file exceeds the maximum file size
+    MaxFileSize,
+
+    /// The entry matches an ignore rule
+    IgnoreRule,
+
+    /// The entry matches an exclude glob, or is a file that matches no include glob
+    GlobFilter,
+
+    /// The directory looks like a Nosey Parker datastore
+    Datastore,
+
+    /// The entry is a symbolic link, and symbolic links are not being followed
+    Symlink,
+
+    /// The entry is neither a file, directory, nor symbolic link
+    UnhandledType,
+
+    /// The entry could not be read
+    Error(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::MaxFileSize => write!(f, "exceeds max file size"),
+            SkipReason::IgnoreRule => write!(f, "matches ignore rule"),
+            SkipReason::GlobFilter => write!(f, "excluded by glob filter"),
+            SkipReason::Datastore => write!(f, "looks like a Nosey Parker datastore"),
+            SkipReason::Symlink => write!(f, "symbolic link"),
+            SkipReason::UnhandledType => write!(f, "unhandled path type"),
+            SkipReason::Error(e) => write!(f, "error: {e}"),
+        }
+    }
+}
+
 pub type Output = Sender<FoundInput>;
 
 // -------------------------------------------------------------------------------------------------
@@ -74,6 +130,10 @@ pub type Output = Sender<FoundInput>;
 // -------------------------------------------------------------------------------------------------
 struct VisitorBuilder<'t> {
     max_file_size: Option<u64>,
+    gitignore: &'t Gitignore,
+    include_globs: Option<&'t GlobSet>,
+    exclude_globs: &'t GlobSet,
+    report_skipped: bool,
     output: &'t Output,
 }
 
@@ -84,6 +144,10 @@ where
     fn build(&mut self) -> Box<dyn ignore::ParallelVisitor + 's> {
         Box::new(Visitor {
             max_file_size: self.max_file_size,
+            gitignore: self.gitignore,
+            include_globs: self.include_globs,
+            exclude_globs: self.exclude_globs,
+            report_skipped: self.report_skipped,
             output: self.output,
         })
     }
@@ -94,6 +158,10 @@ where
 // -------------------------------------------------------------------------------------------------
 struct Visitor<'t> {
     max_file_size: Option<u64>,
+    gitignore: &'t Gitignore,
+    include_globs: Option<&'t GlobSet>,
+    exclude_globs: &'t GlobSet,
+    report_skipped: bool,
     output: &'t Output,
 }
 
@@ -110,6 +178,46 @@ impl<'t> Visitor<'t> {
     fn found_directory(&mut self, r: DirectoryResult) {
         self.output.send(FoundInput::Directory(r)).unwrap();
     }
+
+    fn skipped(&mut self, path: &Path, num_bytes: Option<u64>, reason: SkipReason) {
+        if self.report_skipped {
+            let path = path.to_owned();
+            self.output
+                .send(FoundInput::Skipped(SkippedResult {
+                    path,
+                    num_bytes,
+                    reason,
+                }))
+                .unwrap();
+        }
+    }
+
+    /// Does the given entry match the ignore rules?
+    ///
+    /// Like the `ignore` crate, this never considers the top-level input roots to be ignored.
+    fn is_ignored(&self, entry: &DirEntry, is_dir: bool) -> bool {
+        if entry.depth() == 0 {
+            return false;
+        }
+        let path = entry.path();
+        let path = path.strip_prefix("./").unwrap_or(path);
+        self.gitignore.matched(path, is_dir).is_ignore()
+    }
+
+    /// Is the given entry excluded by the include and exclude globs?
+    ///
+    /// Exclude globs apply to both files and directories, though never to the top-level input
+    /// directories. Include globs apply only to files.
+    fn is_glob_excluded(&self, entry: &DirEntry, is_file: bool) -> bool {
+        let path = entry.path();
+        let path = path.strip_prefix("./").unwrap_or(path);
+        if is_file {
+            self.exclude_globs.is_match(path)
+                || self.include_globs.is_some_and(|g| !g.is_match(path))
+        } else {
+            entry.depth() > 0 && self.exclude_globs.is_match(path)
+        }
+    }
 }
 
 impl<'t> ignore::ParallelVisitor for Visitor<'t> {
@@ -119,6 +227,10 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
         let entry = match result {
             Err(e) => {
                 warn!("Skipping entry: {e}");
+                // Walk errors don't always have a path; report the ones that do
+                if let Some(path) = error_path(&e) {
+                    self.skipped(path, None, SkipReason::Error(e.to_string()));
+                }
                 return WalkState::Skip;
             }
             Ok(v) => v,
@@ -128,15 +240,31 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
         let metadata = match entry.metadata() {
             Err(e) => {
                 warn!("Skipping {}: failed to get metadata: {e}", path.display());
+                self.skipped(path, None, SkipReason::Error(e.to_string()));
                 return WalkState::Skip;
             }
             Ok(v) => v,
         };
 
+        if self.is_ignored(&entry, metadata.is_dir()) {
+            debug!("Skipping {}: matches ignore rule", path.display());
+            let num_bytes = metadata.is_file().then_some(metadata.len());
+            self.skipped(path, num_bytes, SkipReason::IgnoreRule);
+            return WalkState::Skip;
+        }
+
+        if self.is_glob_excluded(&entry, metadata.is_file()) {
+            debug!("Skipping {}: excluded by glob filter", path.display());
+            let num_bytes = metadata.is_file().then_some(metadata.len());
+            self.skipped(path, num_bytes, SkipReason::GlobFilter);
+            return WalkState::Skip;
+        }
+
         if metadata.is_file() {
             let num_bytes = metadata.len();
             if self.file_too_big(num_bytes) {
                 debug!("Skipping {}: size {num_bytes} exceeds max size", path.display());
+                self.skipped(path, Some(num_bytes), SkipReason::MaxFileSize);
             } else {
                 let path = path.to_owned();
                 self.found_file(FileResult { path, num_bytes });
@@ -149,6 +277,7 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
                 && path.join("blobs").is_dir()
             {
                 debug!("Skipping {}: looks like a Nosey Parker datastore", path.display());
+                self.skipped(path, None, SkipReason::Datastore);
                 return WalkState::Skip;
             } else {
                 self.found_directory(DirectoryResult {
@@ -160,13 +289,26 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
             //
             // Had follow_symlinks been enabled, the pointed-to entry would have been yielded
             // instead.
+            self.skipped(path, None, SkipReason::Symlink);
         } else {
             debug!("Skipping {}: unhandled path type: {:?}", path.display(), entry.file_type());
+            self.skipped(path, None, SkipReason::UnhandledType);
         }
         WalkState::Continue
     }
 }
 
+/// Get the path associated with an error from the `ignore` crate, if there is one.
+fn error_path(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        _ => None,
+    }
+}
+
 /// Provides capabitilies to recursively enumerate a filesystem.
 ///
 /// This provides a handful of features, including:
@@ -195,6 +337,15 @@ pub struct FilesystemEnumerator {
 
     /// Should git history be scanned at all?
     enumerate_git_history: bool,
+
+    /// Should skipped filesystem entries be reported?
+    report_skipped: bool,
+
+    /// If set, only files matching one of these globs are enumerated
+    include_globs: Option<GlobSet>,
+
+    /// Files and directories matching one of these globs are not enumerated
+    exclude_globs: GlobSet,
 }
 
 impl FilesystemEnumerator {
@@ -202,6 +353,7 @@ impl FilesystemEnumerator {
     pub const DEFAULT_FOLLOW_LINKS: bool = false;
     pub const DEFAULT_COLLECT_GIT_METADATA: bool = true;
     pub const DEFAULT_ENUMERATE_GIT_HISTORY: bool = true;
+    pub const DEFAULT_REPORT_SKIPPED: bool = false;
 
     /// Create a new `FilesystemEnumerator` with the given set of input roots using default
     /// settings.
@@ -220,7 +372,6 @@ impl FilesystemEnumerator {
         }
         let max_file_size = Some(Self::DEFAULT_MAX_FILESIZE);
         builder.follow_links(Self::DEFAULT_FOLLOW_LINKS);
-        builder.max_filesize(max_file_size);
         builder.standard_filters(false);
 
         Ok(FilesystemEnumerator {
@@ -228,7 +379,10 @@ impl FilesystemEnumerator {
             max_file_size,
             collect_git_metadata: Self::DEFAULT_COLLECT_GIT_METADATA,
             enumerate_git_history: Self::DEFAULT_ENUMERATE_GIT_HISTORY,
+            report_skipped: Self::DEFAULT_REPORT_SKIPPED,
             gitignore_builder: GitignoreBuilder::new(""),
+            include_globs: None,
+            exclude_globs: GlobSet::empty(),
         })
     }
 
@@ -240,13 +394,7 @@ impl FilesystemEnumerator {
 
     /// Add a set of gitignore-style rules from the given ignore file.
     pub fn add_ignore<T: AsRef<Path>>(&mut self, path: T) -> Result<&mut Self> {
-        let path = path.as_ref();
-
-        if let Some(e) = self.gitignore_builder.add(path) {
-            Err(e)?;
-        }
-
-        match self.walk_builder.add_ignore(path) {
+        match self.gitignore_builder.add(path.as_ref()) {
             Some(e) => Err(e)?,
             None => Ok(self),
         }
@@ -262,11 +410,45 @@ impl FilesystemEnumerator {
     ///
     /// Files larger than this value will be skipped.
     pub fn max_filesize(&mut self, max_filesize: Option<u64>) -> &mut Self {
-        self.walk_builder.max_filesize(max_filesize);
         self.max_file_size = max_filesize;
         self
     }
 
+    /// Set the maximum depth of recursion into directories.
+    ///
+    /// The input roots are at depth 0, and entries immediately within them are at depth 1.
+    /// `None` means no limit.
+    pub fn max_depth(&mut self, max_depth: Option<usize>) -> &mut Self {
+        self.walk_builder.max_depth(max_depth);
+        self
+    }
+
+    /// Only enumerate files whose paths match at least one of the given globs.
+    ///
+    /// `None` means no restriction. Directories are always traversed.
+    pub fn include_globs(&mut self, include_globs: Option<GlobSet>) -> &mut Self {
+        self.include_globs = include_globs;
+        self
+    }
+
+    /// Skip files and directories whose paths match any of the given globs.
+    ///
+    /// This takes precedence over `include_globs`. The input roots themselves are only skipped if
+    /// they are files.
+    pub fn exclude_globs(&mut self, exclude_globs: GlobSet) -> &mut Self {
+        self.exclude_globs = exclude_globs;
+        self
+    }
+
+    /// Enable or disable whether skipped filesystem entries are reported.
+    ///
+    /// When enabled, each file or directory that is not enumerated because of its size, an ignore
+    /// rule, or some other reason is sent to the output as a `FoundInput::Skipped` value.
+    pub fn report_skipped(&mut self, report_skipped: bool) -> &mut Self {
+        self.report_skipped = report_skipped;
+        self
+    }
+
     /// Enable or disable whether detailed Git metadata will be collected.
     pub fn collect_git_metadata(&mut self, collect_git_metadata: bool) -> &mut Self {
         self.collect_git_metadata = collect_git_metadata;
@@ -297,8 +479,15 @@ impl FilesystemEnumerator {
     }
 
     pub fn run(&self, output: Output) -> Result<()> {
+        // Ignore rules, globs, and the max file size are applied by our visitor rather than by the
+        // `ignore` crate, so that skipped entries can be reported.
+        let gitignore = self.gitignore()?;
         let mut visitor_builder = VisitorBuilder {
             max_file_size: self.max_file_size,
+            gitignore: &gitignore,
+            include_globs: self.include_globs.as_ref(),
+            exclude_globs: &self.exclude_globs,
+            report_skipped: self.report_skipped,
             output: &output,
         };
 
@@ -311,11 +500,48 @@ impl FilesystemEnumerator {
 }
 
 /// Opens the given Git repository if it exists, returning None otherwise.
+///
+/// Objects from the repository's alternate object stores (listed in `objects/info/alternates`)
+/// are read along with its own objects. A warning is emitted for each alternate object store that
+/// does not exist, as any history stored there cannot be scanned.
 pub fn open_git_repo(path: &Path) -> Result<Option<Repository>> {
     let opts = gix::open::Options::isolated().open_path_as_is(true);
     match gix::open_opts(path, opts) {
         Err(gix::open::Error::NotARepository { .. }) => Ok(None),
         Err(err) => Err(err.into()),
-        Ok(repo) => Ok(Some(repo)),
+        Ok(repo) => {
+            warn_missing_alternates(&repo);
+            Ok(Some(repo))
+        }
+    }
+}
+
+/// Emit a warning for each alternate object store of the given repository that does not exist.
+fn warn_missing_alternates(repo: &Repository) {
+    let objects_dir = repo.common_dir().join("objects");
+    let current_dir = match std::env::current_dir() {
+        Ok(current_dir) => current_dir,
+        Err(e) => {
+            debug!("Failed to get current directory; not checking alternates: {e}");
+            return;
+        }
+    };
+    match gix::odb::alternate::resolve(objects_dir, &current_dir) {
+        Ok(alternates) => {
+            for alternate in alternates.iter().filter(|p| !p.is_dir()) {
+                warn!(
+                    "Alternate object store {} for Git repository at {} does not exist; \
+                     objects stored there will not be scanned",
+                    alternate.display(),
+                    repo.path().display(),
+                );
+            }
+        }
+        Err(e) => {
+            warn!(
+                "Failed to resolve alternate object stores for Git repository at {}: {e}",
+                repo.path().display()
+            );
+        }
     }
 }
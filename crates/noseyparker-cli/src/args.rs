@@ -5,15 +5,19 @@
 use clap::{
     crate_description, crate_version, ArgAction, Args, Parser, Subcommand, ValueEnum, ValueHint,
 };
+use input_enumerator::Glob;
 use lazy_static::lazy_static;
 use noseyparker::git_url::GitUrl;
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use strum::Display;
 #[cfg(feature = "github")]
 use url::Url;
 
-use crate::util::get_writer_for_file_or_stdout;
+use crate::util::{
+    get_compressed_writer_for_file_or_stdout, get_line_writer_for_file_or_stdout,
+    get_writer_for_file_or_stdout, OutputWriter,
+};
 
 // -----------------------------------------------------------------------------
 // system information
@@ -263,7 +267,9 @@ pub enum Command {
     ///
     /// - Rejected: the number of findings whose matches have `reject` status
     ///
-    /// - Mixed: the number of findings whose matches have a mix of `accept` and `reject` status
+    /// - Needs Review: the number of findings whose matches have `needs_review` status
+    ///
+    /// - Mixed: the number of findings whose matches have a mix of statuses
     ///
     /// - Unlabeled: the number of findings whose matches have no status at all
     #[command(display_order = 2, alias = "summarise")]
@@ -579,36 +585,113 @@ pub enum DatastoreCommand {
     /// Initialize a new datastore
     Init(DatastoreInitArgs),
 
+    /// List the named workspace datastores
+    List(DatastoreListArgs),
+
+    /// Show summary statistics about a datastore
+    #[command(alias = "status")]
+    Info(DatastoreInfoArgs),
+
     /// Export a datastore
     Export(DatastoreExportArgs),
+
+    /// Import a datastore bundle exported in the `jsonl` format
+    ///
+    /// The content of the bundle is merged with any existing content in the datastore.
+    /// The datastore is created if it does not already exist.
+    Import(DatastoreImportArgs),
+
+    /// Merge the contents of other datastores into a datastore
+    ///
+    /// This copies the rules, blobs, provenance, matches, and annotations of each source datastore
+    /// into the destination datastore, without duplicating matches that are already present.
+    /// This is useful for combining the results of scans that were split across several machines.
+    /// The destination datastore is created if it does not already exist.
+    Merge(DatastoreMergeArgs),
+
+    /// List the distinct provenance entries of scanned content in a datastore
+    ///
+    /// Each entry describes an input where scanned content was found, such as a file or a Git repository.
+    Provenance(DatastoreProvenanceArgs),
+
+    /// Recompute the analysis of matches in a datastore without rescanning
+    ///
+    /// This redoes the analysis that is otherwise performed at the end of a scan, such as
+    /// determining which matches are redundant. It can be run any number of times.
+    Analyze(DatastoreAnalyzeArgs),
+
+    /// Reclaim unused space in a datastore
+    ///
+    /// This rebuilds the datastore's database, freeing the space left by deleted or replaced data.
+    /// This can be useful for long-lived datastores that are repeatedly rescanned.
+    Compact(DatastoreCompactArgs),
 }
 
 #[derive(Args, Debug)]
+#[command(mut_arg("datastore", |a| a.help("Initialize the datastore at specified path")))]
 pub struct DatastoreInitArgs {
-    /// Initialize the datastore at specified path
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreListArgs {
+    #[command(flatten)]
+    pub output_args: OutputArgs<DatastoreListOutputFormat>,
+}
+
+// -----------------------------------------------------------------------------
+// datastore list output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DatastoreListOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreAnalyzeArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreCompactArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
 }
 
 #[derive(Args, Debug)]
+pub struct DatastoreInfoArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs<DatastoreInfoOutputFormat>,
+}
+
+// -----------------------------------------------------------------------------
+// datastore info output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DatastoreInfoOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    Json,
+}
+
+#[derive(Args, Debug)]
+#[command(mut_arg("datastore", |a| a.help("Datastore to export")))]
 pub struct DatastoreExportArgs {
-    /// Datastore to export
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
 
     /// Write output to the specified path
     #[arg(long, short, value_name = "PATH", value_hint = ValueHint::FilePath)]
@@ -627,31 +710,100 @@ pub struct DatastoreExportArgs {
 pub enum DatastoreExportOutputFormat {
     /// gzipped tarball
     Tgz,
+
+    /// JSON Lines bundle
+    ///
+    /// This is a portable representation of the rules, blob metadata, provenance, matches, and
+    /// annotations in the datastore, which can be read with `datastore import`.
+    Jsonl,
 }
 
-// -----------------------------------------------------------------------------
-// `scan` command
-// -----------------------------------------------------------------------------
-/// Arguments for the `scan` command
 #[derive(Args, Debug)]
-pub struct ScanArgs {
-    /// Use the specified datastore
+#[command(mut_arg("datastore", |a| a.help("Import into the specified datastore")))]
+pub struct DatastoreImportArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    /// Read the bundle from the specified path
     ///
-    /// The datastore will be created if it does not exist.
+    /// If this argument is not provided, stdin will be used.
     #[arg(
         long,
         short,
         value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
+        value_hint = ValueHint::FilePath,
     )]
-    pub datastore: PathBuf,
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+#[command(mut_arg("datastore", |a| a.help("Merge into the specified datastore")))]
+pub struct DatastoreMergeArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    /// Merge the datastores at the specified paths
+    #[arg(required = true, value_name = "SRC", value_hint = ValueHint::DirPath)]
+    pub sources: Vec<PathBuf>,
+
+    /// Handle annotations that conflict with existing ones using the specified policy
+    ///
+    /// A conflict occurs when a match or finding already has a status or comment that is different
+    /// from the merged one.
+    #[arg(long, value_name = "POLICY", default_value_t = AnnotationConflictPolicy::PreferExisting)]
+    pub on_conflict: AnnotationConflictPolicy,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreProvenanceArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs<DatastoreProvenanceOutputFormat>,
+}
+
+// -----------------------------------------------------------------------------
+// datastore provenance output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DatastoreProvenanceOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    Json,
+
+    /// JSON Lines format
+    ///
+    /// This is a sequence of JSON objects, one per line.
+    Jsonl,
+}
+
+// -----------------------------------------------------------------------------
+// `scan` command
+// -----------------------------------------------------------------------------
+/// Arguments for the `scan` command
+#[derive(Args, Debug)]
+#[command(mut_arg("datastore", |a| a.long_help(
+    "Use the specified datastore\n\nThe datastore will be created if it does not exist."
+)))]
+pub struct ScanArgs {
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
 
     /// Use N parallel scanning threads
     #[arg(long("jobs"), short('j'), value_name="N", default_value_t=default_scan_jobs())]
     pub num_jobs: usize,
 
+    /// Profile rules during scanning and print the slowest rules at the end
+    ///
+    /// This records how many times each rule matches and how much time is spent evaluating it.
+    /// This is useful for finding slow rules, e.g., ones prone to catastrophic backtracking.
+    #[arg(long)]
+    pub rule_profile: bool,
+
     #[command(flatten)]
     pub rules: RuleSpecifierArgs,
 
@@ -676,6 +828,29 @@ pub struct ScanArgs {
     )]
     pub snippet_length: usize,
 
+    /// Extend snippets to cover the entire `KEY = value` assignments that contain matches
+    ///
+    /// This is useful for secrets in config files, where the name of the key is often the most
+    /// informative context for a match.
+    /// Assignments are detected heuristically, for the syntax of common config file formats.
+    /// Snippets are only extended, never shortened, so this is best combined with a small
+    /// `--snippet-length`.
+    #[arg(long, help_heading = "Data Collection Options")]
+    pub snippet_assignment: bool,
+
+    /// Use the specified digest algorithm to compute match and finding IDs
+    ///
+    /// SHA-1 gives the same IDs as earlier versions of Nosey Parker.
+    /// The other algorithms give IDs that are prefixed by the algorithm name, like `sha256:...`.
+    /// All the matches in a datastore must have IDs from the same algorithm.
+    #[arg(
+        long,
+        value_name = "ALGO",
+        default_value_t = IdDigest::Sha1,
+        help_heading = "Data Collection Options"
+    )]
+    pub structural_id_algo: IdDigest,
+
     /// Specify which blobs will be copied in entirety to the datastore
     ///
     /// If this option is enabled, corresponding blobs will be written to the `blobs` directory within the datastore.
@@ -695,6 +870,46 @@ pub struct ScanArgs {
     /// Specify the format for blobs copied by the `--copy-blobs` option
     #[arg(long, value_name="FORMAT", default_value_t=DEFAULT_COPY_BLOBS_FORMAT)]
     pub copy_blobs_format: CopyBlobsFormat,
+
+    /// Stop scanning once the datastore contains at least N matches
+    ///
+    /// This guards against a misbehaving rule filling the disk with junk matches.
+    /// Matches recorded before the limit is reached are kept, and the datastore remains usable.
+    /// Matches are recorded in batches, so somewhat more than N matches may be recorded.
+    #[arg(long, value_name = "N", help_heading = "Data Collection Options")]
+    pub max_total_matches: Option<u64>,
+
+    /// Write a manifest of the scan to the specified file
+    ///
+    /// The manifest is a JSON Lines file that records what the scan did with each input:
+    /// each blob that was scanned, with its ID, size, provenance, and number of matches;
+    /// each blob that was not scanned again because identical content had already been seen;
+    /// each filesystem entry that was skipped, along with the reason; and each input that could
+    /// not be enumerated, loaded, or scanned, along with the error.
+    ///
+    /// Failing to write the manifest does not stop the scan, but leaves the manifest incomplete.
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help_heading = "Data Collection Options"
+    )]
+    pub manifest: Option<PathBuf>,
+
+    /// Write statistics about the scan to the specified file
+    ///
+    /// The statistics are a JSON object that gives the number of blobs and bytes scanned, the
+    /// number of blobs skipped for exceeding the maximum blob size, the number of new matches, the
+    /// total numbers of matches and findings in the datastore, and the elapsed time of the scan in
+    /// seconds. It also includes the statistics shown by `datastore info` for the datastore after
+    /// the scan.
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        help_heading = "Data Collection Options"
+    )]
+    pub stats_json: Option<PathBuf>,
 }
 
 #[derive(Args, Debug)]
@@ -727,6 +942,15 @@ pub struct RuleSpecifierArgs {
     /// Control whether built-in rules and rulesets are loaded.
     #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
     pub load_builtins: bool,
+
+    /// Do not normalize the IDs of loaded rules
+    ///
+    /// By default, rule IDs are normalized when loaded: surrounding whitespace is removed, letters
+    /// are lowercased, and internal whitespace is replaced with hyphens.
+    /// Rule IDs referenced by rulesets are normalized the same way.
+    /// A warning is emitted for each rule whose ID is changed.
+    #[arg(long)]
+    pub no_normalize_rule_ids: bool,
 }
 
 /// The mode to use for cloning a Git repository
@@ -795,6 +1019,14 @@ pub struct MetadataArgs {
     /// problematic Git repository input.
     #[arg(long, default_value_t=GitBlobProvenanceMode::FirstSeen, value_name="MODE")]
     pub git_blob_provenance: GitBlobProvenanceMode,
+
+    /// Tag all blobs scanned in this invocation with the given input group name
+    ///
+    /// The input group is recorded as an additional provenance entry for each blob. This makes it
+    /// possible to scan several inputs into the same datastore and later restrict a report to one
+    /// of them using `report --input-group`.
+    #[arg(long, value_name = "NAME")]
+    pub input_group: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -823,6 +1055,31 @@ pub enum CopyBlobsMode {
     None,
 }
 
+/// The digest algorithm used to compute match and finding IDs
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum IdDigest {
+    /// SHA-1
+    Sha1,
+
+    /// SHA-256
+    Sha256,
+
+    /// BLAKE3
+    Blake3,
+}
+
+impl From<IdDigest> for noseyparker::structural_id::StructuralIdAlgo {
+    fn from(val: IdDigest) -> Self {
+        use noseyparker::structural_id::StructuralIdAlgo;
+        match val {
+            IdDigest::Sha1 => StructuralIdAlgo::Sha1,
+            IdDigest::Sha256 => StructuralIdAlgo::Sha256,
+            IdDigest::Blake3 => StructuralIdAlgo::Blake3,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 #[strum(serialize_all = "kebab-case")]
 pub enum CopyBlobsFormat {
@@ -846,6 +1103,9 @@ pub enum GitBlobProvenanceMode {
     /// seen
     FirstSeen,
 
+    /// Like `first-seen`, plus the most recent branch tip commit in which a blob is still present
+    FirstAndLatest,
+
     /// Only the Git repository in which a blob is seen
     Minimal,
 }
@@ -855,6 +1115,8 @@ pub enum GitBlobProvenanceMode {
 pub struct InputSpecifierArgs {
     #[cfg(feature = "github")]
     /// Scan the specified file, directory, or local Git repository
+    ///
+    /// The special input `-` scans content read from stdin as a single blob.
     #[arg(
         value_name="INPUT",
         value_hint=ValueHint::AnyPath,
@@ -871,6 +1133,8 @@ pub struct InputSpecifierArgs {
 
     #[cfg(not(feature = "github"))]
     /// Scan the specified file, directory, or local Git repository
+    ///
+    /// The special input `-` scans content read from stdin as a single blob.
     #[arg(
         value_name="INPUT",
         value_hint=ValueHint::AnyPath,
@@ -997,6 +1261,47 @@ pub struct ContentFilteringArgs {
     )]
     pub max_file_size_mb: f64,
 
+    /// Do not scan blobs larger than the specified size
+    ///
+    /// Unlike `--max-file-size`, this applies to blobs from all inputs, including those found in Git history.
+    /// The size of a Git blob is checked before its content is read.
+    /// The value is parsed as a floating point literal, and hence fractional values can be supplied.
+    /// A non-positive value means "no limit".
+    #[arg(
+        long("max-blob-size"),
+        default_value_t = 100.0,
+        value_name = "MEGABYTES",
+        allow_negative_numbers = true
+    )]
+    pub max_blob_size_mb: f64,
+
+    /// Do not descend more than N levels of directories below the input paths
+    ///
+    /// Entries immediately within an input directory are at depth 1.
+    /// A value of 0 means that only the input paths themselves are scanned.
+    /// This does not limit enumeration of Git repository history.
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Memory-map files at least the specified size instead of reading them into memory
+    ///
+    /// The value is parsed as a floating point literal, and hence fractional values can be supplied.
+    /// This reduces peak memory use when scanning very large files.
+    /// If a file cannot be memory-mapped, it is read into memory instead.
+    /// Files whose size changes while they are being loaded are skipped.
+    #[arg(long("mmap-min-size"), value_name = "MEGABYTES")]
+    pub mmap_min_size_mb: Option<f64>,
+
+    /// Scan files larger than the specified size in overlapping chunks
+    ///
+    /// The value is parsed as a floating point literal, and hence fractional values can be supplied.
+    /// Chunks are split at line boundaries where possible and overlap by at least 64KiB, so matches
+    /// spanning a chunk boundary are still found.
+    /// This bounds the cost of scanning pathologically large files.
+    /// The chunk size must be larger than the overlap.
+    #[arg(long("chunk-size"), value_name = "MEGABYTES")]
+    pub chunk_size_mb: Option<f64>,
+
     /// Use custom path-based ignore rules from the specified file
     ///
     /// The ignore file should contain gitignore-style rules.
@@ -1004,6 +1309,46 @@ pub struct ContentFilteringArgs {
     /// This option can be repeated.
     #[arg(long, short, value_name = "FILE", value_hint = ValueHint::FilePath)]
     pub ignore: Vec<PathBuf>,
+
+    /// Only scan files whose paths match the specified glob
+    ///
+    /// Globs are matched against the entire path of each file found in the filesystem inputs, so `*.py` matches files in any directory.
+    /// Use `**` to match any number of path components, e.g., `**/src/**`.
+    /// Directories are always traversed, and blobs found in Git history are not filtered.
+    ///
+    /// This option can be repeated; files that match any of the globs are scanned.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<Glob>,
+
+    /// Skip files and directories whose paths match the specified glob
+    ///
+    /// Globs are matched the same way as with `--include`.
+    /// Excluding a directory skips everything within it.
+    /// This takes precedence over `--include`.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<Glob>,
+
+    /// Write a coverage report of skipped filesystem inputs to the specified file
+    ///
+    /// The report is a JSON document that gives the number of files scanned and lists each file or directory that was not scanned, along with the reason:
+    /// exceeding the maximum file size, matching an ignore rule, being excluded by `--include` or `--exclude` globs, looking like a Nosey Parker datastore, being a symbolic link, or failing to be read.
+    /// A table summarizing the skip reasons is also printed after scanning.
+    ///
+    /// Only filesystem inputs are covered; ignore rules applied to paths within Git history are not reported.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub coverage_report: Option<PathBuf>,
+
+    /// Also match rules against the paths of scanned files
+    ///
+    /// Only rules that set `match_filenames: true` are matched against paths.
+    /// This finds files that are sensitive by name alone, such as private keys, even when their
+    /// content is not matched.
+    /// Matches found in paths are recorded with extended provenance that notes the path and that
+    /// it was a filename match.
+    #[arg(long)]
+    pub scan_filenames: bool,
     /*
     /// Do not scan files that appear to be binary
     #[arg(long)]
@@ -1019,6 +1364,24 @@ impl ContentFilteringArgs {
             Some((self.max_file_size_mb * 1024.0 * 1024.0) as u64)
         }
     }
+
+    pub fn max_blob_size_bytes(&self) -> Option<u64> {
+        if self.max_blob_size_mb <= 0.0 {
+            None
+        } else {
+            Some((self.max_blob_size_mb * 1024.0 * 1024.0) as u64)
+        }
+    }
+
+    pub fn chunk_size_bytes(&self) -> Option<usize> {
+        self.chunk_size_mb
+            .map(|mb| (mb.max(0.0) * 1024.0 * 1024.0) as usize)
+    }
+
+    pub fn mmap_min_size_bytes(&self) -> Option<u64> {
+        self.mmap_min_size_mb
+            .map(|mb| (mb.max(0.0) * 1024.0 * 1024.0) as u64)
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -1026,36 +1389,96 @@ impl ContentFilteringArgs {
 // -----------------------------------------------------------------------------
 #[derive(Args, Debug)]
 pub struct SummarizeArgs {
-    /// Use the specified datastore
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    /// Sort rules by KEY
+    ///
+    /// Rule names are sorted in ascending order, and counts in descending order.
+    /// Rules with equal counts are sorted by name.
+    #[arg(long, value_name = "KEY", default_value_t = SummarizeSortKey::Total)]
+    pub sort_by: SummarizeSortKey,
+
+    /// Show only the N rules with the most matches
+    ///
+    /// The counts for the remaining rules are combined into a final row labeled "Other rules".
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
 
     #[command(flatten)]
     pub output_args: OutputArgs<SummarizeOutputFormat>,
 }
 
+/// The key to sort rules by in a summary
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SummarizeSortKey {
+    /// The name of the rule
+    Rule,
+
+    /// The number of findings
+    Findings,
+
+    /// The number of matches
+    Total,
+
+    /// The number of findings without a status
+    Unlabeled,
+}
+
 // -----------------------------------------------------------------------------
 // `report` command
 // -----------------------------------------------------------------------------
 #[derive(Args, Debug)]
 pub struct ReportArgs {
-    /// Use the specified datastore
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
+
+    /// Show scores in the `human` format with N digits after the decimal point
+    #[arg(long, default_value_t = 3, value_name = "N")]
+    pub score_precision: usize,
+
+    /// Show the pattern of the rule that produced each finding
+    ///
+    /// In the `human` and `diff` formats, the pattern is shown after the rule name.
+    /// In the `json` and `jsonl` formats, the pattern is included in a `rule_pattern` field.
+    #[arg(long)]
+    pub show_pattern: bool,
+
+    /// Mask the matched content of each finding
+    ///
+    /// The matching portion of each snippet and the content of each capture group are replaced by
+    /// `*` characters, except for the first and last 4 characters of values that are at least 12
+    /// characters long.
+    /// The context before and after each match is trimmed to the line that contains the match, and
+    /// other occurrences of the matched content on that line are masked too.
+    /// This applies to all output formats.
+    ///
+    /// Finding IDs are still included.
+    /// They are unsalted hashes (SHA-1 by default) of the rule and the matched content, and so can
+    /// be used to check whether a guessed value is the secret.
+    #[arg(long)]
+    pub redact: bool,
+
+    /// Collapse findings with identical capture groups across different rules
+    ///
+    /// When several rules match the same secret, each rule produces its own finding.
+    /// With this option, such findings are reported together as a single secret, listing the rules
+    /// that matched it, with the details of each rule's finding nested within.
+    ///
+    /// This is supported with the `human`, `json`, `jsonl`, and `paths` formats.
+    #[arg(long)]
+    pub collapse_by_secret: bool,
+
+    /// Compare findings against the datastore at PATH
+    ///
+    /// This is required by the `diff` format, which shows the findings added and removed
+    /// relative to the baseline datastore, applying the same filtering options to both datastores.
+    /// With other formats, only findings that are not in the baseline datastore are reported,
+    /// e.g., to show just the secrets introduced since an earlier scan.
+    /// Findings are compared using their finding IDs.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath)]
+    pub baseline: Option<PathBuf>,
 
     #[command(flatten)]
     pub filter_args: ReportFilterArgs,
@@ -1089,8 +1512,9 @@ pub struct ReportFilterArgs {
     )]
     pub max_provenance: i64,
 
-    /// Only report findings that have a mean score of at least N
+    /// Only report findings that have a mean score of at least SCORE
     ///
+    /// The mean score of a finding is the mean of the scores of those of its matches that have one.
     /// Scores are floating point numbers in the range [0, 1].
     /// Use the value `0` to disable this filtering.
     ///
@@ -1102,12 +1526,56 @@ pub struct ReportFilterArgs {
     #[arg(long, value_name = "STATUS")]
     pub finding_status: Option<FindingStatus>,
 
+    /// Include only findings from rules whose ID or name matches PATTERN
+    ///
+    /// A pattern that contains `*`, `?`, or `[` is matched as a glob against the entire rule ID or
+    /// name; any other pattern matches if it occurs anywhere in the rule ID or name.
+    /// Matching is case-insensitive.
+    ///
+    /// This option can be repeated; findings from rules that match any of the patterns are
+    /// included.
+    #[arg(long = "rule", value_name = "PATTERN")]
+    pub rules: Vec<String>,
+
     /// Suppress redundant matches and findings
     ///
     /// A match is considered redundant to another if they overlap significantly within the same
     /// blob and satisfy a handful of heuristics.
     #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
     pub suppress_redundant: bool,
+
+    /// Suppress matches that occur within code comments
+    ///
+    /// Whether a match occurs within a comment is determined heuristically when scanning, based
+    /// on the file extension and the text preceding the match on its line.
+    /// Findings whose matches all occur within comments are suppressed entirely.
+    #[arg(long)]
+    pub exclude_comments: bool,
+
+    /// Only report matches from blobs scanned with the given input group
+    ///
+    /// Input groups are assigned when scanning using the `--input-group` option.
+    #[arg(long, value_name = "NAME")]
+    pub input_group: Option<String>,
+
+    /// Suppress the findings and matches whose IDs are listed in FILE
+    ///
+    /// The file should contain one finding ID or match structural ID per line.
+    /// Blank lines and lines starting with `#` are ignored.
+    /// A listed finding is suppressed entirely, and a listed match is omitted from its finding;
+    /// findings whose matches are all listed are suppressed as well.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub ignore: Vec<PathBuf>,
+
+    /// Suppress findings with the `accept` status
+    ///
+    /// These are the findings that `--finding-status=accept` selects.
+    /// This allows findings that have been reviewed and accepted, e.g., secrets in test fixtures,
+    /// to be left out of reports using annotations rather than `--ignore` files.
+    #[arg(long)]
+    pub ignore_accepted: bool,
 }
 
 #[derive(ValueEnum, Debug, Display, Clone, Copy)]
@@ -1118,10 +1586,17 @@ pub enum FindingStatus {
     Accept,
     /// Findings with `reject` matches
     Reject,
-    /// Findings with both `accept` and `reject` matches
+    /// Findings with `needs_review` matches
+    #[value(name = "needs-review")]
+    #[strum(serialize = "needs-review")]
+    NeedsReview,
+    /// Findings with matches of more than one status
     Mixed,
-    /// Findings without any `accept` or `reject` matches
+    /// Findings without any matches with a status
+    #[value(alias = "unlabeled")]
     Null,
+    /// Findings with `reject` matches that also have new matches without a status
+    Regression,
 }
 
 // -----------------------------------------------------------------------------
@@ -1144,16 +1619,8 @@ pub enum AnnotationsCommand {
 
 #[derive(Args, Debug)]
 pub struct AnnotationsExportArgs {
-    /// Use the specified datastore
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
 
     /// Write annotations to the specified path
     ///
@@ -1169,16 +1636,8 @@ pub struct AnnotationsExportArgs {
 
 #[derive(Args, Debug)]
 pub struct AnnotationsImportArgs {
-    /// Use the specified datastore
-    #[arg(
-        long,
-        short,
-        value_name = "PATH",
-        value_hint = ValueHint::DirPath,
-        env("NP_DATASTORE"),
-        default_value=DEFAULT_DATASTORE,
-    )]
-    pub datastore: PathBuf,
+    #[command(flatten)]
+    pub datastore_args: DatastoreSpecifierArgs,
 
     /// Read annotations from the specified path
     ///
@@ -1190,6 +1649,40 @@ pub struct AnnotationsImportArgs {
         value_hint = ValueHint::FilePath,
     )]
     pub input: Option<PathBuf>,
+
+    /// Handle annotations that conflict with existing ones using the specified policy
+    ///
+    /// A conflict occurs when a match or finding already has a status or comment that is different
+    /// from the imported one.
+    #[arg(long, value_name = "POLICY", default_value_t = AnnotationConflictPolicy::PreferExisting)]
+    pub on_conflict: AnnotationConflictPolicy,
+}
+
+/// The method of handling conflicting annotations when importing
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum AnnotationConflictPolicy {
+    /// Keep the existing value of each conflicting status or comment
+    ///
+    /// Other values of the same annotation are still imported.
+    PreferExisting,
+
+    /// Skip annotations that have any conflicting status or comment entirely
+    Skip,
+
+    /// Replace the existing value of each conflicting status or comment with the imported one
+    Overwrite,
+}
+
+impl From<AnnotationConflictPolicy> for noseyparker::datastore::ImportPolicy {
+    fn from(val: AnnotationConflictPolicy) -> Self {
+        use noseyparker::datastore::ImportPolicy;
+        match val {
+            AnnotationConflictPolicy::PreferExisting => ImportPolicy::PreferExisting,
+            AnnotationConflictPolicy::Skip => ImportPolicy::Skip,
+            AnnotationConflictPolicy::Overwrite => ImportPolicy::Overwrite,
+        }
+    }
 }
 
 // -----------------------------------------------------------------------------
@@ -1256,6 +1749,39 @@ pub struct ManPagesArgs {
     pub output: PathBuf,
 }
 
+// -----------------------------------------------------------------------------
+// datastore options
+// -----------------------------------------------------------------------------
+#[derive(Args, Debug)]
+pub struct DatastoreSpecifierArgs {
+    /// Use the specified datastore
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
+
+    /// Use the named workspace instead of a datastore path
+    ///
+    /// Workspaces are datastores kept under a common base directory:
+    /// `$XDG_DATA_HOME/noseyparker`, or `~/.local/share/noseyparker` if `XDG_DATA_HOME` is not set.
+    /// This cannot be used with `--datastore`, including through the `NP_DATASTORE` variable.
+    /// Use `datastore list` to show the existing workspaces.
+    #[arg(long, value_name = "NAME", conflicts_with = "datastore")]
+    pub workspace: Option<String>,
+}
+
+impl DatastoreSpecifierArgs {
+    /// Get the path of the specified datastore, resolving the workspace name if one was given.
+    pub fn resolve(&self) -> anyhow::Result<PathBuf> {
+        crate::workspace::resolve_datastore(&self.datastore, self.workspace.as_deref())
+    }
+}
+
 // -----------------------------------------------------------------------------
 // output options
 // -----------------------------------------------------------------------------
@@ -1272,12 +1798,82 @@ pub struct OutputArgs<Format: ValueEnum + Send + Sync + 'static> {
     // FIXME: make this optional, and if not specified, infer from the extension of the output file
     #[arg(long, short, value_name = "FORMAT", default_value = "human")]
     pub format: Format,
+
+    /// Flush output after each record
+    ///
+    /// By default, output written to a file is block-buffered, and JSON Lines output written to
+    /// stdout is line-buffered. This option makes output line-buffered regardless of the format or
+    /// destination, which is useful when the output is consumed in real time.
+    #[arg(long)]
+    pub flush_each: bool,
+
+    /// Compress output using the specified format
+    ///
+    /// If this argument is not provided, the compression format is inferred from the extension of
+    /// the output path: `.gz` for gzip and `.zst` for Zstandard. Otherwise, output is not
+    /// compressed.
+    #[arg(long, value_name = "FORMAT")]
+    pub compress: Option<OutputCompression>,
 }
 
 impl<Format: ValueEnum + Send + Sync> OutputArgs<Format> {
     /// Get a writer for the specified output destination.
-    pub fn get_writer(&self) -> std::io::Result<Box<dyn std::io::Write>> {
-        get_writer_for_file_or_stdout(self.output.as_ref())
+    ///
+    /// The writer is line-buffered if `--flush-each` was given or if uncompressed JSON Lines output
+    /// is being written to stdout; otherwise, it is block-buffered. Its `finish` method must be
+    /// called once all output has been written.
+    pub fn get_writer(&self) -> std::io::Result<OutputWriter> {
+        match self.compression() {
+            Some(compression) => get_compressed_writer_for_file_or_stdout(
+                self.output.as_ref(),
+                compression,
+                self.flush_each,
+            ),
+            None if self.flush_each || (self.output.is_none() && self.is_jsonl()) => {
+                get_line_writer_for_file_or_stdout(self.output.as_ref()).map(OutputWriter::from)
+            }
+            None => get_writer_for_file_or_stdout(self.output.as_ref()).map(OutputWriter::from),
+        }
+    }
+
+    /// Get the compression format to use, either as specified or as inferred from the output path.
+    pub fn compression(&self) -> Option<OutputCompression> {
+        self.compress.or_else(|| {
+            self.output
+                .as_deref()
+                .and_then(OutputCompression::from_path)
+        })
+    }
+
+    /// Is the output format JSON Lines?
+    fn is_jsonl(&self) -> bool {
+        self.format
+            .to_possible_value()
+            .is_some_and(|v| v.get_name() == "jsonl")
+    }
+}
+
+// -----------------------------------------------------------------------------
+// output compression format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum OutputCompression {
+    /// gzip compression
+    Gzip,
+
+    /// Zstandard compression
+    Zstd,
+}
+
+impl OutputCompression {
+    /// Infer the compression format from the extension of the given path, if possible.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
     }
 }
 
@@ -1296,6 +1892,9 @@ pub enum ReportOutputFormat {
     /// JSON Lines format
     ///
     /// This is a sequence of JSON objects, one per line.
+    /// Each line is a complete finding, with all of its reported matches nested in its `matches`
+    /// field, so the grouping of matches into findings is preserved without needing boundary
+    /// records.
     Jsonl,
 
     /// SARIF format (experimental)
@@ -1306,6 +1905,24 @@ pub enum ReportOutputFormat {
     /// Support for SARIF output is experimental.
     /// If you run into problems when using this, please create an issue in the GitHub project: <https://github.com/praetorian-inc/noseyparker>.
     Sarif,
+
+    /// Paths of files with findings, one per line
+    ///
+    /// Each distinct path is listed once, in sorted order.
+    /// All matches of each finding are considered, regardless of `--max-matches` and `--max-provenance`.
+    Paths,
+
+    /// Paths of files with findings, separated by NUL bytes
+    ///
+    /// This is like the `paths` format, but is suitable for use with `xargs -0`.
+    Paths0,
+
+    /// A patch-like comparison against the datastore given with `--baseline`
+    ///
+    /// Findings that are only in the reported datastore are shown with a `+` marker, and findings
+    /// that are only in the baseline datastore are shown with a `-` marker, grouped by rule.
+    /// The numbers of added and removed findings are shown first.
+    Diff,
 }
 
 // -----------------------------------------------------------------------------
@@ -1324,6 +1941,11 @@ pub enum SummarizeOutputFormat {
     ///
     /// This is a sequence of JSON objects, one per line.
     Jsonl,
+
+    /// CSV format
+    ///
+    /// This is a header row followed by one row per rule.
+    Csv,
 }
 
 #[cfg(feature = "github")]
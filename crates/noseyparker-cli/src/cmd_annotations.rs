@@ -17,8 +17,9 @@ pub fn run(global_args: &GlobalArgs, args: &AnnotationsArgs) -> Result<()> {
 }
 
 fn cmd_annotations_import(global_args: &GlobalArgs, args: &AnnotationsImportArgs) -> Result<()> {
-    let mut datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
-        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+    let datastore_path = args.datastore_args.resolve()?;
+    let mut datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
 
     let input = get_reader_for_file_or_stdin(args.input.as_ref())?;
 
@@ -29,23 +30,22 @@ fn cmd_annotations_import(global_args: &GlobalArgs, args: &AnnotationsImportArgs
         annotations.match_annotations.len(),
         annotations.finding_annotations.len()
     );
-    datastore.import_annotations(&annotations)?;
+    datastore.import_annotations(&annotations, args.on_conflict.into())?;
 
     Ok(())
 }
 
 fn cmd_annotations_export(global_args: &GlobalArgs, args: &AnnotationsExportArgs) -> Result<()> {
-    let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
-        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
 
     let output = get_writer_for_file_or_stdout(args.output.as_ref())
         .context("Failed to open output for writing")?;
 
-    let annotations = datastore
-        .get_annotations()
-        .context("Failed to get annotations")?;
-
-    serde_json::to_writer(output, &annotations).context("Failed to write JSON output")?;
+    datastore
+        .export_annotations(output)
+        .context("Failed to write annotations")?;
 
     Ok(())
 }
@@ -1,26 +1,138 @@
 use anyhow::{Context, Result};
+use indicatif::{HumanBytes, HumanCount};
+use std::io::Write;
 use tracing::info;
 
-use crate::args::{DatastoreArgs, DatastoreExportArgs, DatastoreInitArgs, GlobalArgs};
-use noseyparker::datastore::Datastore;
+use crate::args::{
+    DatastoreAnalyzeArgs, DatastoreArgs, DatastoreCompactArgs, DatastoreExportArgs,
+    DatastoreImportArgs, DatastoreInfoArgs, DatastoreInfoOutputFormat, DatastoreInitArgs,
+    DatastoreListArgs, DatastoreListOutputFormat, DatastoreMergeArgs, DatastoreProvenanceArgs,
+    DatastoreProvenanceOutputFormat, GlobalArgs,
+};
+use crate::reportable::Reportable;
+use crate::util::get_reader_for_file_or_stdin;
+use crate::workspace::{list_workspaces, Workspace};
+use noseyparker::datastore::{Datastore, DatastoreStats};
 
 pub fn run(global_args: &GlobalArgs, args: &DatastoreArgs) -> Result<()> {
     use crate::args::DatastoreCommand::*;
     match &args.command {
         Init(args) => cmd_datastore_init(global_args, args),
+        List(args) => cmd_datastore_list(global_args, args),
+        Info(args) => cmd_datastore_info(global_args, args),
         Export(args) => cmd_datastore_export(global_args, args),
+        Import(args) => cmd_datastore_import(global_args, args),
+        Merge(args) => cmd_datastore_merge(global_args, args),
+        Provenance(args) => cmd_datastore_provenance(global_args, args),
+        Analyze(args) => cmd_datastore_analyze(global_args, args),
+        Compact(args) => cmd_datastore_compact(global_args, args),
     }
 }
 
 fn cmd_datastore_init(global_args: &GlobalArgs, args: &DatastoreInitArgs) -> Result<()> {
-    let datastore = Datastore::create(&args.datastore, global_args.advanced.sqlite_cache_size)?;
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::create(&datastore_path, global_args.advanced.sqlite_cache_size)?;
     info!("Initialized new datastore at {}", &datastore.root_dir().display());
     Ok(())
 }
 
+struct DatastoreListReporter {
+    workspaces: Vec<Workspace>,
+}
+
+impl Reportable for DatastoreListReporter {
+    type Format = DatastoreListOutputFormat;
+
+    fn report<W: std::io::Write>(&self, format: Self::Format, mut writer: W) -> Result<()> {
+        match format {
+            DatastoreListOutputFormat::Human => {
+                let width = self
+                    .workspaces
+                    .iter()
+                    .map(|w| w.name.len())
+                    .max()
+                    .unwrap_or(0);
+                for w in self.workspaces.iter() {
+                    writeln!(writer, "{:<width$}  {}", w.name, w.path.display())?;
+                }
+            }
+            DatastoreListOutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &self.workspaces)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn cmd_datastore_list(_global_args: &GlobalArgs, args: &DatastoreListArgs) -> Result<()> {
+    let mut output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+    let workspaces = list_workspaces().context("Failed to list workspaces")?;
+    DatastoreListReporter { workspaces }.report(args.output_args.format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
+}
+
+struct DatastoreInfoReporter {
+    root_dir: std::path::PathBuf,
+    stats: DatastoreStats,
+}
+
+impl Reportable for DatastoreInfoReporter {
+    type Format = DatastoreInfoOutputFormat;
+
+    fn report<W: std::io::Write>(&self, format: Self::Format, mut writer: W) -> Result<()> {
+        match format {
+            DatastoreInfoOutputFormat::Human => {
+                let s = &self.stats;
+                let rows = [
+                    ("Datastore:", self.root_dir.display().to_string()),
+                    ("Schema version:", s.schema_version.to_string()),
+                    ("Findings:", HumanCount(s.num_findings).to_string()),
+                    ("Matches:", HumanCount(s.num_matches).to_string()),
+                    ("Rules with findings:", HumanCount(s.distinct_rules).to_string()),
+                    ("Provenance entries:", HumanCount(s.num_provenance).to_string()),
+                    ("Annotations:", HumanCount(s.num_annotations).to_string()),
+                    ("Database size:", HumanBytes(s.database_size).to_string()),
+                ];
+                for (label, value) in rows {
+                    writeln!(writer, "{label:<21}{value}")?;
+                }
+            }
+            DatastoreInfoOutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, &self.stats)?;
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn cmd_datastore_info(global_args: &GlobalArgs, args: &DatastoreInfoArgs) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+    let mut output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+    let stats = datastore
+        .stats()
+        .context("Failed to get datastore statistics")?;
+    DatastoreInfoReporter {
+        root_dir: datastore.root_dir().to_owned(),
+        stats,
+    }
+    .report(args.output_args.format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
+}
+
 fn cmd_datastore_export(global_args: &GlobalArgs, args: &DatastoreExportArgs) -> Result<()> {
-    let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
-        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
     let output_path = &args.output;
 
     // XXX Move this code into datastore.rs?
@@ -63,7 +175,153 @@ fn cmd_datastore_export(global_args: &GlobalArgs, args: &DatastoreExportArgs) ->
                 output_path.display()
             );
         }
+
+        Jsonl => {
+            let write_bundle = |output_path: &std::path::Path| -> Result<()> {
+                let output = std::fs::File::create(output_path)?;
+                datastore.export_json(std::io::BufWriter::new(output))
+            };
+
+            write_bundle(output_path).context("Failed to write bundle")?;
+
+            info!(
+                "Exported datastore at {} to {}",
+                &datastore.root_dir().display(),
+                output_path.display()
+            );
+        }
     }
 
     Ok(())
 }
+
+fn cmd_datastore_import(global_args: &GlobalArgs, args: &DatastoreImportArgs) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let mut datastore =
+        Datastore::create_or_open(&datastore_path, global_args.advanced.sqlite_cache_size)
+            .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+
+    let input = get_reader_for_file_or_stdin(args.input.as_ref())
+        .context("Failed to open input for reading")?;
+    let num_matches_added = datastore
+        .import_json(std::io::BufReader::new(input))
+        .context("Failed to import bundle")?;
+
+    info!(
+        "Imported {} new matches into datastore at {}",
+        HumanCount(num_matches_added),
+        datastore.root_dir().display(),
+    );
+    Ok(())
+}
+
+fn cmd_datastore_merge(global_args: &GlobalArgs, args: &DatastoreMergeArgs) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let mut datastore =
+        Datastore::create_or_open(&datastore_path, global_args.advanced.sqlite_cache_size)
+            .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+
+    for src_path in args.sources.iter() {
+        let src = Datastore::open(src_path, global_args.advanced.sqlite_cache_size)
+            .with_context(|| format!("Failed to open datastore at {}", src_path.display()))?;
+        let num_matches_added = datastore
+            .merge(&src, args.on_conflict.into())
+            .with_context(|| format!("Failed to merge datastore at {}", src_path.display()))?;
+
+        info!(
+            "Merged {} new matches from datastore at {} into datastore at {}",
+            HumanCount(num_matches_added),
+            src_path.display(),
+            datastore.root_dir().display(),
+        );
+    }
+    Ok(())
+}
+
+fn cmd_datastore_provenance(
+    global_args: &GlobalArgs,
+    args: &DatastoreProvenanceArgs,
+) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+    let mut writer = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+
+    // Entries are written as they are read from the datastore rather than collected first, so
+    // that listing a very large datastore doesn't require holding everything in memory.
+    use DatastoreProvenanceOutputFormat::*;
+    let format = args.output_args.format;
+    if format == Json {
+        write!(writer, "[")?;
+    }
+    let mut first = true;
+    datastore
+        .visit_provenance(|p| {
+            match format {
+                Human => writeln!(writer, "{p}")?,
+                Json => {
+                    if !first {
+                        write!(writer, ",")?;
+                    }
+                    write!(writer, "\n  ")?;
+                    serde_json::to_writer(&mut writer, &p)?;
+                }
+                Jsonl => {
+                    serde_json::to_writer(&mut writer, &p)?;
+                    writeln!(writer)?;
+                }
+            }
+            first = false;
+            Ok(())
+        })
+        .context("Failed to list provenance entries")?;
+    if format == Json {
+        if !first {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "]")?;
+    }
+    writer.finish().context("Failed to finish writing output")?;
+
+    Ok(())
+}
+
+fn cmd_datastore_analyze(global_args: &GlobalArgs, args: &DatastoreAnalyzeArgs) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let mut datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+    datastore
+        .reanalyze()
+        .context("Failed to analyze datastore")?;
+
+    let num_matches = datastore.get_num_matches()?;
+    let num_redundant = datastore.get_num_redundant_matches()?;
+    info!(
+        "Analyzed {} matches in datastore at {}; {} are redundant",
+        HumanCount(num_matches),
+        datastore.root_dir().display(),
+        HumanCount(num_redundant),
+    );
+    Ok(())
+}
+
+fn cmd_datastore_compact(global_args: &GlobalArgs, args: &DatastoreCompactArgs) -> Result<()> {
+    let datastore_path = args.datastore_args.resolve()?;
+    let mut datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+
+    let size_before = datastore.database_size()?;
+    datastore.compact().context("Failed to compact datastore")?;
+    let size_after = datastore.database_size()?;
+
+    println!(
+        "Compacted datastore at {}: {} before, {} after",
+        datastore.root_dir().display(),
+        HumanBytes(size_before),
+        HumanBytes(size_after),
+    );
+    Ok(())
+}
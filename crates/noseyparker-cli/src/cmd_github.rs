@@ -31,11 +31,12 @@ fn list_repos(global_args: &GlobalArgs, args: &GitHubReposListArgs, api_url: Url
         None,
     )
     .context("Failed to enumerate GitHub repositories")?;
-    let output = args
+    let mut output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
-    RepoReporter(repo_urls).report(args.output_args.format, output)
+    RepoReporter(repo_urls).report(args.output_args.format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
 }
 
 struct RepoReporter(Vec<String>);
@@ -1,32 +1,58 @@
 use anyhow::{bail, Context, Result};
-use bstr::{BStr, ByteSlice};
+use bstr::{BStr, BString, ByteSlice};
 use indenter::indented;
 use schemars::JsonSchema;
 use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter, Write};
+use std::path::Path;
 use tracing::info;
 
 use noseyparker::blob_metadata::BlobMetadata;
 use noseyparker::bstring_escape::Escaped;
-use noseyparker::datastore::{Datastore, FindingDataEntry, FindingMetadata, Status};
+use noseyparker::datastore::{
+    Datastore, FindingDataEntry, FindingMetadata, MatchFilter, Status, Statuses,
+};
 use noseyparker::defaults::get_builtin_rules;
 use noseyparker::match_type::{Group, Groups, Match};
-use noseyparker::provenance::Provenance;
+use noseyparker::provenance::{BlobLineage, Provenance};
 use noseyparker::provenance_set::ProvenanceSet;
 
 use crate::args::{FindingStatus, GlobalArgs, ReportArgs, ReportOutputFormat};
 use crate::reportable::Reportable;
 
+mod diff_format;
 mod human_format;
+mod paths_format;
 mod sarif_format;
 mod styles;
 
 use styles::{StyledObject, Styles};
 
 pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
-    let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
-        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
-    let output = args
+    let format = args.output_args.format;
+    if args.collapse_by_secret
+        && matches!(format, ReportOutputFormat::Sarif | ReportOutputFormat::Diff)
+    {
+        bail!("The `--collapse-by-secret` option is not supported with the `{format}` format");
+    }
+    if format == ReportOutputFormat::Diff && args.baseline.is_none() {
+        bail!("The `diff` format requires a baseline datastore to be given with `--baseline`")
+    }
+
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+    let baseline = match &args.baseline {
+        Some(baseline_path) => Some(
+            Datastore::open(baseline_path, global_args.advanced.sqlite_cache_size).with_context(
+                || format!("Failed to open baseline datastore at {}", baseline_path.display()),
+            )?,
+        ),
+        None => None,
+    };
+    let mut output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
@@ -43,12 +69,27 @@ pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
         Some(args.filter_args.max_provenance.try_into().unwrap())
     };
 
+    let rule_patterns = args
+        .filter_args
+        .rules
+        .iter()
+        .map(|p| rule_pattern(p))
+        .collect::<Result<Vec<_>>>()?;
+
     let min_score = if args.filter_args.min_score <= 0.0 {
         None
     } else {
         Some(args.filter_args.min_score)
     };
 
+    let ignored_ids = args
+        .filter_args
+        .ignore
+        .iter()
+        .map(|p| read_ignore_file(p))
+        .collect::<Result<Vec<_>>>()?
+        .concat();
+
     // enable output styling:
     // - if the output destination is not explicitly specified and colors are not disabled
     // - if the output destination *is* explicitly specified and colors are forced on
@@ -58,18 +99,51 @@ pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
         global_args.color == crate::args::Mode::Always
     };
 
-    let styles = Styles::new(styles_enabled);
-
-    let reporter = DetailsReporter {
-        datastore,
-        max_matches,
-        max_provenance,
-        suppress_redundant: args.filter_args.suppress_redundant,
-        min_score,
-        finding_status: args.filter_args.finding_status,
-        styles,
+    let mk_reporter = |datastore| -> Result<DetailsReporter> {
+        let (ignored_finding_ids, ignored_match_ids) =
+            partition_ignored_ids(&datastore, &ignored_ids)?;
+        Ok(DetailsReporter {
+            datastore,
+            max_matches,
+            max_provenance,
+            match_filter: MatchFilter {
+                suppress_redundant: args.filter_args.suppress_redundant,
+                exclude_comments: args.filter_args.exclude_comments,
+                input_group: args.filter_args.input_group.clone(),
+                updated_since: None,
+                ignored_match_ids,
+            },
+            ignored_finding_ids,
+            ignore_accepted: args.filter_args.ignore_accepted,
+            min_score,
+            rule_patterns: rule_patterns.clone(),
+            finding_status: args.filter_args.finding_status,
+            score_precision: args.score_precision,
+            collapse_by_secret: args.collapse_by_secret,
+            show_pattern: args.show_pattern,
+            redact: args.redact,
+            baseline: None,
+            new_finding_ids: None,
+            styles: Styles::new(styles_enabled),
+        })
     };
-    reporter.report(args.output_args.format, output)
+
+    let mut reporter = mk_reporter(datastore)?;
+    match baseline {
+        Some(baseline) if format == ReportOutputFormat::Diff => {
+            reporter.baseline = Some(Box::new(mk_reporter(baseline)?));
+        }
+        Some(baseline) => {
+            let new_finding_ids = reporter
+                .datastore
+                .new_finding_ids(&baseline)
+                .context("Failed to compare findings against baseline datastore")?;
+            reporter.new_finding_ids = Some(new_finding_ids);
+        }
+        None => {}
+    }
+    reporter.report(format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
 }
 
 struct DetailsReporter {
@@ -77,21 +151,170 @@ struct DetailsReporter {
     max_matches: Option<usize>,
     max_provenance: Option<usize>,
     min_score: Option<f64>,
-    suppress_redundant: bool,
+    rule_patterns: Vec<glob::Pattern>,
+    match_filter: MatchFilter,
+    ignored_finding_ids: HashSet<String>,
+    ignore_accepted: bool,
     finding_status: Option<FindingStatus>,
+    score_precision: usize,
+    collapse_by_secret: bool,
+    show_pattern: bool,
+    redact: bool,
+    baseline: Option<Box<DetailsReporter>>,
+    /// If set, only these findings are reported; used for `--baseline` with non-`diff` formats
+    new_finding_ids: Option<HashSet<String>>,
     styles: Styles,
 }
 
+/// Parse a `--rule` pattern.
+///
+/// Patterns that contain glob metacharacters are used as-is; any other pattern is turned into a
+/// glob that matches it as a substring.
+fn rule_pattern(pattern: &str) -> Result<glob::Pattern> {
+    let glob = if pattern.contains(['*', '?', '[']) {
+        pattern.to_owned()
+    } else {
+        format!("*{}*", glob::Pattern::escape(pattern))
+    };
+    glob::Pattern::new(&glob).with_context(|| format!("Invalid rule pattern {pattern:?}"))
+}
+
+/// Read the finding and match IDs listed in the given `--ignore` file.
+///
+/// Blank lines and lines starting with `#` are skipped.
+fn read_ignore_file(path: &Path) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read ignore file {}", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Split the IDs listed in `--ignore` files into those of findings in the given datastore and the
+/// rest, which are taken to be match IDs.
+fn partition_ignored_ids(
+    datastore: &Datastore,
+    ids: &[String],
+) -> Result<(HashSet<String>, Vec<String>)> {
+    let mut finding_ids = HashSet::new();
+    let mut match_ids = Vec::new();
+    for id in ids {
+        if datastore
+            .has_finding(id)
+            .context("Failed to look up ignored ID in datastore")?
+        {
+            finding_ids.insert(id.clone());
+        } else {
+            match_ids.push(id.clone());
+        }
+    }
+    Ok((finding_ids, match_ids))
+}
+
+/// Does any of the given patterns match the rule ID or name of the given finding?
+fn rule_matches(patterns: &[glob::Pattern], metadata: &FindingMetadata) -> bool {
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    patterns.iter().any(|p| {
+        p.matches_with(&metadata.rule_text_id, options)
+            || p.matches_with(&metadata.rule_name, options)
+    })
+}
+
 /// Does `requested_status` match the given set of statuses?
-fn statuses_match(requested_status: FindingStatus, statuses: &[Status]) -> bool {
-    matches!(
-        (requested_status, statuses),
-        (FindingStatus::Accept, &[Status::Accept])
-            | (FindingStatus::Reject, &[Status::Reject])
-            | (FindingStatus::Null, &[])
-            | (FindingStatus::Mixed, &[Status::Accept, Status::Reject])
-            | (FindingStatus::Mixed, &[Status::Reject, Status::Accept])
-    )
+fn statuses_match(requested_status: FindingStatus, statuses: &Statuses) -> bool {
+    match requested_status {
+        FindingStatus::Accept => statuses.only(Status::Accept),
+        FindingStatus::Reject => statuses.only(Status::Reject),
+        FindingStatus::NeedsReview => statuses.only(Status::NeedsReview),
+        FindingStatus::Mixed => statuses.is_mixed(),
+        FindingStatus::Null => statuses.is_empty(),
+        FindingStatus::Regression => false,
+    }
+}
+
+/// The number of characters left unmasked at each end of a redacted value
+const REDACT_KEEP_CHARS: usize = 4;
+
+/// Mask the given content, keeping only a few characters at each end if it is long enough that
+/// doing so still hides most of it.
+fn redact(content: &[u8]) -> BString {
+    let chars: Vec<char> = content.chars().collect();
+    let n = chars.len();
+    let keep = if n >= 3 * REDACT_KEEP_CHARS {
+        REDACT_KEEP_CHARS
+    } else {
+        0
+    };
+    let mut result = String::with_capacity(n);
+    result.extend(&chars[..keep]);
+    result.push_str(&"*".repeat(n - 2 * keep));
+    result.extend(&chars[n - keep..]);
+    result.into()
+}
+
+/// Mask the content of each of the given capture groups.
+fn redact_groups(groups: &mut Groups) {
+    for g in groups.0.iter_mut() {
+        g.value = redact(&g.value);
+    }
+}
+
+/// Mask the matched content of the given match.
+///
+/// The context before and after the match is trimmed to the line that contains the match, and any
+/// other occurrences of the matched content or capture groups on that line are masked too.
+fn redact_match(m: &mut Match) {
+    let mut secrets: Vec<BString> = m.groups.0.iter().map(|g| g.value.clone()).collect();
+    secrets.push(m.snippet.matching.clone());
+    secrets.retain(|s| !s.is_empty());
+    let redact_context = |context: &[u8]| -> BString {
+        let mut context = BString::from(context);
+        for secret in secrets.iter() {
+            context = context.replace(secret, redact(secret)).into();
+        }
+        context
+    };
+
+    let snippet = &mut m.snippet;
+    let before = match snippet.before.rfind_byte(b'\n') {
+        Some(i) => &snippet.before[i + 1..],
+        None => &snippet.before[..],
+    };
+    let after = match snippet.after.find_byte(b'\n') {
+        Some(i) => &snippet.after[..i],
+        None => &snippet.after[..],
+    };
+    snippet.before = redact_context(before);
+    snippet.after = redact_context(after);
+    snippet.matching = redact(&snippet.matching);
+    redact_groups(&mut m.groups);
+}
+
+/// Partition the given findings into sets that have identical capture group content.
+///
+/// Group names are not considered, so findings from rules that name their groups differently are
+/// put in the same set. Sets are ordered by their first finding, and findings within each set keep
+/// their order.
+fn group_by_secret(group_metadata: Vec<FindingMetadata>) -> Vec<Vec<FindingMetadata>> {
+    let mut index: HashMap<Vec<BString>, usize> = HashMap::new();
+    let mut sets: Vec<Vec<FindingMetadata>> = Vec::new();
+    for metadata in group_metadata {
+        let values = metadata.groups.0.iter().map(|g| g.value.clone()).collect();
+        match index.entry(values) {
+            Entry::Occupied(e) => sets[*e.get()].push(metadata),
+            Entry::Vacant(e) => {
+                e.insert(sets.len());
+                sets.push(vec![metadata]);
+            }
+        }
+    }
+    sets
 }
 
 impl DetailsReporter {
@@ -99,13 +322,72 @@ impl DetailsReporter {
     fn get_finding_metadata(&self) -> Result<Vec<FindingMetadata>> {
         let datastore = &self.datastore;
         let mut group_metadata = datastore
-            .get_finding_metadata(self.suppress_redundant)
+            .get_finding_metadata(&self.match_filter)
             .context("Failed to get match group metadata from datastore")?;
 
+        // Suppress findings listed in `--ignore` files
+        if !self.ignored_finding_ids.is_empty() || !self.match_filter.ignored_match_ids.is_empty() {
+            // Findings whose matches are all listed have already been omitted by the match
+            // filter, so count the findings without it to get the total number suppressed
+            let unfiltered = MatchFilter {
+                ignored_match_ids: Vec::new(),
+                ..self.match_filter.clone()
+            };
+            let mut old_len = 0;
+            datastore
+                .visit_finding_metadata(&unfiltered, |_| {
+                    old_len += 1;
+                    Ok(())
+                })
+                .context("Failed to get match group metadata from datastore")?;
+            group_metadata.retain(|md| !self.ignored_finding_ids.contains(&md.finding_id));
+            let num_suppressed = old_len - group_metadata.len();
+
+            if num_suppressed == 1 {
+                info!(
+                    "Note: 1 finding listed in `--ignore` files was suppressed; \
+                       rerun without `--ignore` to show it"
+                );
+            } else if num_suppressed > 1 {
+                info!(
+                    "Note: {num_suppressed} findings listed in `--ignore` files were suppressed; \
+                       rerun without `--ignore` to show them"
+                );
+            }
+        }
+
+        // Suppress findings from non-matching rules
+        if !self.rule_patterns.is_empty() {
+            let old_len = group_metadata.len();
+            group_metadata.retain(|md| rule_matches(&self.rule_patterns, md));
+            let num_suppressed = old_len - group_metadata.len();
+
+            if num_suppressed == 1 {
+                info!(
+                    "Note: 1 finding from a rule not matching `--rule` was suppressed; \
+                       rerun without `--rule` to show it"
+                );
+            } else if num_suppressed > 1 {
+                info!(
+                    "Note: {num_suppressed} findings from rules not matching `--rule` were \
+                       suppressed; rerun without `--rule` to show them"
+                );
+            }
+        }
+
         // Suppress findings with non-matching status
         if let Some(status) = self.finding_status {
             let old_len = group_metadata.len();
-            group_metadata.retain(|md| statuses_match(status, md.statuses.0.as_slice()));
+            if let FindingStatus::Regression = status {
+                let regressed: HashSet<String> = datastore
+                    .get_regressed_finding_ids(None)
+                    .context("Failed to get regressed findings from datastore")?
+                    .into_iter()
+                    .collect();
+                group_metadata.retain(|md| regressed.contains(&md.finding_id));
+            } else {
+                group_metadata.retain(|md| statuses_match(status, &md.statuses));
+            }
             let num_suppressed = old_len - group_metadata.len();
 
             if num_suppressed == 1 {
@@ -122,6 +404,25 @@ impl DetailsReporter {
             }
         }
 
+        // Suppress accepted findings
+        if self.ignore_accepted {
+            let old_len = group_metadata.len();
+            group_metadata.retain(|md| !md.statuses.only(Status::Accept));
+            let num_suppressed = old_len - group_metadata.len();
+
+            if num_suppressed == 1 {
+                info!(
+                    "Note: 1 finding with `accept` status was suppressed; \
+                       rerun without `--ignore-accepted` to show it"
+                );
+            } else if num_suppressed > 1 {
+                info!(
+                    "Note: {num_suppressed} findings with `accept` status were suppressed; \
+                       rerun without `--ignore-accepted` to show them"
+                );
+            }
+        }
+
         // Suppress findings with non-matching score
         if let Some(min_score) = self.min_score {
             let old_len = group_metadata.len();
@@ -142,24 +443,85 @@ impl DetailsReporter {
             }
         }
 
+        // Suppress findings that are in the baseline datastore
+        if let Some(new_finding_ids) = &self.new_finding_ids {
+            let old_len = group_metadata.len();
+            group_metadata.retain(|md| new_finding_ids.contains(&md.finding_id));
+            let num_suppressed = old_len - group_metadata.len();
+
+            if num_suppressed == 1 {
+                info!(
+                    "Note: 1 finding in the baseline datastore was suppressed; \
+                       rerun without `--baseline` to show it"
+                );
+            } else if num_suppressed > 1 {
+                info!(
+                    "Note: {num_suppressed} findings in the baseline datastore were suppressed; \
+                       rerun without `--baseline` to show them"
+                );
+            }
+        }
+
         Ok(group_metadata)
     }
 
     /// Get the matches associated with the given finding.
     fn get_matches(&self, metadata: &FindingMetadata) -> Result<Vec<ReportMatch>> {
-        Ok(self
-            .datastore
-            .get_finding_data(
-                metadata,
-                self.max_matches,
-                self.max_provenance,
-                self.suppress_redundant,
-            )
-            .with_context(|| format!("Failed to get matches for finding {metadata:?}"))
-            .expect("should be able to find get matches for finding")
+        self.datastore
+            .get_finding_data(metadata, self.max_matches, self.max_provenance, &self.match_filter)
+            .with_context(|| format!("Failed to get matches for finding {metadata:?}"))?
             .into_iter()
-            .map(|e| e.into())
-            .collect())
+            .map(|e| self.get_report_match(e))
+            .collect()
+    }
+
+    /// Convert a finding data entry into a report match, looking up the lineage of its blob if
+    /// its provenance says it was derived from another one.
+    fn get_report_match(&self, e: FindingDataEntry) -> Result<ReportMatch> {
+        let mut rm = ReportMatch::from(e);
+        let derived = rm
+            .provenance
+            .iter()
+            .any(|p| matches!(p, Provenance::Extended(ep) if ep.parent().is_some()));
+        if derived {
+            rm.lineage = self
+                .datastore
+                .get_blob_lineage(&rm.m.blob_id)
+                .context("Failed to get blob lineage from datastore")?;
+            if let Some(root) = rm.lineage.last() {
+                rm.lineage_root = self
+                    .datastore
+                    .get_provenance_by_blob_id(&root.parent_blob)
+                    .context("Failed to get provenance from datastore")?;
+            }
+        }
+        Ok(rm)
+    }
+
+    /// Get the matches associated with the given finding, and the rule pattern if requested.
+    ///
+    /// If redaction is enabled, the matched content in the finding is masked, and the context of
+    /// each match is trimmed to the line that contains it.
+    fn get_finding(&self, mut metadata: FindingMetadata) -> Result<Finding> {
+        let mut matches = self.get_matches(&metadata)?;
+        if self.redact {
+            redact_groups(&mut metadata.groups);
+            for rm in matches.iter_mut() {
+                redact_match(&mut rm.m);
+            }
+        }
+        let rule_pattern = if self.show_pattern {
+            self.datastore
+                .get_rule_pattern(&metadata.rule_structural_id)
+                .context("Failed to get rule pattern from datastore")?
+        } else {
+            None
+        };
+        Ok(Finding {
+            metadata,
+            matches,
+            rule_pattern,
+        })
     }
 
     fn style_finding_heading<D>(&self, val: D) -> StyledObject<D> {
@@ -196,6 +558,9 @@ impl Reportable for DetailsReporter {
             ReportOutputFormat::Json => self.json_format(writer),
             ReportOutputFormat::Jsonl => self.jsonl_format(writer),
             ReportOutputFormat::Sarif => self.sarif_format(writer),
+            ReportOutputFormat::Paths => self.paths_format(writer, b'\n'),
+            ReportOutputFormat::Paths0 => self.paths_format(writer, b'\0'),
+            ReportOutputFormat::Diff => self.diff_format(writer),
         }
     }
 }
@@ -223,18 +588,34 @@ impl DetailsReporter {
         }
 
         let mut first = true;
-
-        for metadata in group_metadata {
+        let mut write_sep = |writer: &mut W| -> Result<()> {
             if !first {
                 if let Some(sep) = sep {
                     write!(writer, "{}", sep)?;
                 }
             }
             first = false;
+            Ok(())
+        };
+
+        if self.collapse_by_secret {
+            for metadatas in group_by_secret(group_metadata) {
+                write_sep(&mut writer)?;
+
+                let findings = metadatas
+                    .into_iter()
+                    .map(|metadata| self.get_finding(metadata))
+                    .collect::<Result<Vec<_>>>()?;
+                let s = SecretFindings::new(findings);
+                serde_json::to_writer(&mut writer, &s)?;
+            }
+        } else {
+            for metadata in group_metadata {
+                write_sep(&mut writer)?;
 
-            let matches = self.get_matches(&metadata)?;
-            let f = Finding::new(metadata, matches);
-            serde_json::to_writer(&mut writer, &f)?;
+                let f = self.get_finding(metadata)?;
+                serde_json::to_writer(&mut writer, &f)?;
+            }
         }
 
         if let Some(end) = end {
@@ -259,6 +640,36 @@ pub(crate) struct Finding {
     #[serde(flatten)]
     metadata: FindingMetadata,
     matches: Vec<ReportMatch>,
+
+    /// The pattern of the rule that produced the matches, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_pattern: Option<String>,
+}
+
+/// A set of findings from different rules that all have the same capture group content
+#[derive(Serialize, JsonSchema)]
+pub(crate) struct SecretFindings {
+    /// The capture group content shared by the findings
+    groups: Groups,
+
+    /// The names of the rules that produced the findings
+    rule_names: Vec<String>,
+
+    /// The findings, one per rule
+    findings: Vec<Finding>,
+}
+
+impl SecretFindings {
+    /// Create a new `SecretFindings` from a nonempty list of findings with identical group content.
+    fn new(findings: Vec<Finding>) -> Self {
+        let groups = findings[0].groups().clone();
+        let rule_names = findings.iter().map(|f| f.rule_name().to_owned()).collect();
+        Self {
+            groups,
+            rule_names,
+            findings,
+        }
+    }
 }
 
 /// A match produced by one of Nosey Parker's rules.
@@ -285,6 +696,14 @@ struct ReportMatch {
 
     /// The match structural IDs that this match is considered redundant to
     redundant_to: Vec<String>,
+
+    /// The chain of blobs that the match's blob was derived from, if any
+    #[serde(skip)]
+    lineage: Vec<BlobLineage>,
+
+    /// The provenance of the blob at the end of `lineage`
+    #[serde(skip)]
+    lineage_root: Vec<Provenance>,
 }
 
 impl From<FindingDataEntry> for ReportMatch {
@@ -297,15 +716,13 @@ impl From<FindingDataEntry> for ReportMatch {
             comment: e.match_comment,
             status: e.match_status,
             redundant_to: e.redundant_to,
+            lineage: Vec::new(),
+            lineage_root: Vec::new(),
         }
     }
 }
 
 impl Finding {
-    fn new(metadata: FindingMetadata, matches: Vec<ReportMatch>) -> Self {
-        Self { metadata, matches }
-    }
-
     /// The name of the rule that produced this finding
     fn rule_name(&self) -> &str {
         &self.metadata.rule_name
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use super::human_format::PrettyFinding;
+use super::*;
+
+impl DetailsReporter {
+    /// Write a patch-like comparison of the findings in this reporter's datastore against those in
+    /// the baseline datastore.
+    ///
+    /// Added findings are those that are only in this reporter's datastore; removed findings are
+    /// those that are only in the baseline datastore. Each one is written using the `human` format,
+    /// with every line prefixed by a `+` or `-` marker, grouped by rule.
+    pub fn diff_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        let baseline = match &self.baseline {
+            Some(baseline) => baseline,
+            None => bail!("The `diff` format requires a baseline datastore"),
+        };
+
+        let current = self.get_finding_metadata()?;
+        let previous = baseline.get_finding_metadata()?;
+
+        let current_ids: HashSet<String> = current.iter().map(|md| md.finding_id.clone()).collect();
+        let previous_ids: HashSet<String> =
+            previous.iter().map(|md| md.finding_id.clone()).collect();
+
+        let mut changes: Vec<(char, &DetailsReporter, FindingMetadata)> = current
+            .into_iter()
+            .filter(|md| !previous_ids.contains(&md.finding_id))
+            .map(|md| ('+', self, md))
+            .collect();
+        let num_added = changes.len();
+        changes.extend(
+            previous
+                .into_iter()
+                .filter(|md| !current_ids.contains(&md.finding_id))
+                .map(|md| ('-', baseline.as_ref(), md)),
+        );
+        let num_removed = changes.len() - num_added;
+
+        // Group by rule, keeping added findings before removed ones within each rule
+        changes.sort_by(|(_, _, a), (_, _, b)| a.rule_name.cmp(&b.rule_name));
+
+        writeln!(writer, "{} {num_added}", self.style_heading("Added findings:"))?;
+        writeln!(writer, "{} {num_removed}", self.style_heading("Removed findings:"))?;
+
+        let mut prev_rule_name: Option<String> = None;
+        for (marker, reporter, metadata) in changes {
+            if prev_rule_name.as_ref() != Some(&metadata.rule_name) {
+                writeln!(writer)?;
+                writeln!(
+                    writer,
+                    "{} {}",
+                    self.style_heading("Rule:"),
+                    self.style_rule(&metadata.rule_name)
+                )?;
+                prev_rule_name = Some(metadata.rule_name.clone());
+            }
+            writeln!(writer)?;
+
+            let heading = if marker == '+' {
+                "Added finding"
+            } else {
+                "Removed finding"
+            };
+            writeln!(
+                writer,
+                "{marker} {} (id {})",
+                self.style_finding_heading(heading),
+                self.style_id(&metadata.finding_id),
+            )?;
+
+            let finding = reporter.get_finding(metadata)?;
+            let rendered = PrettyFinding(reporter, &finding).to_string();
+            for line in rendered.trim_end_matches('\n').lines() {
+                if line.is_empty() {
+                    writeln!(writer, "{marker}")?;
+                } else {
+                    writeln!(writer, "{marker} {line}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,26 +2,90 @@ use super::*;
 
 impl DetailsReporter {
     pub fn human_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        if self.collapse_by_secret {
+            return self.human_format_by_secret(writer);
+        }
+
         let group_metadata = self.get_finding_metadata()?;
         let num_findings = group_metadata.len();
         for (finding_num, metadata) in group_metadata.into_iter().enumerate() {
             let finding_num = finding_num + 1;
-            let matches = self.get_matches(&metadata)?;
-            let finding = Finding { metadata, matches };
+            let finding = self.get_finding(metadata)?;
             writeln!(
                 &mut writer,
                 "{} (id {})",
                 self.style_finding_heading(format!("Finding {finding_num}/{num_findings}")),
                 self.style_id(&finding.metadata.finding_id),
             )?;
-            writeln!(&mut writer, "{}", PrettyFinding(self, &finding))?;
+            write!(&mut writer, "{}", PrettyFinding(self, &finding))?;
+            self.write_rule_references(&mut writer, &finding.metadata)?;
+            writeln!(&mut writer)?;
         }
         Ok(())
     }
+
+    /// Write findings grouped by their capture groups, with the findings from each rule that
+    /// matched the same secret nested under a single heading.
+    fn human_format_by_secret<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        let secrets = group_by_secret(self.get_finding_metadata()?);
+        let num_secrets = secrets.len();
+        for (secret_num, group_metadata) in secrets.into_iter().enumerate() {
+            let secret_num = secret_num + 1;
+            let rule_names: Vec<&str> = group_metadata
+                .iter()
+                .map(|md| md.rule_name.as_str())
+                .collect();
+            writeln!(
+                &mut writer,
+                "{}",
+                self.style_finding_heading(format!("Secret {secret_num}/{num_secrets}")),
+            )?;
+            writeln!(
+                &mut writer,
+                "{} {}",
+                self.style_heading("Rules:"),
+                self.style_rule(rule_names.join(", ")),
+            )?;
+            writeln!(&mut writer)?;
+
+            let num_findings = group_metadata.len();
+            for (finding_num, metadata) in group_metadata.into_iter().enumerate() {
+                let finding_num = finding_num + 1;
+                let finding = self.get_finding(metadata)?;
+                writeln!(
+                    &mut writer,
+                    "{} (id {})",
+                    self.style_finding_heading(format!("Finding {finding_num}/{num_findings}")),
+                    self.style_id(&finding.metadata.finding_id),
+                )?;
+                write!(&mut writer, "{}", PrettyFinding(self, &finding))?;
+                self.write_rule_references(&mut writer, &finding.metadata)?;
+                writeln!(&mut writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a footer listing the references of the rule that produced the finding, if any.
+    fn write_rule_references<W: std::io::Write>(
+        &self,
+        mut writer: W,
+        metadata: &FindingMetadata,
+    ) -> Result<()> {
+        if metadata.rule_references.is_empty() {
+            return Ok(());
+        }
+        writeln!(&mut writer, "{}", self.style_heading("References:"))?;
+        for reference in metadata.rule_references.iter() {
+            writeln!(&mut writer, "    {}", self.style_metadata(reference))?;
+        }
+        writeln!(&mut writer)?;
+        Ok(())
+    }
 }
 
 /// A wrapper type to allow human-oriented pretty-printing of a `Finding`.
-pub struct PrettyFinding<'a>(&'a DetailsReporter, &'a Finding);
+pub struct PrettyFinding<'a>(pub(super) &'a DetailsReporter, pub(super) &'a Finding);
 
 impl<'a> Display for PrettyFinding<'a> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -33,24 +97,45 @@ impl<'a> Display for PrettyFinding<'a> {
             reporter.style_rule(finding.rule_name())
         )?;
 
-        // write out status if set: either `Accept`, `Reject`, or `Mixed` (when there are
-        // conflicting match statuses within the finding)
-        let statuses = &finding.metadata.statuses.0;
-        let num_statuses = statuses.len();
-        #[allow(clippy::comparison_chain)]
-        if num_statuses > 1 {
+        // write out rule pattern if requested
+        if let Some(pattern) = &finding.rule_pattern {
+            if pattern.contains('\n') {
+                writeln!(f, "{}", reporter.style_heading("Pattern:"))?;
+                writeln!(f)?;
+                writeln!(
+                    indented(f).with_str("    "),
+                    "{}",
+                    reporter.style_metadata(pattern.trim_end())
+                )?;
+                writeln!(f)?;
+            } else {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("Pattern:"),
+                    reporter.style_metadata(pattern)
+                )?;
+            }
+        }
+
+        // write out status if set: either `Accept`, `Reject`, `Needs review`, or `Mixed` (when
+        // there are conflicting match statuses within the finding)
+        let statuses = &finding.metadata.statuses;
+        if statuses.is_mixed() {
             writeln!(f, "{} Mixed", reporter.style_heading("Status:"))?;
-        } else if num_statuses == 1 {
-            let status = match statuses[0] {
+        } else if let Some(status) = statuses.iter().next() {
+            let status = match status {
                 Status::Accept => "Accept",
                 Status::Reject => "Reject",
+                Status::NeedsReview => "Needs review",
             };
             writeln!(f, "{} {status}", reporter.style_heading("Status:"))?;
         };
 
         // write out score if set
         if let Some(mean_score) = finding.metadata.mean_score {
-            writeln!(f, "{} {mean_score:.3}", reporter.style_heading("Score:"))?;
+            let precision = reporter.score_precision;
+            writeln!(f, "{} {mean_score:.precision$}", reporter.style_heading("Score:"))?;
         };
 
         // write out comment if set
@@ -60,7 +145,7 @@ impl<'a> Display for PrettyFinding<'a> {
 
         let mut write_group =
             |group_heading: StyledObject<String>, g: &Group| -> std::fmt::Result {
-                let g = &g.0;
+                let g = &g.value;
                 // write out the group on one line if it's single-line, and multiple lines otherwise
                 if g.contains(&b'\n') {
                     writeln!(f, "{group_heading}")?;
@@ -73,16 +158,18 @@ impl<'a> Display for PrettyFinding<'a> {
                 Ok(())
             };
 
+        // named groups are labeled with their names, e.g., `Group 2 (secret):`
         let gs = &finding.groups().0;
-        if gs.len() > 1 {
-            for (i, g) in gs.iter().enumerate() {
-                let i = i + 1;
-                let group_heading = reporter.style_heading(format!("Group {i}:"));
-                write_group(group_heading, g)?;
+        for (i, g) in gs.iter().enumerate() {
+            let mut heading = "Group".to_string();
+            if gs.len() > 1 {
+                heading += &format!(" {}", i + 1);
+            }
+            if let Some(name) = &g.name {
+                heading += &format!(" ({name})");
             }
-        } else {
-            let group_heading = reporter.style_heading("Group:".into());
-            write_group(group_heading, &gs[0])?;
+            heading.push(':');
+            write_group(reporter.style_heading(heading), g)?;
         }
 
         // write out count if not all matches are displayed
@@ -111,6 +198,8 @@ impl<'a> Display for PrettyFinding<'a> {
                 comment,
                 status,
                 redundant_to,
+                lineage,
+                lineage_root,
             } = rm;
 
             writeln!(
@@ -134,13 +223,15 @@ impl<'a> Display for PrettyFinding<'a> {
                 let status = match status {
                     Status::Accept => "Accept",
                     Status::Reject => "Reject",
+                    Status::NeedsReview => "Needs review",
                 };
                 writeln!(f, "{} {status}", reporter.style_heading("Status:"))?;
             }
 
             // write out match score if set
             if let Some(score) = score {
-                writeln!(f, "{} {score:.3}", reporter.style_heading("Score:"))?;
+                let precision = reporter.score_precision;
+                writeln!(f, "{} {score:.precision$}", reporter.style_heading("Score:"))?;
             };
 
             // write out match comment if set
@@ -148,6 +239,11 @@ impl<'a> Display for PrettyFinding<'a> {
                 writeln!(f, "{} {comment}", reporter.style_heading("Comment:"))?;
             };
 
+            // write out lexical context if known
+            if let Some(context) = &m.context {
+                writeln!(f, "{} {context}", reporter.style_heading("Context:"))?;
+            };
+
             let blob_metadata = {
                 format!(
                     "{} bytes, {}, {}",
@@ -203,6 +299,15 @@ impl<'a> Display for PrettyFinding<'a> {
                             )?;
                             writeln!(f)?;
                         }
+                        if let Some(cs) = &e.latest_commit {
+                            writeln!(
+                                f,
+                                "{} still present in {} as {}",
+                                reporter.style_heading("Commit:"),
+                                reporter.style_metadata(cs.commit_metadata.commit_id),
+                                cs.blob_path,
+                            )?;
+                        }
                     }
                     // FIXME: implement this case properly
                     Provenance::Extended(e) => {
@@ -216,6 +321,27 @@ impl<'a> Display for PrettyFinding<'a> {
                 }
             }
 
+            // write out the blobs that this one was derived from, if known
+            for l in lineage {
+                writeln!(
+                    f,
+                    "{} {} ({}, bytes {}-{})",
+                    reporter.style_heading("Derived from:"),
+                    reporter.style_metadata(&l.parent_blob),
+                    l.transform,
+                    l.span.start,
+                    l.span.end,
+                )?;
+            }
+            for p in lineage_root {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("Originally from:"),
+                    reporter.style_metadata(p),
+                )?;
+            }
+
             writeln!(
                 f,
                 "{} {} ({})",
@@ -228,10 +354,12 @@ impl<'a> Display for PrettyFinding<'a> {
             writeln!(f)?;
             writeln!(
                 indented(&mut f).with_str("    "),
-                "{}{}{}",
+                "{}{}{}{}{}",
+                m.snippet.before_marker(),
                 Escaped(&m.snippet.before),
                 reporter.style_match(Escaped(&m.snippet.matching)),
-                Escaped(&m.snippet.after)
+                Escaped(&m.snippet.after),
+                m.snippet.after_marker(),
             )?;
             writeln!(f)?;
         }
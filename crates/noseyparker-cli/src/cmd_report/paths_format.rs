@@ -0,0 +1,39 @@
+use std::collections::BTreeSet;
+
+use super::*;
+
+impl DetailsReporter {
+    /// Write the distinct paths of files that have at least one finding, in sorted order, each
+    /// followed by `terminator`.
+    ///
+    /// All matches and provenance entries of each finding are considered, regardless of the
+    /// `--max-matches` and `--max-provenance` settings. For blobs found in Git history, the path
+    /// within the repository is used. Provenance entries without a path are skipped.
+    pub fn paths_format<W: std::io::Write>(&self, mut writer: W, terminator: u8) -> Result<()> {
+        let group_metadata = self.get_finding_metadata()?;
+
+        let mut paths = BTreeSet::new();
+        for metadata in group_metadata {
+            let entries = self
+                .datastore
+                .get_finding_data(&metadata, None, None, &self.match_filter)
+                .with_context(|| format!("Failed to get matches for finding {metadata:?}"))?;
+            for entry in entries {
+                paths.extend(
+                    entry
+                        .provenance
+                        .iter()
+                        .filter_map(|p| p.blob_path())
+                        .map(|p| p.to_owned()),
+                );
+            }
+        }
+
+        for path in paths {
+            writer.write_all(path.as_os_str().as_encoded_bytes())?;
+            writer.write_all(&[terminator])?;
+        }
+
+        Ok(())
+    }
+}
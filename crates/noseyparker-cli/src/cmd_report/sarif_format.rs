@@ -113,8 +113,7 @@ impl DetailsReporter {
 
         let mut findings = Vec::with_capacity(group_metadata.len());
         for metadata in group_metadata {
-            let matches = self.get_matches(&metadata)?;
-            let finding = Finding::new(metadata, matches);
+            let finding = self.get_finding(metadata)?;
             findings.push(self.make_sarif_result(&finding)?);
         }
 
@@ -153,15 +152,15 @@ fn noseyparker_sarif_rules() -> Result<Vec<sarif::ReportingDescriptor>> {
                 .text(&rule.pattern)
                 .build();
 
-            let rule = sarif::ReportingDescriptor::builder()
+            let mut descriptor = sarif::ReportingDescriptor::builder()
                 .id(&rule.name) // FIXME: nosey parker rules need to have stable, unique IDs, preferably without spaces
                 // .name(&rule.name)  // FIXME: populate this once we have proper IDs
                 .short_description(description)
                 // .full_description(description)  // FIXME: populate this
                 .help(help) // FIXME: provide better help messages for NP rules that we can include here
-                // .help_uri() // FIXME: populate this
                 .build();
-            Ok(rule)
+            descriptor.help_uri = rule.references.first().cloned();
+            Ok(descriptor)
         })
         .collect::<Result<Vec<_>>>()
 }
@@ -226,9 +226,12 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
             match pat.static_captures_len() {
                 // the default is a single capture group for the entire match
                 // not sure if 0 can actually happen
+                //
+                // Such rules work, using their entire match as their capture group, but are
+                // likely to produce less meaningful findings.
                 Some(0) | Some(1) => {
-                    error!("Rule has no capture groups");
-                    num_errors += 1;
+                    warn!("Rule has no capture groups; its entire match will be used instead");
+                    num_warnings += 1;
                 }
                 Some(_len) => {}
                 None => {
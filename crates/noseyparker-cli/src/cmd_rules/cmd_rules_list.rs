@@ -10,7 +10,7 @@ use crate::rule_loader::{LoadedRules, RuleLoader};
 pub fn run(_global_args: &GlobalArgs, args: &RulesListArgs) -> Result<()> {
     let _span = debug_span!("cmd_rules_list").entered();
 
-    let output = args
+    let mut output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
@@ -20,7 +20,8 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesListArgs) -> Result<()> {
         .context("Failed to load rules")?;
 
     let reporter = RulesReporter { loaded };
-    reporter.report(args.output_args.format, output)
+    reporter.report(args.output_args.format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
 }
 
 struct RulesReporter {
@@ -2,31 +2,36 @@ use anyhow::{bail, Context, Result};
 use indicatif::{HumanBytes, HumanCount, HumanDuration};
 use rayon::prelude::*;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tracing::{debug, error, error_span, info, trace, warn};
 
+use crate::util::Counted;
 use crate::{args, rule_loader::RuleLoader};
 
 use content_guesser::Guesser;
-use input_enumerator::{FilesystemEnumerator, FoundInput};
+use input_enumerator::{FilesystemEnumerator, FoundInput, SkipReason, SkippedResult};
 use progress::Progress;
 
-use noseyparker::blob::{Blob, BlobId};
+use noseyparker::blob::{Blob, BlobId, SizeChangedError};
 use noseyparker::blob_id_map::BlobIdMap;
 use noseyparker::blob_metadata::BlobMetadata;
-use noseyparker::datastore::Datastore;
+use noseyparker::datastore::{Datastore, DatastoreStats};
 use noseyparker::defaults::DEFAULT_IGNORE_RULES;
 use noseyparker::git_binary::{CloneMode, Git};
 use noseyparker::git_url::GitUrl;
+use noseyparker::lexical_context::LexicalSyntax;
 use noseyparker::location;
 use noseyparker::match_type::Match;
-use noseyparker::matcher::{Matcher, ScanResult};
+use noseyparker::matcher::{Matcher, ScanResult, DEFAULT_CHUNK_OVERLAP};
 use noseyparker::matcher_stats::MatcherStats;
-use noseyparker::provenance::Provenance;
+use noseyparker::provenance::{CommitProvenance, Provenance};
 use noseyparker::provenance_set::ProvenanceSet;
 use noseyparker::rules_database::RulesDatabase;
+use noseyparker::score::score_match;
+use noseyparker::structural_id::StructuralIdAlgo;
 
 // -------------------------------------------------------------------------------------------------
 /// Something that can be turned into a parallel iterator of blobs
@@ -122,13 +127,43 @@ struct FileResultIter {
     blob: Blob,
 }
 
-impl ParallelBlobIterator for input_enumerator::FileResult {
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::FileResult) {
     type Iter = FileResultIter;
 
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
-        let blob = Blob::from_file(&self.path)
-            .with_context(|| format!("Failed to load blob from {}", self.path.display()))?;
-        Ok(Some(FileResultIter { inner: self, blob }))
+        let (cfg, input) = self;
+        let path = &input.path;
+        if !cfg.blob_size_limit.allows(input.num_bytes, path.display()) {
+            return Ok(None);
+        }
+        let blob = match cfg.mmap_min_size {
+            Some(mmap_min_size) if input.num_bytes >= mmap_min_size => {
+                match Blob::from_file_mmap(path) {
+                    Ok(blob) if blob.len() as u64 == input.num_bytes => blob,
+                    Ok(blob) => {
+                        warn!(
+                            "Skipping {}: size changed from {} to {} bytes since enumeration",
+                            path.display(),
+                            input.num_bytes,
+                            blob.len()
+                        );
+                        return Ok(None);
+                    }
+                    Err(e) if e.is::<SizeChangedError>() => {
+                        warn!("Skipping {}: {e}", path.display());
+                        return Ok(None);
+                    }
+                    Err(e) => {
+                        return Err(e).with_context(|| {
+                            format!("Failed to load blob from {}", path.display())
+                        })
+                    }
+                }
+            }
+            _ => Blob::from_file(path)
+                .with_context(|| format!("Failed to load blob from {}", path.display()))?,
+        };
+        Ok(Some(FileResultIter { inner: input, blob }))
     }
 }
 
@@ -150,13 +185,18 @@ impl ParallelIterator for FileResultIter {
 /// A parallel iterator for an `input_enumerator::GitRepoResult`
 struct GitRepoResultIter {
     inner: input_enumerator::GitRepoResult,
+    blob_size_limit: BlobSizeLimit,
 }
 
-impl ParallelBlobIterator for input_enumerator::GitRepoResult {
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::GitRepoResult) {
     type Iter = GitRepoResultIter;
 
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
-        Ok(Some(GitRepoResultIter { inner: self }))
+        let (cfg, inner) = self;
+        Ok(Some(GitRepoResultIter {
+            inner,
+            blob_size_limit: cfg.blob_size_limit.clone(),
+        }))
     }
 }
 
@@ -169,6 +209,7 @@ impl ParallelIterator for GitRepoResultIter {
     {
         let repo = self.inner.repository.into_sync();
         let repo_path = Arc::new(self.inner.path.clone());
+        let blob_size_limit = self.blob_size_limit;
         self.inner
             .blobs
             .into_par_iter()
@@ -188,13 +229,18 @@ impl ParallelIterator for GitRepoResultIter {
             .with_min_len(1024)
             .map_init(
                 || repo.to_thread_local(),
-                |repo, md| -> Result<(ProvenanceSet, Blob)> {
+                |repo, md| -> Result<Option<(ProvenanceSet, Blob)>> {
                     let blob_id = md.blob_oid;
 
-                    let blob = || -> Result<Blob> {
+                    let blob = || -> Result<Option<Blob>> {
+                        // Check the size from the object header to avoid decompressing big blobs
+                        let num_bytes = repo.find_header(blob_id)?.size();
+                        if !blob_size_limit.allows(num_bytes, format_args!("blob {blob_id}")) {
+                            return Ok(None);
+                        }
                         let mut blob = repo.find_object(blob_id)?.try_into_blob()?;
                         let data = std::mem::take(&mut blob.data); // avoid a copy
-                        Ok(Blob::new(BlobId::from(&blob_id), data))
+                        Ok(Some(Blob::new(BlobId::from(&blob_id), data)))
                     }()
                     .with_context(|| {
                         format!(
@@ -202,20 +248,29 @@ impl ParallelIterator for GitRepoResultIter {
                             repo_path.display(),
                         )
                     })?;
+                    let Some(blob) = blob else {
+                        return Ok(None);
+                    };
 
+                    let latest_commit = md.latest_seen.map(|e| CommitProvenance {
+                        commit_metadata: e.commit_metadata,
+                        blob_path: e.path,
+                    });
                     let provenance =
                         ProvenanceSet::try_from_iter(md.first_seen.into_iter().map(|e| {
                             Provenance::from_git_repo_with_first_commit(
                                 repo_path.clone(),
                                 e.commit_metadata,
                                 e.path,
+                                latest_commit.clone(),
                             )
                         }))
                         .unwrap_or_else(|| Provenance::from_git_repo(repo_path.clone()).into());
 
-                    Ok((provenance, blob))
+                    Ok(Some((provenance, blob)))
                 },
             )
+            .filter_map(Result::transpose)
             .drive_unindexed(consumer)
     }
 }
@@ -224,7 +279,250 @@ impl ParallelIterator for GitRepoResultIter {
 struct EnumeratorConfig {
     enumerate_git_history: bool,
     collect_git_metadata: bool,
+
+    /// Should the latest branch tip commit containing each Git blob be recorded?
+    collect_git_latest_commits: bool,
+
     gitignore: input_enumerator::Gitignore,
+
+    /// Files at least this many bytes are memory-mapped rather than read into memory
+    mmap_min_size: Option<u64>,
+
+    blob_size_limit: BlobSizeLimit,
+}
+
+// -------------------------------------------------------------------------------------------------
+/// The maximum size of blobs to scan, along with a count of the blobs skipped for exceeding it
+#[derive(Clone, Default)]
+struct BlobSizeLimit {
+    /// The maximum size of blobs to scan, in bytes, if any
+    max_size: Option<u64>,
+
+    /// The number of blobs skipped for exceeding the maximum size
+    num_skipped: Arc<AtomicU64>,
+}
+
+impl BlobSizeLimit {
+    /// Should a blob of the given size be scanned?
+    ///
+    /// If not, the blob is counted as skipped.
+    fn allows(&self, num_bytes: u64, what: impl std::fmt::Display) -> bool {
+        match self.max_size {
+            Some(max_size) if num_bytes > max_size => {
+                debug!("Skipping {what}: size {num_bytes} exceeds max blob size");
+                self.num_skipped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+/// A record of which filesystem inputs were scanned and which were skipped, for `--coverage-report`
+#[derive(Default, serde::Serialize)]
+struct CoverageReport {
+    /// The number of files enumerated from the filesystem for scanning
+    num_files_scanned: u64,
+
+    /// The filesystem entries that were not scanned
+    skipped: Vec<SkippedResult>,
+}
+
+impl CoverageReport {
+    fn observe(&mut self, input: &FoundInput) {
+        match input {
+            FoundInput::File(_) => self.num_files_scanned += 1,
+            FoundInput::Skipped(s) => self.skipped.push(s.clone()),
+            FoundInput::Directory(_) | FoundInput::EnumeratorFile(_) => {}
+        }
+    }
+
+    /// Build a table of the number of skipped entries for each reason.
+    fn table(&self) -> prettytable::Table {
+        use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
+        use prettytable::row;
+        use std::collections::BTreeMap;
+
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for s in self.skipped.iter() {
+            let reason = match &s.reason {
+                // Don't make a separate row for each distinct error message
+                SkipReason::Error(_) => "error".to_string(),
+                r => r.to_string(),
+            };
+            *counts.entry(reason).or_default() += 1;
+        }
+
+        let f = FormatBuilder::new()
+            .column_separator(' ')
+            .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
+            .padding(1, 1)
+            .build();
+
+        let mut table: prettytable::Table = counts
+            .iter()
+            .map(|(reason, count)| row![l -> reason, r -> HumanCount(*count)])
+            .collect();
+        table.set_format(f);
+        table.set_titles(row![lb -> "Skip Reason", cb -> "Entries"]);
+        table
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+/// Statistics about a completed scan, for the human-readable output and `--stats-json`
+#[derive(serde::Serialize)]
+struct ScanStats {
+    /// The number of blobs scanned
+    blobs_scanned: u64,
+
+    /// The number of bytes scanned
+    bytes_scanned: u64,
+
+    /// The number of matches newly recorded to the datastore by the scan
+    new_matches: u64,
+
+    /// The total number of matches in the datastore after the scan
+    total_matches: u64,
+
+    /// The total number of findings in the datastore after the scan
+    total_findings: u64,
+
+    /// The number of blobs skipped for exceeding the maximum blob size
+    oversize_blobs_skipped: u64,
+
+    /// The elapsed time of the scan, in seconds
+    elapsed_secs: f64,
+
+    /// A snapshot of the overall contents of the datastore after the scan
+    datastore: DatastoreStats,
+}
+
+impl ScanStats {
+    fn write_json(&self, path: &Path) -> Result<()> {
+        use std::io::Write;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes_per_sec = (self.bytes_scanned as f64 / self.elapsed_secs) as u64;
+        write!(
+            f,
+            "Scanned {} from {} blobs in {} ({}/s); {}/{} new matches",
+            HumanBytes(self.bytes_scanned),
+            HumanCount(self.blobs_scanned),
+            HumanDuration(Duration::from_secs_f64(self.elapsed_secs)),
+            HumanBytes(bytes_per_sec),
+            HumanCount(self.new_matches),
+            HumanCount(self.total_matches),
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+/// How `BlobProcessor::run` handled a blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlobStatus {
+    /// The blob was scanned for matches
+    Scanned,
+
+    /// The blob was not scanned again, as identical content had already been seen
+    Duplicate,
+}
+
+/// A single record of the `--manifest` output
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum ManifestEntry<'a> {
+    /// A blob that was scanned for matches
+    Scanned {
+        blob_id: BlobId,
+        num_bytes: usize,
+        num_matches: usize,
+        provenance: &'a ProvenanceSet,
+    },
+
+    /// A blob that was not scanned again, as identical content had already been seen
+    Duplicate {
+        blob_id: BlobId,
+        num_bytes: usize,
+        provenance: &'a ProvenanceSet,
+    },
+
+    /// A filesystem entry that was not scanned
+    Skipped(&'a SkippedResult),
+
+    /// An input that could not be enumerated, loaded, or scanned
+    Error {
+        /// The path of the input, if it could not be enumerated
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<&'a Path>,
+
+        /// The blob, if it was loaded but could not be scanned
+        #[serde(skip_serializing_if = "Option::is_none")]
+        blob_id: Option<BlobId>,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        provenance: Option<&'a ProvenanceSet>,
+
+        error: String,
+    },
+}
+
+/// A writer for the JSON Lines `--manifest` output, shared among scanner threads
+///
+/// Failing to write the manifest does not affect the scan: the error is logged, and no further
+/// entries are written.
+struct ManifestWriter {
+    path: PathBuf,
+
+    /// The underlying writer, or `None` once writing has failed
+    writer: Mutex<Option<std::io::BufWriter<std::fs::File>>>,
+}
+
+impl ManifestWriter {
+    fn create(path: &Path) -> Result<Self> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create manifest at {}", path.display()))?;
+        Ok(Self {
+            path: path.to_owned(),
+            writer: Mutex::new(Some(std::io::BufWriter::new(file))),
+        })
+    }
+
+    fn write(&self, entry: &ManifestEntry) {
+        use std::io::Write;
+        let mut writer = self.writer.lock().unwrap();
+        let Some(w) = writer.as_mut() else {
+            return;
+        };
+        let result = serde_json::to_writer(&mut *w, entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writeln!(w));
+        if let Err(e) = result {
+            error!(
+                "Failed to write manifest to {}; it will be incomplete: {e}",
+                self.path.display()
+            );
+            *writer = None;
+        }
+    }
+
+    fn finish(self) {
+        use std::io::Write;
+        if let Some(mut w) = self.writer.into_inner().unwrap() {
+            if let Err(e) = w.flush() {
+                error!("Failed to write manifest to {}: {e}", self.path.display());
+            }
+        }
+    }
 }
 
 // --------------------------------------------------------------------------------
@@ -240,7 +538,7 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
         let (cfg, input) = self;
         match input {
-            FoundInput::File(i) => Ok(i.into_blob_iter()?.map(FoundInputIter::File)),
+            FoundInput::File(i) => Ok((cfg, i).into_blob_iter()?.map(FoundInputIter::File)),
 
             FoundInput::Directory(i) => {
                 let path = &i.path;
@@ -256,6 +554,7 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
                                     repository,
                                     &cfg.gitignore,
                                 )
+                                .collect_latest_commits(cfg.collect_git_latest_commits)
                                 .run()?
                             } else {
                                 input_enumerator::GitRepoEnumerator::new(path, repository).run()?
@@ -267,7 +566,7 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
                                 t1.elapsed().as_secs_f64()
                             );
 
-                            result
+                            (cfg, result)
                                 .into_blob_iter()
                                 .map(|i| i.map(FoundInputIter::GitRepo))
                         }
@@ -281,6 +580,8 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
             FoundInput::EnumeratorFile(i) => {
                 Ok(i.into_blob_iter()?.map(FoundInputIter::EnumeratorFile))
             }
+
+            FoundInput::Skipped(_) => Ok(None),
         }
     }
 }
@@ -300,6 +601,16 @@ impl ParallelIterator for FoundInputIter {
     }
 }
 
+/// Get the path of a found input.
+fn found_input_path(input: &FoundInput) -> &Path {
+    match input {
+        FoundInput::File(i) => &i.path,
+        FoundInput::Directory(i) => &i.path,
+        FoundInput::EnumeratorFile(i) => &i.path,
+        FoundInput::Skipped(i) => &i.path,
+    }
+}
+
 // --------------------------------------------------------------------------------
 
 /// This command scans multiple filesystem inputs for secrets.
@@ -334,12 +645,28 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // Open datastore
     // ---------------------------------------------------------------------------------------------
     init_progress.set_message("Initializing (datastore)...");
+    let datastore_path = args.datastore_args.resolve()?;
     let mut datastore =
-        Datastore::create_or_open(&args.datastore, global_args.advanced.sqlite_cache_size)
+        Datastore::create_or_open(&datastore_path, global_args.advanced.sqlite_cache_size)
             .with_context(|| {
-                format!("Failed to open datastore at {}", &args.datastore.display())
+                format!("Failed to open datastore at {}", &datastore_path.display())
             })?;
 
+    // Match IDs computed by different algorithms can't be compared, so all the matches in a
+    // datastore must use the same one
+    let structural_id_algo: StructuralIdAlgo = args.structural_id_algo.into();
+    if let Some(other) = datastore
+        .get_structural_id_algos()?
+        .into_iter()
+        .find(|algo| *algo != structural_id_algo)
+    {
+        bail!(
+            "The datastore at {} contains matches with {other} IDs; \
+             use `--structural-id-algo={other}` or a different datastore",
+            datastore_path.display()
+        );
+    }
+
     // ---------------------------------------------------------------------------------------------
     // Load rules and record them to the datastore
     // ---------------------------------------------------------------------------------------------
@@ -363,6 +690,26 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
         rules_db
     };
+
+    // Rules eligible for matching against paths, if enabled
+    let filename_rules_db = if args.content_filtering_args.scan_filenames {
+        let rules: Vec<_> = rules_db
+            .rules()
+            .iter()
+            .filter(|r| r.syntax().match_filenames)
+            .cloned()
+            .collect();
+        if rules.is_empty() {
+            warn!("No enabled rules set `match_filenames`; paths will not be scanned");
+            None
+        } else {
+            let db =
+                RulesDatabase::from_rules(rules).context("Failed to compile filename rules")?;
+            Some(db)
+        }
+    } else {
+        None
+    };
     drop(init_progress);
 
     // ---------------------------------------------------------------------------------------------
@@ -379,8 +726,17 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // ---------------------------------------------------------------------------------------------
     // Clone or update all mentioned Git URLs; gather set of input roots for scanning
     // ---------------------------------------------------------------------------------------------
+    // The special `-` input refers to content read from stdin rather than to a path
+    let is_stdin = |p: &PathBuf| p.as_path() == Path::new("-");
+    let scan_stdin = args.input_specifier_args.path_inputs.iter().any(is_stdin);
     let input_roots = {
-        let mut input_roots = args.input_specifier_args.path_inputs.clone();
+        let mut input_roots: Vec<PathBuf> = args
+            .input_specifier_args
+            .path_inputs
+            .iter()
+            .filter(|p| !is_stdin(p))
+            .cloned()
+            .collect();
         if !repo_urls.is_empty() {
             input_roots.extend(clone_git_repo_urls(global_args, args, &datastore, repo_urls)?);
         }
@@ -389,10 +745,23 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         input_roots
     };
 
-    if input_roots.is_empty() && args.input_specifier_args.enumerators.is_empty() {
+    if input_roots.is_empty() && args.input_specifier_args.enumerators.is_empty() && !scan_stdin {
         bail!("No inputs to scan");
     }
 
+    let stdin_input = if scan_stdin {
+        use std::io::Read;
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .lock()
+            .read_to_end(&mut bytes)
+            .context("Failed to read input from stdin")?;
+        let provenance = ProvenanceSet::from(Provenance::from_stdin());
+        Some((provenance, Blob::from_bytes(bytes)))
+    } else {
+        None
+    };
+
     // we'll need this later
     let blobs_dir = datastore.blobs_dir();
 
@@ -400,6 +769,7 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // Kick off input enumeration in a separate thread, writing results to a channel
     // ---------------------------------------------------------------------------------------------
     let scan_start = Instant::now();
+    let scan_start_time = SystemTime::now();
     let (enum_thread, input_recv, gitignore) = {
         let (fs_enumerator, gitignore) = make_fs_enumerator(args, &datastore, input_roots)
             .context("Failed to initialize filesystem enumerator")?;
@@ -435,13 +805,22 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // Kick off datastore persistence in a separate thread, providing a channel for scanners to
     // write into. (SQLite works best with a single writer)
     // ---------------------------------------------------------------------------------------------
+    //
+    // The datastore thread sets `match_limit_reached` once `--max-total-matches` is reached, after
+    // which no more inputs are scanned.
+    // ---------------------------------------------------------------------------------------------
+    let match_limit_reached = Arc::new(AtomicBool::new(false));
     let (datastore_thread, send_ds) = {
         let channel_size = std::cmp::max(args.num_jobs, 64) * DATASTORE_BATCH_SIZE;
         let (send_ds, recv_ds) = crossbeam_channel::bounded::<DatastoreMessage>(channel_size);
 
+        let max_total_matches = args.max_total_matches;
+        let match_limit_reached = match_limit_reached.clone();
         let datastore_thread = std::thread::Builder::new()
             .name("datastore".to_string())
-            .spawn(move || datastore_writer(datastore, recv_ds))?;
+            .spawn(move || {
+                datastore_writer(datastore, recv_ds, max_total_matches, &match_limit_reached)
+            })?;
 
         (datastore_thread, send_ds)
     };
@@ -461,16 +840,37 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         },
         collect_git_metadata: match args.metadata_args.git_blob_provenance {
             args::GitBlobProvenanceMode::FirstSeen => true,
+            args::GitBlobProvenanceMode::FirstAndLatest => true,
             args::GitBlobProvenanceMode::Minimal => false,
         },
+        collect_git_latest_commits: args.metadata_args.git_blob_provenance
+            == args::GitBlobProvenanceMode::FirstAndLatest,
         gitignore,
+        mmap_min_size: args.content_filtering_args.mmap_min_size_bytes(),
+        blob_size_limit: BlobSizeLimit {
+            max_size: args.content_filtering_args.max_blob_size_bytes(),
+            num_skipped: Default::default(),
+        },
     };
 
     let t1 = Instant::now();
     let num_blob_processors = Mutex::new(0u64); // how many blob processors have been initialized?
     let matcher_stats = Mutex::new(MatcherStats::default());
     let seen_blobs = BlobIdMap::new();
-    let matcher = Matcher::new(&rules_db, &seen_blobs, Some(&matcher_stats))?;
+    let mut matcher = Matcher::new(&rules_db, &seen_blobs, Some(&matcher_stats))?;
+    if args.rule_profile {
+        matcher.enable_rule_profiling();
+    }
+    if let Some(chunk_size) = args.content_filtering_args.chunk_size_bytes() {
+        matcher
+            .enable_chunking(chunk_size, DEFAULT_CHUNK_OVERLAP)
+            .context("Invalid chunk size")?;
+    }
+    let seen_filenames = BlobIdMap::new();
+    let filename_matcher = filename_rules_db
+        .as_ref()
+        .map(|db| Matcher::new(db, &seen_filenames, None))
+        .transpose()?;
 
     let blob_copier = match args.copy_blobs {
         args::CopyBlobsMode::All | args::CopyBlobsMode::Matching => match args.copy_blobs_format {
@@ -485,15 +885,37 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
     let blob_processor_init_time = Mutex::new(t1.elapsed());
 
+    let coverage = args
+        .content_filtering_args
+        .coverage_report
+        .as_ref()
+        .map(|_| Mutex::new(CoverageReport::default()));
+
+    let manifest = args
+        .manifest
+        .as_deref()
+        .map(ManifestWriter::create)
+        .transpose()?;
+
+    let input_group = args
+        .metadata_args
+        .input_group
+        .as_deref()
+        .map(Provenance::from_input_group);
+
     let make_blob_processor = || -> BlobProcessor {
         let t1 = Instant::now();
         let matcher = matcher.clone();
+        let filename_matcher = filename_matcher.clone();
         *num_blob_processors.lock().unwrap() += 1;
         let guesser = Guesser::new().expect("should be able to create filetype guessser");
         let proc = BlobProcessor {
             matcher,
+            filename_matcher,
             guesser,
             snippet_length: args.snippet_length,
+            snippet_assignment: args.snippet_assignment,
+            structural_id_algo,
             blob_metadata_recording_mode: args.metadata_args.blob_metadata,
             blob_copier: blob_copier.clone(),
             copy_blobs_mode: args.copy_blobs,
@@ -503,39 +925,122 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         proc
     };
 
+    let limit_reached: &AtomicBool = &match_limit_reached;
+    let blob_size_limit: &BlobSizeLimit = &enum_cfg.blob_size_limit;
+    let manifest_writer: Option<&ManifestWriter> = manifest.as_ref();
     let scan_res: Result<()> = input_recv
         .into_iter()
         .par_bridge()
-        .filter_map(|input: FoundInput| match (&enum_cfg, input).into_blob_iter() {
-            Err(e) => {
-                error!("Error enumerating input: {e:#}");
-                None
+        .filter(|_| !limit_reached.load(Ordering::Relaxed))
+        .inspect(|input: &FoundInput| {
+            if let Some(coverage) = &coverage {
+                coverage.lock().unwrap().observe(input);
+            }
+            if let (Some(manifest), FoundInput::Skipped(s)) = (manifest_writer, input) {
+                manifest.write(&ManifestEntry::Skipped(s));
+            }
+        })
+        .filter_map(|input: FoundInput| {
+            let path = manifest_writer.map(|_| found_input_path(&input).to_owned());
+            match (&enum_cfg, input).into_blob_iter() {
+                Err(e) => {
+                    error!("Error enumerating input: {e:#}");
+                    if let (Some(manifest), Some(path)) = (manifest_writer, &path) {
+                        manifest.write(&ManifestEntry::Error {
+                            path: Some(path),
+                            blob_id: None,
+                            provenance: None,
+                            error: format!("{e:#}"),
+                        });
+                    }
+                    None
+                }
+                Ok(blob_iter) => blob_iter,
             }
-            Ok(blob_iter) => blob_iter,
         })
         .flatten()
+        .chain(stdin_input.map(Ok))
         .try_for_each_init(
             || (make_blob_processor(), progress.clone()),
             move |(processor, progress), entry| {
-                let (provenance, blob) = match entry {
+                if limit_reached.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                let (mut provenance, blob) = match entry {
                     Err(e) => {
                         error!("Error loading input: {e:#}");
+                        if let Some(manifest) = manifest_writer {
+                            manifest.write(&ManifestEntry::Error {
+                                path: None,
+                                blob_id: None,
+                                provenance: None,
+                                error: format!("{e:#}"),
+                            });
+                        }
                         return Ok(());
                     }
                     Ok(entry) => entry,
                 };
+                // Blobs from files and Git repositories have already been checked before loading
+                if !blob_size_limit.allows(blob.len() as u64, format_args!("blob {}", blob.id)) {
+                    return Ok(());
+                }
+                if let Some(input_group) = &input_group {
+                    provenance.push(input_group.clone());
+                }
 
                 progress.inc(blob.len().try_into().unwrap());
-                match processor.run(provenance, blob) {
+                match processor.run_filenames(&provenance, &blob) {
+                    Err(e) => {
+                        error!("Error scanning input paths: {e:#}");
+                    }
+                    Ok(msgs) => {
+                        for msg in msgs {
+                            send_ds.send(msg)?;
+                        }
+                    }
+                }
+                let blob_id = blob.id;
+                let num_bytes = blob.len();
+                let manifest = manifest_writer.map(|m| (m, provenance.clone()));
+                let (status, num_matches) = match processor.run(provenance, blob) {
                     Err(e) => {
                         error!("Error scanning input: {e:#}");
+                        if let Some((manifest, provenance)) = &manifest {
+                            manifest.write(&ManifestEntry::Error {
+                                path: None,
+                                blob_id: Some(blob_id),
+                                provenance: Some(provenance),
+                                error: format!("{e:#}"),
+                            });
+                        }
+                        return Ok(());
                     }
-                    Ok(None) => {
+                    Ok((status, None)) => {
                         // nothing to record
+                        (status, 0)
                     }
-                    Ok(Some(msg)) => {
+                    Ok((status, Some(msg))) => {
+                        let num_matches = msg.2.len();
                         send_ds.send(msg)?;
+                        (status, num_matches)
                     }
+                };
+                if let Some((manifest, provenance)) = &manifest {
+                    let entry = match status {
+                        BlobStatus::Scanned => ManifestEntry::Scanned {
+                            blob_id,
+                            num_bytes,
+                            num_matches,
+                            provenance,
+                        },
+                        BlobStatus::Duplicate => ManifestEntry::Duplicate {
+                            blob_id,
+                            num_bytes,
+                            provenance,
+                        },
+                    };
+                    manifest.write(&entry);
                 }
                 Ok(())
             },
@@ -561,7 +1066,24 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
     progress.finish();
 
-    datastore.check_match_redundancies()?;
+    if let Some(manifest) = manifest {
+        manifest.finish();
+    }
+
+    // Matches recorded before this scan were checked by the scan that recorded them, so only
+    // the blobs with new matches need to be checked; `datastore analyze` redoes the full check
+    datastore.check_match_redundancies_since(scan_start_time)?;
+
+    let regressed = datastore
+        .get_regressed_finding_ids(Some(scan_start_time))
+        .context("Failed to check for regressed findings")?;
+    if !regressed.is_empty() {
+        warn!(
+            "Found new matches for {} whose matches had all been rejected; \
+             run `report --finding-status=regression` to show them",
+            Counted::regular(regressed.len(), "finding"),
+        );
+    }
 
     // ---------------------------------------------------------------------------------------------
     // Finalize and report
@@ -576,49 +1098,93 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
         drop(matcher);
         let matcher_stats = matcher_stats.into_inner()?;
-        let scan_duration = scan_start.elapsed();
-        let seen_bytes_per_sec =
-            (matcher_stats.bytes_seen as f64 / scan_duration.as_secs_f64()) as u64;
+        let datastore_stats = datastore
+            .stats()
+            .context("Failed to get datastore statistics")?;
+        let stats = ScanStats {
+            blobs_scanned: matcher_stats.blobs_seen,
+            bytes_scanned: matcher_stats.bytes_seen,
+            new_matches: num_new_matches,
+            total_matches: num_matches,
+            total_findings: datastore_stats.num_findings,
+            oversize_blobs_skipped: enum_cfg.blob_size_limit.num_skipped.load(Ordering::Relaxed),
+            elapsed_secs: scan_start.elapsed().as_secs_f64(),
+            datastore: datastore_stats,
+        };
+        println!("{stats}");
 
-        println!(
-            "Scanned {} from {} blobs in {} ({}/s); {}/{} new matches",
-            HumanBytes(matcher_stats.bytes_seen),
-            HumanCount(matcher_stats.blobs_seen),
-            HumanDuration(scan_duration),
-            HumanBytes(seen_bytes_per_sec),
-            HumanCount(num_new_matches),
-            HumanCount(num_matches),
-        );
+        if stats.oversize_blobs_skipped > 0 {
+            println!(
+                "\nSkipped {} larger than the maximum blob size",
+                Counted::regular(stats.oversize_blobs_skipped as usize, "blob"),
+            );
+        }
 
-        #[cfg(feature = "rule_profiling")]
-        {
-            println!("Rule stats:");
-            let mut entries = matcher_stats.rule_stats.get_entries();
-            entries.retain(|e| e.raw_match_count > 0);
-            entries.sort_by_key(|e| e.stage2_duration);
-            entries.reverse();
-            for entry in entries {
-                let rule_name = &rules_db
-                    .get_rule(entry.rule_id)
-                    .expect("rule index should be valid")
-                    .name();
+        if let Some(stats_path) = &args.stats_json {
+            stats.write_json(stats_path).with_context(|| {
+                format!("Failed to write scan statistics to {}", stats_path.display())
+            })?;
+        }
+
+        if let Some(max_total_matches) = args.max_total_matches {
+            if match_limit_reached.load(Ordering::Relaxed) {
                 println!(
-                    "{:>50} {:>10} {:>10.4}s",
-                    rule_name,
-                    entry.raw_match_count,
-                    entry.stage2_duration.as_secs_f64()
+                    "\nStopped scanning after reaching the limit of {} total matches; \
+                     results are incomplete",
+                    HumanCount(max_total_matches)
                 );
             }
         }
 
+        if let Some(rule_stats) = &matcher_stats.rule_stats {
+            println!();
+            rule_profile_table(&rules_db, rule_stats)
+                .print_tty(global_args.use_color(std::io::stdout()))?;
+        }
+
+        if let (Some(coverage), Some(coverage_path)) =
+            (coverage, &args.content_filtering_args.coverage_report)
+        {
+            let mut coverage = coverage.into_inner()?;
+            coverage.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+
+            let write_report = || -> Result<()> {
+                use std::io::Write;
+                let mut writer = std::io::BufWriter::new(std::fs::File::create(coverage_path)?);
+                serde_json::to_writer_pretty(&mut writer, &coverage)?;
+                writeln!(writer)?;
+                writer.flush()?;
+                Ok(())
+            };
+            write_report().with_context(|| {
+                format!("Failed to write coverage report to {}", coverage_path.display())
+            })?;
+
+            println!(
+                "\nScanned {} files from the filesystem; skipped {} entries",
+                HumanCount(coverage.num_files_scanned),
+                HumanCount(coverage.skipped.len() as u64),
+            );
+            if !coverage.skipped.is_empty() {
+                println!();
+                coverage
+                    .table()
+                    .print_tty(global_args.use_color(std::io::stdout()))?;
+            }
+        }
+
         if num_matches > 0 {
             let summary = datastore
                 .get_summary()
                 .context("Failed to get finding summary")
                 .unwrap();
-            let table = crate::cmd_summarize::summary_table(&summary, /* simple= */ true);
+            let table = crate::cmd_summarize::summary_table(
+                &summary,
+                /* simple= */ true,
+                global_args.use_color(std::io::stdout()),
+            );
             println!();
-            table.print_tty(global_args.use_color(std::io::stdout()))?;
+            table.print(&mut std::io::stdout())?;
         }
 
         println!("\nRun the `report` command next to show finding details.");
@@ -627,6 +1193,48 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     Ok(())
 }
 
+/// Build a table of per-rule profiling statistics, with the slowest rules first.
+fn rule_profile_table(
+    rules_db: &RulesDatabase,
+    rule_stats: &noseyparker::rule_profiling::RuleProfile,
+) -> prettytable::Table {
+    use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
+    use prettytable::row;
+
+    let mut entries = rule_stats.get_entries();
+    entries.retain(|e| e.raw_match_count > 0);
+    entries.sort_by_key(|e| std::cmp::Reverse(e.stage2_duration));
+
+    let f = FormatBuilder::new()
+        .column_separator(' ')
+        .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
+        .padding(1, 1)
+        .build();
+
+    let mut table: prettytable::Table = entries
+        .iter()
+        .map(|e| {
+            let rule = rules_db
+                .get_rule(e.rule_id)
+                .expect("rule index should be valid");
+            row![
+                l -> rule.name(),
+                l -> rule.id(),
+                r -> HumanCount(e.raw_match_count),
+                r -> format!("{:.4}s", e.stage2_duration.as_secs_f64()),
+            ]
+        })
+        .collect();
+    table.set_format(f);
+    table.set_titles(row![
+        lb -> "Rule",
+        lb -> "ID",
+        cb -> "Raw Matches",
+        cb -> "Match Time",
+    ]);
+    table
+}
+
 #[derive(Clone)]
 enum BlobCopier {
     Noop,
@@ -826,16 +1434,91 @@ impl MetadataResult {
 /// one `scan` run
 struct BlobProcessor<'a> {
     matcher: Matcher<'a>,
+
+    /// A matcher for the paths of scanned blobs, if enabled
+    filename_matcher: Option<Matcher<'a>>,
+
     guesser: Guesser,
 
     snippet_length: usize,
+
+    /// Whether to extend snippets to cover `KEY = value` assignments
+    snippet_assignment: bool,
+
+    /// The algorithm used to compute match IDs
+    structural_id_algo: StructuralIdAlgo,
+
     blob_metadata_recording_mode: args::BlobMetadataMode,
     copy_blobs_mode: args::CopyBlobsMode,
     blob_copier: BlobCopier,
 }
 
 impl<'a> BlobProcessor<'a> {
-    fn run(&mut self, provenance: ProvenanceSet, blob: Blob) -> Result<Option<DatastoreMessage>> {
+    /// Match the filename rules against the paths given in the provenance entries of a blob.
+    ///
+    /// A path is not part of the blob's content, so matches in it are recorded in the blob with an
+    /// empty span at its start. Their provenance notes the path that was matched, along with the
+    /// entries of the blob's provenance that have no path, such as its input group.
+    fn run_filenames(
+        &mut self,
+        provenance: &ProvenanceSet,
+        blob: &Blob,
+    ) -> Result<Vec<DatastoreMessage>> {
+        let matcher = match self.filename_matcher.as_mut() {
+            None => return Ok(Vec::new()),
+            Some(matcher) => matcher,
+        };
+
+        let mut msgs = Vec::new();
+        for path in provenance.iter().filter_map(|p| p.blob_path()) {
+            let path_blob = Blob::from_bytes(path.as_os_str().as_encoded_bytes().to_vec());
+            let path_provenance = ProvenanceSet::try_from_iter(
+                std::iter::once(Provenance::from_filename_match(path)).chain(
+                    provenance
+                        .iter()
+                        .filter(|p| p.blob_path().is_none())
+                        .cloned(),
+                ),
+            )
+            .expect("provenance should be non-empty");
+            let matches = match matcher.scan_blob(&path_blob, &path_provenance)? {
+                ScanResult::New(matches) if !matches.is_empty() => matches,
+                _ => continue,
+            };
+
+            let loc_mapping = location::LocationMapping::new(&path_blob.bytes);
+            let start = location::SourcePoint { line: 1, column: 0 };
+            let matches = matches
+                .iter()
+                .map(|m| {
+                    // The structural ID is still computed from the path, so that matches in
+                    // different paths of the same blob are distinct
+                    let mut m = Match::convert(&loc_mapping, m, 0, None, self.structural_id_algo);
+                    m.blob_id = blob.id;
+                    m.location = location::Location {
+                        offset_span: location::OffsetSpan { start: 0, end: 0 },
+                        source_span: location::SourceSpan { start, end: start },
+                    };
+                    m.snippet.matching.clear();
+                    (None, m)
+                })
+                .collect();
+            let metadata = BlobMetadata {
+                id: blob.id,
+                num_bytes: blob.len(),
+                mime_essence: None,
+                charset: None,
+            };
+            msgs.push((path_provenance, metadata, matches));
+        }
+        Ok(msgs)
+    }
+
+    fn run(
+        &mut self,
+        provenance: ProvenanceSet,
+        blob: Blob,
+    ) -> Result<(BlobStatus, Option<DatastoreMessage>)> {
         let blob_id = blob.id.hex();
         let _span = error_span!("matcher", blob_id, bytes = blob.len()).entered();
 
@@ -853,7 +1536,7 @@ impl<'a> BlobProcessor<'a> {
             // blob already seen, but with no matches; nothing to do!
             ScanResult::SeenSansMatches => {
                 trace!(us = scan_us, mbps = scan_mbps, status = "seen_nomatch");
-                Ok(None)
+                Ok((BlobStatus::Duplicate, None))
             }
 
             // blob already seen; all we need to do is record its provenance
@@ -865,7 +1548,7 @@ impl<'a> BlobProcessor<'a> {
                     mime_essence: None,
                     charset: None,
                 };
-                Ok(Some((provenance, metadata, Vec::new())))
+                Ok((BlobStatus::Duplicate, Some((provenance, metadata, Vec::new()))))
             }
 
             // blob has not been seen; need to record blob metadata, provenance, and matches
@@ -889,7 +1572,7 @@ impl<'a> BlobProcessor<'a> {
                 if self.blob_metadata_recording_mode != args::BlobMetadataMode::All
                     && matches.is_empty()
                 {
-                    return Ok(None);
+                    return Ok((BlobStatus::Scanned, None));
                 }
 
                 let metadata = match self.blob_metadata_recording_mode {
@@ -923,14 +1606,26 @@ impl<'a> BlobProcessor<'a> {
                     Some(max_end) => {
                         // compute the location mapping only on the input that's necessary to look at
                         let loc_mapping = location::LocationMapping::new(&blob.bytes[0..max_end]);
+                        let syntax = provenance
+                            .iter()
+                            .find_map(|p| p.blob_path())
+                            .and_then(LexicalSyntax::from_path);
 
                         let capacity: usize = matches.iter().map(|m| m.captures.len() - 1).sum();
                         let mut new_matches = Vec::with_capacity(capacity);
-                        new_matches.extend(
-                            matches.iter().map(|m| {
-                                (None, Match::convert(&loc_mapping, m, self.snippet_length))
-                            }),
-                        );
+                        new_matches.extend(matches.iter().map(|m| {
+                            let mut m = Match::convert(
+                                &loc_mapping,
+                                m,
+                                self.snippet_length,
+                                syntax.as_ref(),
+                                self.structural_id_algo,
+                            );
+                            if self.snippet_assignment {
+                                m.extend_snippet_to_assignment(&blob.bytes);
+                            }
+                            (Some(score_match(&m)), m)
+                        }));
                         new_matches
                     }
                     None => {
@@ -939,7 +1634,7 @@ impl<'a> BlobProcessor<'a> {
                     }
                 };
 
-                Ok(Some((provenance, metadata, matches)))
+                Ok((BlobStatus::Scanned, Some((provenance, metadata, matches))))
             }
         }
     }
@@ -983,6 +1678,18 @@ fn make_fs_enumerator(
 
         ie.threads(args.num_jobs);
         ie.max_filesize(args.content_filtering_args.max_file_size_bytes());
+        ie.max_depth(args.content_filtering_args.max_depth);
+        let include = &args.content_filtering_args.include;
+        if !include.is_empty() {
+            ie.include_globs(Some(build_glob_set(include).context("Invalid include globs")?));
+        }
+        ie.exclude_globs(
+            build_glob_set(&args.content_filtering_args.exclude)
+                .context("Invalid exclude globs")?,
+        );
+        ie.report_skipped(
+            args.content_filtering_args.coverage_report.is_some() || args.manifest.is_some(),
+        );
         if args.input_specifier_args.git_history == args::GitHistoryMode::None {
             ie.enumerate_git_history(false);
         }
@@ -1002,6 +1709,7 @@ fn make_fs_enumerator(
         // Determine whether to collect git metadata or not
         let collect_git_metadata = match args.metadata_args.git_blob_provenance {
             args::GitBlobProvenanceMode::FirstSeen => true,
+            args::GitBlobProvenanceMode::FirstAndLatest => true,
             args::GitBlobProvenanceMode::Minimal => false,
         };
         ie.collect_git_metadata(collect_git_metadata);
@@ -1010,6 +1718,15 @@ fn make_fs_enumerator(
     }
 }
 
+/// Combine the given globs into a single set.
+fn build_glob_set(globs: &[input_enumerator::Glob]) -> Result<input_enumerator::GlobSet> {
+    let mut builder = input_enumerator::GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(glob.clone());
+    }
+    Ok(builder.build()?)
+}
+
 // -------------------------------------------------------------------------------------------------
 /// Enumerate mentioned GitHub repositories via the GitHub REST API, returning vector of repo urls
 #[cfg(feature = "github")]
@@ -1092,6 +1809,8 @@ const DATASTORE_COMMIT_INTERVAL: Duration = Duration::from_secs(1);
 fn datastore_writer(
     mut datastore: Datastore,
     recv_ds: crossbeam_channel::Receiver<DatastoreMessage>,
+    max_total_matches: Option<u64>,
+    match_limit_reached: &AtomicBool,
 ) -> Result<(Datastore, u64, u64)> {
     let _span = error_span!("datastore", "{}", datastore.root_dir().display()).entered();
     let mut total_recording_time: std::time::Duration = Default::default();
@@ -1103,14 +1822,33 @@ fn datastore_writer(
     let mut matches_in_batch: usize = 0;
     let mut last_commit_time = Instant::now();
 
+    let num_initial_matches = match max_total_matches {
+        Some(_) => datastore.get_num_matches()?,
+        None => 0,
+    };
+    if max_total_matches.is_some_and(|max| num_initial_matches >= max) {
+        match_limit_reached.store(true, Ordering::Relaxed);
+    }
+
     for message in recv_ds {
+        // Once the match limit is reached, drain the channel without recording anything more
+        if match_limit_reached.load(Ordering::Relaxed) {
+            continue;
+        }
+
         total_messages += 1;
         matches_in_batch += message.2.len();
         batch.push(message);
 
+        // Commit early when the batch might reach the match limit, so it is not overshot by much
+        let might_reach_limit = max_total_matches.is_some_and(|max| {
+            num_initial_matches + num_matches_added + matches_in_batch as u64 >= max
+        });
+
         if batch.len() >= DATASTORE_BATCH_SIZE
             || matches_in_batch >= DATASTORE_BATCH_SIZE
             || last_commit_time.elapsed() >= DATASTORE_COMMIT_INTERVAL
+            || might_reach_limit
         {
             let t1 = std::time::Instant::now();
             let batch_len = batch.len();
@@ -1123,6 +1861,9 @@ fn datastore_writer(
             num_matches_added += num_added;
             batch.clear();
             matches_in_batch = 0;
+            if max_total_matches.is_some_and(|max| num_initial_matches + num_matches_added >= max) {
+                match_limit_reached.store(true, Ordering::Relaxed);
+            }
             let elapsed = t1.elapsed();
             trace!(
                 "Recorded {num_added} matches from {batch_len} messages in {:.6}s",
@@ -1,14 +1,16 @@
 use anyhow::{Context, Result};
 use indicatif::HumanCount;
+use std::collections::HashSet;
 
-use noseyparker::datastore::{Datastore, FindingSummary};
+use noseyparker::datastore::{Datastore, FindingSummary, FindingSummaryEntry};
 
-use crate::args::{GlobalArgs, SummarizeArgs, SummarizeOutputFormat};
+use crate::args::{GlobalArgs, SummarizeArgs, SummarizeOutputFormat, SummarizeSortKey};
 use crate::reportable::Reportable;
 
 struct FindingSummaryReporter {
     summary: FindingSummary,
     simple: bool,
+    styles_enabled: bool,
 }
 
 impl Reportable for FindingSummaryReporter {
@@ -19,6 +21,7 @@ impl Reportable for FindingSummaryReporter {
             SummarizeOutputFormat::Human => self.human_format(writer),
             SummarizeOutputFormat::Json => self.json_format(writer),
             SummarizeOutputFormat::Jsonl => self.jsonl_format(writer),
+            SummarizeOutputFormat::Csv => self.csv_format(writer),
         }
     }
 }
@@ -26,8 +29,7 @@ impl Reportable for FindingSummaryReporter {
 impl FindingSummaryReporter {
     fn human_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
         writeln!(writer)?;
-        // FIXME: this doesn't preserve ANSI styling on the table
-        summary_table(&self.summary, self.simple).print(&mut writer)?;
+        summary_table(&self.summary, self.simple, self.styles_enabled).print(&mut writer)?;
         Ok(())
     }
 
@@ -43,30 +45,167 @@ impl FindingSummaryReporter {
         }
         Ok(())
     }
+
+    fn csv_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "rule,findings,matches,accepted,rejected,needs_review,mixed,unlabeled")?;
+        for e in self.summary.0.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                csv_field(&e.rule_name),
+                e.distinct_count,
+                e.total_count,
+                e.accept_count,
+                e.reject_count,
+                e.needs_review_count,
+                e.mixed_count,
+                e.unlabeled_count,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Format a value as a CSV field, quoting it if necessary.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
 }
 
 pub fn run(global_args: &GlobalArgs, args: &SummarizeArgs) -> Result<()> {
-    let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
-        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
-    let output = args
+    let datastore_path = args.datastore_args.resolve()?;
+    let datastore = Datastore::open(&datastore_path, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", datastore_path.display()))?;
+    let mut output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
-    let summary = datastore
+    let mut summary = datastore
         .get_summary()
         .context("Failed to get finding summary")
         .unwrap();
+    sort_summary(&mut summary, args.sort_by);
+    if let Some(n) = args.top {
+        keep_top_rules(&mut summary, n);
+    }
+
+    // enable output styling:
+    // - if the output destination is not explicitly specified and colors are not disabled
+    // - if the output destination *is* explicitly specified and colors are forced on
+    let styles_enabled = if args.output_args.output.is_none() {
+        global_args.use_color(std::io::stdout())
+    } else {
+        global_args.color == crate::args::Mode::Always
+    };
+
     FindingSummaryReporter {
         simple: false,
         summary,
+        styles_enabled,
     }
-    .report(args.output_args.format, output)
+    .report(args.output_args.format, &mut output)?;
+    output.finish().context("Failed to finish writing output")
 }
 
-pub(crate) fn summary_table(summary: &FindingSummary, simple: bool) -> prettytable::Table {
+/// Sort the entries of a summary by the given key.
+///
+/// Rule names are sorted in ascending order, and counts in descending order, with ties broken by
+/// rule name.
+fn sort_summary(summary: &mut FindingSummary, sort_by: SummarizeSortKey) {
+    let count = |e: &FindingSummaryEntry| match sort_by {
+        SummarizeSortKey::Rule => 0,
+        SummarizeSortKey::Findings => e.distinct_count,
+        SummarizeSortKey::Total => e.total_count,
+        SummarizeSortKey::Unlabeled => e.unlabeled_count,
+    };
+    summary.0.sort_by(|a, b| {
+        count(b)
+            .cmp(&count(a))
+            .then_with(|| a.rule_name.cmp(&b.rule_name))
+    });
+}
+
+/// The rule name used for the entry that combines the rules left out by `keep_top_rules`
+const OTHER_RULES_NAME: &str = "Other rules";
+
+/// Keep only the entries for the `n` rules with the most matches, in their existing order, and
+/// combine the remaining entries into a single final entry.
+fn keep_top_rules(summary: &mut FindingSummary, n: usize) {
+    let mut ranked: Vec<&FindingSummaryEntry> = summary.0.iter().collect();
+    ranked.sort_by(|a, b| {
+        b.total_count
+            .cmp(&a.total_count)
+            .then_with(|| a.rule_name.cmp(&b.rule_name))
+    });
+    let top: HashSet<String> = ranked.iter().take(n).map(|e| e.rule_name.clone()).collect();
+
+    let (kept, others): (Vec<_>, Vec<_>) = std::mem::take(&mut summary.0)
+        .into_iter()
+        .partition(|e| top.contains(&e.rule_name));
+    summary.0 = kept;
+    if others.is_empty() {
+        return;
+    }
+
+    let mut combined = FindingSummaryEntry {
+        rule_name: OTHER_RULES_NAME.to_string(),
+        distinct_count: 0,
+        total_count: 0,
+        accept_count: 0,
+        reject_count: 0,
+        needs_review_count: 0,
+        mixed_count: 0,
+        unlabeled_count: 0,
+    };
+    for e in others {
+        combined.distinct_count += e.distinct_count;
+        combined.total_count += e.total_count;
+        combined.accept_count += e.accept_count;
+        combined.reject_count += e.reject_count;
+        combined.needs_review_count += e.needs_review_count;
+        combined.mixed_count += e.mixed_count;
+        combined.unlabeled_count += e.unlabeled_count;
+    }
+    summary.0.push(combined);
+}
+
+/// Build a table summarizing findings by rule.
+///
+/// If `styles_enabled` is true, the cell contents are styled with ANSI escape sequences, so that
+/// the styling is preserved however the table is printed.
+pub(crate) fn summary_table(
+    summary: &FindingSummary,
+    simple: bool,
+    styles_enabled: bool,
+) -> prettytable::Table {
+    use console::Style;
     use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
     use prettytable::row;
 
+    let style_heading = Style::new().bold().force_styling(styles_enabled);
+    let style_rule = Style::new()
+        .bright()
+        .bold()
+        .blue()
+        .force_styling(styles_enabled);
+    let style_accept = Style::new().green().force_styling(styles_enabled);
+    let style_reject = Style::new().red().force_styling(styles_enabled);
+    let style_needs_review = Style::new().yellow().force_styling(styles_enabled);
+    let style_mixed = Style::new().magenta().force_styling(styles_enabled);
+
+    let count = |n: usize| HumanCount(n.try_into().unwrap());
+    // Only nonzero status counts are styled, so that they stand out
+    let status_count = |style: &Style, n: usize| {
+        if n == 0 {
+            count(n).to_string()
+        } else {
+            style.apply_to(count(n)).to_string()
+        }
+    };
+
     let f = FormatBuilder::new()
         .column_separator(' ')
         .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
@@ -79,17 +218,17 @@ pub(crate) fn summary_table(summary: &FindingSummary, simple: bool) -> prettytab
             .iter()
             .map(|e| {
                 row![
-                     l -> &e.rule_name,
-                     r -> HumanCount(e.distinct_count.try_into().unwrap()),
-                     r -> HumanCount(e.total_count.try_into().unwrap()),
+                     l -> style_rule.apply_to(&e.rule_name),
+                     r -> count(e.distinct_count),
+                     r -> count(e.total_count),
                 ]
             })
             .collect();
         table.set_format(f);
         table.set_titles(row![
-            lb -> "Rule",
-            cb -> "Findings",
-            cb -> "Matches",
+            l -> style_heading.apply_to("Rule"),
+            c -> style_heading.apply_to("Findings"),
+            c -> style_heading.apply_to("Matches"),
         ]);
         table
     } else {
@@ -98,25 +237,27 @@ pub(crate) fn summary_table(summary: &FindingSummary, simple: bool) -> prettytab
             .iter()
             .map(|e| {
                 row![
-                     l -> &e.rule_name,
-                     r -> HumanCount(e.distinct_count.try_into().unwrap()),
-                     r -> HumanCount(e.total_count.try_into().unwrap()),
-                     r -> HumanCount(e.accept_count.try_into().unwrap()),
-                     r -> HumanCount(e.reject_count.try_into().unwrap()),
-                     r -> HumanCount(e.mixed_count.try_into().unwrap()),
-                     r -> HumanCount(e.unlabeled_count.try_into().unwrap()),
+                     l -> style_rule.apply_to(&e.rule_name),
+                     r -> count(e.distinct_count),
+                     r -> count(e.total_count),
+                     r -> status_count(&style_accept, e.accept_count),
+                     r -> status_count(&style_reject, e.reject_count),
+                     r -> status_count(&style_needs_review, e.needs_review_count),
+                     r -> status_count(&style_mixed, e.mixed_count),
+                     r -> count(e.unlabeled_count),
                 ]
             })
             .collect();
         table.set_format(f);
         table.set_titles(row![
-            lb -> "Rule",
-            cb -> "Findings",
-            cb -> "Matches",
-            cb -> "Accepted",
-            cb -> "Rejected",
-            cb -> "Mixed",
-            cb -> "Unlabeled",
+            l -> style_heading.apply_to("Rule"),
+            c -> style_heading.apply_to("Findings"),
+            c -> style_heading.apply_to("Matches"),
+            c -> style_heading.apply_to("Accepted"),
+            c -> style_heading.apply_to("Rejected"),
+            c -> style_heading.apply_to("Needs Review"),
+            c -> style_heading.apply_to("Mixed"),
+            c -> style_heading.apply_to("Unlabeled"),
         ]);
         table
     }
@@ -17,6 +17,7 @@ mod cmd_summarize;
 mod reportable;
 mod rule_loader;
 mod util;
+mod workspace;
 
 use args::{CommandLineArgs, GlobalArgs};
 
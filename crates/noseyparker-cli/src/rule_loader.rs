@@ -1,7 +1,7 @@
 use anyhow::{anyhow, bail, Context, Result};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use noseyparker::defaults::get_builtin_rules;
 use noseyparker_rules::{Rule, Rules, RulesetSyntax};
@@ -13,6 +13,7 @@ pub struct RuleLoader {
     load_builtins: bool,
     additional_load_paths: Vec<PathBuf>,
     enabled_ruleset_ids: Vec<String>,
+    normalize_rule_ids: bool,
 }
 
 impl RuleLoader {
@@ -23,6 +24,7 @@ impl RuleLoader {
             load_builtins: true,
             additional_load_paths: Vec::new(),
             enabled_ruleset_ids: Vec::new(),
+            normalize_rule_ids: true,
         }
     }
 
@@ -48,6 +50,13 @@ impl RuleLoader {
         self
     }
 
+    /// Control whether the IDs of loaded rules, and the rule IDs referenced by loaded rulesets, are
+    /// normalized; see `normalize_rule_id`.
+    pub fn normalize_rule_ids(mut self, normalize_rule_ids: bool) -> Self {
+        self.normalize_rule_ids = normalize_rule_ids;
+        self
+    }
+
     /// Load rules according to this loader's configuration.
     pub fn load(&self) -> Result<LoadedRules> {
         let mut rules = Rules::new();
@@ -70,6 +79,21 @@ impl RuleLoader {
 
         let (mut rules, mut rulesets) = (rules.rules, rules.rulesets);
 
+        if self.normalize_rule_ids {
+            for rule in rules.iter_mut() {
+                let id = normalize_rule_id(&rule.id);
+                if id != rule.id {
+                    warn!("Normalized rule ID `{}` ({}) to `{id}`", rule.id, rule.name);
+                    rule.id = id;
+                }
+            }
+            for ruleset in rulesets.iter_mut() {
+                for rule_id in ruleset.include_rule_ids.iter_mut() {
+                    *rule_id = normalize_rule_id(rule_id);
+                }
+            }
+        }
+
         rules.sort_by(|r1, r2| r1.id.cmp(&r2.id));
         rulesets.sort_by(|r1, r2| r1.id.cmp(&r2.id));
 
@@ -93,6 +117,7 @@ impl RuleLoader {
             .load_builtins(specs.load_builtins)
             .additional_rule_load_paths(specs.rules_path.as_slice())
             .enable_ruleset_ids(specs.ruleset.iter())
+            .normalize_rule_ids(!specs.no_normalize_rule_ids)
     }
 }
 
@@ -213,3 +238,12 @@ fn sort_and_deduplicate_rules(rules: &mut Vec<&Rule>) {
     rules.sort_by(|r1, r2| r1.id().cmp(r2.id()));
     rules.dedup_by(|r1, r2| r1.id() == r2.id());
 }
+
+/// Normalize a rule ID: surrounding whitespace is removed, letters are lowercased, and each run of
+/// internal whitespace is replaced with a hyphen.
+fn normalize_rule_id(id: &str) -> String {
+    id.split_whitespace()
+        .map(|part| part.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
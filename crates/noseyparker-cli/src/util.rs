@@ -1,7 +1,9 @@
 use std::fs::File;
-use std::io::{stdin, stdout, BufReader, BufWriter};
+use std::io::{stdin, stdout, BufReader, BufWriter, LineWriter, Write};
 use std::path::Path;
 
+use crate::args::OutputCompression;
+
 /// A utility type to generate properly pluralized count expressions in log messages,
 /// e.g., "1 rule" or "7 rules", without copying data.
 pub enum Counted<'a> {
@@ -74,6 +76,131 @@ pub fn get_writer_for_file_or_stdout<P: AsRef<Path>>(
     }
 }
 
+/// Get a line-buffered writer for the file at the specified output destination, or stdout if not
+/// specified.
+///
+/// Output is flushed after each newline is written, which is useful when the output is consumed
+/// in real time by another program.
+pub fn get_line_writer_for_file_or_stdout<P: AsRef<Path>>(
+    path: Option<P>,
+) -> std::io::Result<Box<dyn std::io::Write>> {
+    match path.as_ref() {
+        None => Ok(Box::new(LineWriter::new(stdout()))),
+        Some(p) => {
+            let f = File::create(p)?;
+            Ok(Box::new(LineWriter::new(f)))
+        }
+    }
+}
+
+/// A writer for command output, possibly compressing what is written to it.
+///
+/// `finish` must be called once all output has been written: a compressed stream is incomplete
+/// until then, and errors from writing the last of the output are only reported by it.
+pub struct OutputWriter(OutputWriterInner);
+
+enum OutputWriterInner {
+    Uncompressed(Box<dyn Write>),
+    Compressed(Compressor),
+    LineBufferedCompressed(LineWriter<Compressor>),
+}
+
+impl OutputWriter {
+    /// Finish the output, writing the end of any compressed stream and flushing everything to the
+    /// underlying destination.
+    pub fn finish(self) -> std::io::Result<()> {
+        let mut inner = match self.0 {
+            OutputWriterInner::Uncompressed(w) => w,
+            OutputWriterInner::Compressed(c) => c.finish()?,
+            OutputWriterInner::LineBufferedCompressed(w) => {
+                w.into_inner().map_err(|e| e.into_error())?.finish()?
+            }
+        };
+        inner.flush()
+    }
+}
+
+impl From<Box<dyn Write>> for OutputWriter {
+    fn from(w: Box<dyn Write>) -> Self {
+        OutputWriter(OutputWriterInner::Uncompressed(w))
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.0 {
+            OutputWriterInner::Uncompressed(w) => w.write(buf),
+            OutputWriterInner::Compressed(w) => w.write(buf),
+            OutputWriterInner::LineBufferedCompressed(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.0 {
+            OutputWriterInner::Uncompressed(w) => w.flush(),
+            OutputWriterInner::Compressed(w) => w.flush(),
+            OutputWriterInner::LineBufferedCompressed(w) => w.flush(),
+        }
+    }
+}
+
+enum Compressor {
+    Gzip(flate2::write::GzEncoder<Box<dyn Write>>),
+    Zstd(zstd::Encoder<'static, Box<dyn Write>>),
+}
+
+impl Compressor {
+    /// Write the end of the compressed stream, returning the underlying writer.
+    fn finish(self) -> std::io::Result<Box<dyn Write>> {
+        match self {
+            Compressor::Gzip(e) => e.finish(),
+            Compressor::Zstd(e) => e.finish(),
+        }
+    }
+}
+
+impl Write for Compressor {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Compressor::Gzip(e) => e.write(buf),
+            Compressor::Zstd(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Compressor::Gzip(e) => e.flush(),
+            Compressor::Zstd(e) => e.flush(),
+        }
+    }
+}
+
+/// Get a compressing writer for the file at the specified output destination, or stdout if not
+/// specified.
+///
+/// The compressed stream is only complete once `OutputWriter::finish` has been called. If
+/// `line_buffered` is true, output is flushed through the compressor after each newline is
+/// written.
+pub fn get_compressed_writer_for_file_or_stdout<P: AsRef<Path>>(
+    path: Option<P>,
+    compression: OutputCompression,
+    line_buffered: bool,
+) -> std::io::Result<OutputWriter> {
+    let inner = get_writer_for_file_or_stdout(path)?;
+    let compressor = match compression {
+        OutputCompression::Gzip => {
+            Compressor::Gzip(flate2::write::GzEncoder::new(inner, Default::default()))
+        }
+        OutputCompression::Zstd => Compressor::Zstd(zstd::Encoder::new(inner, 0)?),
+    };
+    let inner = if line_buffered {
+        OutputWriterInner::LineBufferedCompressed(LineWriter::new(compressor))
+    } else {
+        OutputWriterInner::Compressed(compressor)
+    };
+    Ok(OutputWriter(inner))
+}
+
 /// Get a buffered reader for the file at the specified input source, or stdin if not specified.
 pub fn get_reader_for_file_or_stdin<P: AsRef<Path>>(
     path: Option<P>,
@@ -0,0 +1,86 @@
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Get the base directory under which named workspace datastores are stored.
+///
+/// This is `$XDG_DATA_HOME/noseyparker` if `XDG_DATA_HOME` is set, and
+/// `$HOME/.local/share/noseyparker` otherwise.
+pub fn workspaces_dir() -> Result<PathBuf> {
+    if let Some(data_home) = std::env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(data_home).join("noseyparker"));
+    }
+    match std::env::var_os("HOME").filter(|d| !d.is_empty()) {
+        Some(home) => Ok(PathBuf::from(home).join(".local/share/noseyparker")),
+        None => {
+            bail!("Failed to determine workspaces directory: neither XDG_DATA_HOME nor HOME is set")
+        }
+    }
+}
+
+/// Check that the given workspace name can be used as a single path component.
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        bail!("Invalid workspace name {name:?}: must be a non-empty name without path separators");
+    }
+    Ok(())
+}
+
+/// Determine the datastore path to use from the `--datastore` and `--workspace` arguments.
+///
+/// If a workspace name is given, the workspaces directory is created if needed. Otherwise, the
+/// datastore path is used as is.
+pub fn resolve_datastore(datastore: &Path, workspace: Option<&str>) -> Result<PathBuf> {
+    let name = match workspace {
+        None => return Ok(datastore.to_owned()),
+        Some(name) => name,
+    };
+    validate_name(name)?;
+    let dir = workspaces_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create workspaces directory at {}", dir.display()))?;
+    Ok(dir.join(name))
+}
+
+/// A named workspace datastore
+#[derive(serde::Serialize)]
+pub struct Workspace {
+    /// The name of the workspace
+    pub name: String,
+
+    /// The path of the workspace's datastore
+    pub path: PathBuf,
+}
+
+/// List the existing workspaces, sorted by name.
+///
+/// Each directory in the workspaces directory is a workspace.
+/// If the workspaces directory does not exist, there are no workspaces.
+pub fn list_workspaces() -> Result<Vec<Workspace>> {
+    let dir = workspaces_dir()?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Failed to read workspaces directory at {}", dir.display())
+            })
+        }
+    };
+
+    let mut workspaces = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .with_context(|| format!("Failed to read workspaces directory at {}", dir.display()))?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Ok(name) = entry.file_name().into_string() {
+            workspaces.push(Workspace {
+                name,
+                path: entry.path(),
+            });
+        }
+    }
+    workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(workspaces)
+}
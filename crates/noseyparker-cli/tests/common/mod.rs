@@ -300,7 +300,7 @@ pub fn get_report_json_redactions() -> Vec<(&'static str, Redaction)> {
     vec![
         ("[].matches[].provenance[].path", Redaction::from("<ROOT>/input.txt")),
         ("[].matches[].provenance[].repo_path", Redaction::from("<REPO>")),
-        ("[].score", insta::rounded_redaction(3)),
+        ("[].mean_score", insta::rounded_redaction(3)),
         ("[].matches[].score", insta::rounded_redaction(3)),
     ]
 }
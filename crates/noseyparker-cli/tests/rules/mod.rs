@@ -2,6 +2,8 @@
 
 use super::*;
 
+use indoc::indoc;
+
 /// Check the default list of rules in Nosey Parker using a snapshot test.
 /// This will alert us to when the default rules have changed for some reason (usually because a
 /// rule has been added).
@@ -32,3 +34,57 @@ fn rules_check_builtins() {
 fn rules_list_no_builtins() {
     assert_cmd_snapshot!(noseyparker_success!("rules", "list", "--load-builtins=false"));
 }
+
+/// Check that rule IDs are normalized when loaded, and that rulesets referring to them still
+/// resolve
+#[test]
+fn rules_normalize_ids() {
+    let scan_env = ScanEnv::new();
+    let rules = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Alpha Key
+              id: ' Test.Alpha  Key '
+              pattern: 'alpha_key = ([A-Z0-9]{20})'
+
+            rulesets:
+            - id: test
+              name: Test
+              description: Test rules
+              include_rule_ids:
+              - 'TEST.alpha key'
+        "#},
+    );
+    let input =
+        scan_env.input_file_with_contents("input.txt", "alpha_key = AAAABBBBCCCCDDDDEEEE\n");
+
+    noseyparker!("scan", "-d", scan_env.dspath(), "--load-builtins=false", "--ruleset=test")
+        .arg("--rules-path")
+        .arg(rules.path())
+        .arg(input.path())
+        .assert()
+        .success()
+        .stdout(match_scan_stats("33 B", 1, 1, 1))
+        .stderr(is_match(
+            r"Normalized rule ID ` Test.Alpha  Key ` \(Alpha Key\) to `test.alpha-key`",
+        ));
+
+    let list_rule_ids = |extra_args: &[&str]| -> Vec<String> {
+        let cmd = noseyparker!("rules", "list", "--format=json", "--load-builtins=false")
+            .arg("--rules-path")
+            .arg(rules.path())
+            .args(extra_args)
+            .assert()
+            .success();
+        let entries: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout).unwrap();
+        entries["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(list_rule_ids(&[]), ["test.alpha-key"]);
+    assert_eq!(list_rule_ids(&["--no-normalize-rule-ids"]), [" Test.Alpha  Key "]);
+}
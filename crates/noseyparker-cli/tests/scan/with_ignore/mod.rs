@@ -203,3 +203,230 @@ fn negation_01() {
     )
     .stdout(match_scan_stats("104 B", 1, 1, 1));
 }
+
+/// Test that `--coverage-report` lists the files skipped because of ignore rules and the max file
+/// size.
+#[test]
+fn coverage_report_01() {
+    let scan_env = ScanEnv::new();
+    let ignore_file = scan_env.input_file_with_contents(
+        "npignore",
+        indoc! {r#"
+            *.dat
+        "#},
+    );
+
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_secret("input/input.dat");
+    scan_env.input_file_with_secret("input/big.txt");
+    scan_env.input_file_with_contents("input/small.txt", "hello\n");
+
+    let coverage = scan_env.child("coverage.json");
+    noseyparker_success!(
+        "scan",
+        "--ignore",
+        ignore_file.path(),
+        "--max-file-size=0.00005",
+        "--coverage-report",
+        coverage.path(),
+        "-d",
+        scan_env.dspath(),
+        input.path()
+    )
+    .stdout(match_scan_stats("6 B", 1, 0, 0))
+    .stdout(is_match(r"(?m)^Scanned 1 files from the filesystem; skipped 2 entries$"))
+    .stdout(is_match(r"(?m)^ exceeds max file size +1$"))
+    .stdout(is_match(r"(?m)^ matches ignore rule +1$"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(coverage.path()).unwrap()).unwrap();
+    assert_eq!(report["num_files_scanned"], 1);
+    let skipped = report["skipped"].as_array().unwrap();
+    assert_eq!(skipped.len(), 2);
+
+    assert!(skipped[0]["path"].as_str().unwrap().ends_with("big.txt"));
+    assert_eq!(skipped[0]["num_bytes"], 104);
+    assert_eq!(skipped[0]["reason"]["kind"], "max_file_size");
+
+    assert!(skipped[1]["path"].as_str().unwrap().ends_with("input.dat"));
+    assert_eq!(skipped[1]["reason"]["kind"], "ignore_rule");
+}
+
+/// Test that `--manifest` records scanned, duplicate, and skipped inputs.
+#[test]
+fn scan_manifest_01() {
+    let scan_env = ScanEnv::new();
+    let ignore_file = scan_env.input_file_with_contents(
+        "npignore",
+        indoc! {r#"
+            *.dat
+        "#},
+    );
+
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_secret("input/a.txt");
+    scan_env.input_file_with_secret("input/b.txt");
+    scan_env.input_file_with_contents("input/c.txt", "hello\n");
+    scan_env.input_file_with_secret("input/input.dat");
+
+    let manifest = scan_env.child("manifest.jsonl");
+    noseyparker_success!(
+        "scan",
+        "--ignore",
+        ignore_file.path(),
+        "--manifest",
+        manifest.path(),
+        "-d",
+        scan_env.dspath(),
+        input.path()
+    )
+    .stdout(match_scan_stats("214 B", 3, 1, 1));
+
+    let entries: Vec<serde_json::Value> = std::fs::read_to_string(manifest.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 4);
+
+    let with_status = |status: &str| -> Vec<&serde_json::Value> {
+        entries.iter().filter(|e| e["status"] == status).collect()
+    };
+
+    let mut scanned = with_status("scanned");
+    scanned.sort_by_key(|e| e["num_bytes"].as_u64());
+    assert_eq!(scanned.len(), 2);
+    assert_eq!(scanned[0]["num_bytes"], 6);
+    assert_eq!(scanned[0]["num_matches"], 0);
+    assert_eq!(scanned[1]["num_bytes"], 104);
+    assert_eq!(scanned[1]["num_matches"], 1);
+
+    let duplicate = with_status("duplicate");
+    assert_eq!(duplicate.len(), 1);
+    assert_eq!(duplicate[0]["blob_id"], scanned[1]["blob_id"]);
+
+    let skipped = with_status("skipped");
+    assert_eq!(skipped.len(), 1);
+    assert!(skipped[0]["path"].as_str().unwrap().ends_with("input.dat"));
+    assert_eq!(skipped[0]["reason"]["kind"], "ignore_rule");
+}
+
+/// Test that `--manifest` records inputs that could not be enumerated or loaded.
+#[test]
+fn scan_manifest_errors() {
+    let scan_env = ScanEnv::new();
+
+    let good_line = serde_json::json!({
+        "content": scan_env.input_with_secret(),
+        "provenance": "input.txt",
+    })
+    .to_string();
+    let enumerator_input =
+        scan_env.input_file_with_contents("input.jsonl", &format!("{good_line}\nnot json\n"));
+    let missing = scan_env.child("missing.jsonl");
+
+    let manifest = scan_env.child("manifest.jsonl");
+    noseyparker_success!(
+        "scan",
+        "--manifest",
+        manifest.path(),
+        "-d",
+        scan_env.dspath(),
+        "--enumerator",
+        enumerator_input.path(),
+        "--enumerator",
+        missing.path()
+    );
+
+    let entries: Vec<serde_json::Value> = std::fs::read_to_string(manifest.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 3);
+
+    let scanned: Vec<_> = entries
+        .iter()
+        .filter(|e| e["status"] == "scanned")
+        .collect();
+    assert_eq!(scanned.len(), 1);
+    assert_eq!(scanned[0]["num_matches"], 1);
+
+    let mut errors: Vec<_> = entries.iter().filter(|e| e["status"] == "error").collect();
+    errors.sort_by_key(|e| e.get("path").is_some());
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("input.jsonl:2"));
+    assert!(errors[1]["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("missing.jsonl"));
+    assert!(!errors[1]["error"].as_str().unwrap().is_empty());
+}
+
+/// Test that `--include` and `--exclude` globs filter the files that get scanned, with excludes
+/// taking precedence.
+#[test]
+fn scan_include_exclude() {
+    let scan_env = ScanEnv::new();
+    let secret = scan_env.input_with_secret();
+
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_contents("input/keep.py", secret);
+    scan_env.input_file_with_contents("input/skip.txt", &format!("{secret}\n"));
+    scan_env.input_file_with_contents("input/vendor/lib.py", &format!("{secret}# vendored\n"));
+
+    // `vendor/lib.py` matches both globs, and is excluded
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--include=*.py",
+        "--exclude=**/vendor/**",
+        input.path()
+    )
+    .stdout(match_scan_stats("104 B", 1, 1, 1));
+
+    let cmd = noseyparker_success!("report", "-d", scan_env.dspath(), "--format=json");
+    let findings: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout).unwrap();
+    let findings = findings.as_array().unwrap();
+    assert_eq!(findings.len(), 1);
+    let matches = findings[0]["matches"].as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    let path = matches[0]["provenance"][0]["path"].as_str().unwrap();
+    assert!(path.ends_with("keep.py"));
+
+    noseyparker_success!("scan", "-d", scan_env.dspath(), "--include=*.py", input.path())
+        .stdout(match_scan_stats("219 B", 2, 1, 2));
+
+    // Excluding a directory skips everything within it
+    let coverage = scan_env.child("coverage.json");
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--exclude=**/vendor",
+        "--exclude=*.py",
+        "--coverage-report",
+        coverage.path(),
+        input.path()
+    )
+    .stdout(match_scan_stats("105 B", 1, 1, 3))
+    .stdout(is_match(r"(?m)^ excluded by glob filter +2$"));
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(coverage.path()).unwrap()).unwrap();
+    let mut skipped: Vec<(&str, &serde_json::Value)> = report["skipped"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| (s["path"].as_str().unwrap(), &s["reason"]["kind"]))
+        .collect();
+    skipped.sort_by_key(|(path, _)| *path);
+    assert_eq!(skipped.len(), 2);
+    assert!(skipped[0].0.ends_with("keep.py"));
+    assert!(skipped[1].0.ends_with("vendor"));
+    assert!(skipped.iter().all(|(_, kind)| *kind == "glob_filter"));
+}
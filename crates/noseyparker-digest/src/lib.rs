@@ -42,6 +42,90 @@ impl std::io::Write for Sha1 {
     }
 }
 
+#[derive(Default)]
+pub struct Sha256(sha2::Sha256);
+
+pub type Sha256Digest = [u8; 32];
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        sha2::Digest::update(&mut self.0, input);
+    }
+
+    pub fn hexdigest(self) -> String {
+        hex::encode(self.digest())
+    }
+
+    pub fn digest(self) -> Sha256Digest {
+        sha2::Digest::finalize(self.0).into()
+    }
+}
+
+pub fn sha256_hexdigest(input: &[u8]) -> String {
+    let mut h = Sha256::default();
+    h.update(input);
+    h.hexdigest()
+}
+
+impl std::io::Write for Sha256 {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Blake3(blake3::Hasher);
+
+pub type Blake3Digest = [u8; 32];
+
+impl Blake3 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.0.update(input);
+    }
+
+    pub fn hexdigest(self) -> String {
+        hex::encode(self.digest())
+    }
+
+    pub fn digest(self) -> Blake3Digest {
+        self.0.finalize().into()
+    }
+}
+
+pub fn blake3_hexdigest(input: &[u8]) -> String {
+    let mut h = Blake3::default();
+    h.update(input);
+    h.hexdigest()
+}
+
+impl std::io::Write for Blake3 {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +135,20 @@ mod tests {
     fn empty() {
         assert_eq!(sha1_hexdigest(&[]), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
     }
+
+    #[test]
+    fn empty_sha256() {
+        assert_eq!(
+            sha256_hexdigest(&[]),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn empty_blake3() {
+        assert_eq!(
+            blake3_hexdigest(&[]),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
 }
@@ -37,6 +37,11 @@ pub struct RuleSyntax {
     /// A list of string categories for the rule
     #[serde(default)]
     pub categories: Vec<String>,
+
+    /// Whether the rule is also matched against the paths of scanned blobs when `scan
+    /// --scan-filenames` is given
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub match_filenames: bool,
 }
 
 lazy_static! {
@@ -86,6 +91,7 @@ impl RuleSyntax {
     ///     negative_examples: vec![],
     ///     references: vec![],
     ///     categories: vec![],
+    ///     match_filenames: false,
     /// };
     /// assert_eq!(r.as_anchored_regex().unwrap().as_str(), r"hello\s*world\z");
     /// ```
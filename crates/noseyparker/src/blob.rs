@@ -1,14 +1,68 @@
 use anyhow::Result;
 use std::path::Path;
+use tracing::debug;
 
 pub use crate::blob_id::BlobId;
 
+// -------------------------------------------------------------------------------------------------
+// BlobBytes
+// -------------------------------------------------------------------------------------------------
+/// The content of a blob: either owned in memory, or memory-mapped from a file.
+pub enum BlobBytes {
+    Owned(Vec<u8>),
+    Mapped(memmap2::Mmap),
+}
+
+impl BlobBytes {
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            BlobBytes::Owned(v) => v.as_slice(),
+            BlobBytes::Mapped(m) => m,
+        }
+    }
+}
+
+impl std::ops::Deref for BlobBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for BlobBytes {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl From<Vec<u8>> for BlobBytes {
+    #[inline]
+    fn from(bytes: Vec<u8>) -> Self {
+        BlobBytes::Owned(bytes)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// SizeChangedError
+// -------------------------------------------------------------------------------------------------
+/// The error from `Blob::from_file_mmap` when a file's size changes while it is being loaded.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("file size changed while loading ({before} bytes before, {after} bytes after)")]
+pub struct SizeChangedError {
+    pub before: u64,
+    pub after: u64,
+}
+
 // -------------------------------------------------------------------------------------------------
 // Blob
 // -------------------------------------------------------------------------------------------------
 pub struct Blob {
     pub id: BlobId,
-    pub bytes: Vec<u8>,
+    pub bytes: BlobBytes,
 }
 
 impl Blob {
@@ -16,13 +70,56 @@ impl Blob {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let bytes = std::fs::read(path)?;
         let id = BlobId::compute_from_bytes(&bytes);
+        Ok(Blob {
+            id,
+            bytes: bytes.into(),
+        })
+    }
+
+    /// Load a blob from the file at the given path by memory-mapping it rather than reading it
+    /// into memory.
+    ///
+    /// If the file cannot be memory-mapped, it is read into memory instead.
+    /// If the size of the file changes while it is being loaded, the error is a `SizeChangedError`.
+    ///
+    /// Note that if the file is truncated by another process while the mapping is in use, accessing
+    /// the blob content may crash the process; this is inherent to memory-mapped I/O.
+    pub fn from_file_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let expected_len = file.metadata()?.len();
+
+        // SAFETY: the mapping is read-only; concurrent modification of the underlying file is
+        // detected below on a best-effort basis.
+        let bytes = match unsafe { memmap2::Mmap::map(&file) } {
+            Ok(mmap) => BlobBytes::Mapped(mmap),
+            Err(e) => {
+                debug!("Failed to memory-map {}; reading instead: {e}", path.display());
+                BlobBytes::Owned(std::fs::read(path)?)
+            }
+        };
+
+        let id = BlobId::compute_from_bytes(&bytes);
+
+        let actual_len = file.metadata()?.len();
+        if bytes.len() as u64 != expected_len || actual_len != expected_len {
+            return Err(SizeChangedError {
+                before: expected_len,
+                after: actual_len,
+            }
+            .into());
+        }
+
         Ok(Blob { id, bytes })
     }
 
     #[inline]
     pub fn from_bytes(bytes: Vec<u8>) -> Self {
         let id = BlobId::compute_from_bytes(&bytes);
-        Blob { id, bytes }
+        Blob {
+            id,
+            bytes: bytes.into(),
+        }
     }
 
     /// Create a new `Blob` with the given ID and content.
@@ -30,7 +127,10 @@ impl Blob {
     /// It is not checked that the ID matches that of the provided content.
     #[inline]
     pub fn new(id: BlobId, bytes: Vec<u8>) -> Self {
-        Blob { id, bytes }
+        Blob {
+            id,
+            bytes: bytes.into(),
+        }
     }
 
     /// Get the size of the blob in bytes.
@@ -45,3 +145,23 @@ impl Blob {
         self.bytes.is_empty()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn from_file_mmap_matches_from_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("input.txt");
+        std::fs::write(&path, b"some content to be memory-mapped\n").unwrap();
+
+        let b1 = Blob::from_file(&path).unwrap();
+        let b2 = Blob::from_file_mmap(&path).unwrap();
+
+        assert_eq!(b1.id, b2.id);
+        assert_eq!(b1.bytes.as_slice(), b2.bytes.as_slice());
+    }
+}
@@ -49,6 +49,11 @@ impl schemars::JsonSchema for BlobId {
 
 impl BlobId {
     /// Create a new `BlobId` computed from the given input.
+    ///
+    /// This is the same as the object ID Git assigns to a blob with the given content, i.e., the
+    /// SHA-1 digest of `blob <length>\0` followed by the content, as computed by
+    /// `git hash-object`. Blob IDs can thus be computed independently of Nosey Parker and
+    /// correlated with its output.
     #[inline]
     pub fn compute_from_bytes(input: &[u8]) -> Self {
         use noseyparker_digest::Sha1;
@@ -179,4 +184,13 @@ mod test {
             "06d7405020018ddf3cacee90fd4af10487da3d20"
         );
     }
+
+    /// The expected value here comes from `printf 'hello world\n' | git hash-object --stdin`
+    #[test]
+    fn matches_git_hash_object() {
+        assert_eq!(
+            BlobId::compute_from_bytes(b"hello world\n").hex(),
+            "3b18e512dba79e4c8300dd08aeb37f8e728b8dad"
+        );
+    }
 }
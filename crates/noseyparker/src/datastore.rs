@@ -1,34 +1,65 @@
 use anyhow::{bail, Context, Result};
 use bstr::BString;
 use indoc::indoc;
-use noseyparker_rules::Rule;
+use noseyparker_rules::{Rule, RuleSyntax};
 use rusqlite::Connection;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use tracing::{debug, debug_span, info, trace};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, debug_span, info, trace, warn};
 
+use crate::blob_id::BlobId;
 use crate::blob_metadata::BlobMetadata;
 use crate::git_url::GitUrl;
 use crate::location::{Location, OffsetSpan, SourcePoint, SourceSpan};
 use crate::match_type::Match;
-use crate::provenance::Provenance;
+use crate::provenance::{BlobLineage, Provenance};
 use crate::provenance_set::ProvenanceSet;
 use crate::snippet::Snippet;
+use crate::structural_id::StructuralIdAlgo;
 
-const CURRENT_SCHEMA_VERSION: u64 = 70;
-const CURRENT_SCHEMA: &str = include_str!("datastore/schema_70.sql");
+const CURRENT_SCHEMA_VERSION: u64 = 77;
+const CURRENT_SCHEMA: &str = include_str!("datastore/schema_77.sql");
+
+/// The SQL for migrating the schema from each older supported version to the next one, in order
+const SCHEMA_MIGRATIONS: &[(u64, &str)] = &[
+    (70, include_str!("datastore/migrations/migrate_70_to_71.sql")),
+    (71, include_str!("datastore/migrations/migrate_71_to_72.sql")),
+    (72, include_str!("datastore/migrations/migrate_72_to_73.sql")),
+    (73, include_str!("datastore/migrations/migrate_73_to_74.sql")),
+    (74, include_str!("datastore/migrations/migrate_74_to_75.sql")),
+    (75, include_str!("datastore/migrations/migrate_75_to_76.sql")),
+    (76, include_str!("datastore/migrations/migrate_76_to_77.sql")),
+];
+
+/// The oldest schema version that can be migrated to the current one
+const OLDEST_SCHEMA_VERSION: u64 = SCHEMA_MIGRATIONS[0].0;
 
 pub mod annotation;
+mod bundle;
+pub mod finding_cursor;
 pub mod finding_data;
 pub mod finding_metadata;
 pub mod finding_summary;
+pub mod match_filter;
+pub mod stats;
 pub mod status;
 
-pub use annotation::{Annotations, FindingAnnotation, MatchAnnotation};
+pub use annotation::{
+    AnnotationImportStats, Annotations, FindingAnnotation, ImportPolicy, ImportStats,
+    MatchAnnotation,
+};
+pub use finding_cursor::FindingCursor;
 pub use finding_data::{FindingData, FindingDataEntry};
 pub use finding_metadata::FindingMetadata;
 pub use finding_summary::{FindingSummary, FindingSummaryEntry};
+pub use match_filter::MatchFilter;
+pub use stats::DatastoreStats;
 pub use status::{Status, Statuses};
 
+use bundle::{BundleEntry, BundleMatch};
+
 // -------------------------------------------------------------------------------------------------
 // Datastore
 // -------------------------------------------------------------------------------------------------
@@ -53,6 +84,10 @@ pub struct Datastore {
 
     /// A connection to the database backing this `Datastore`.
     conn: Connection,
+
+    /// The ignored match IDs currently loaded into the `ignored_match_id` temporary table; see
+    /// `load_ignored_match_ids`.
+    loaded_ignored_match_ids: RefCell<Vec<String>>,
 }
 
 // Public implementation
@@ -71,11 +106,17 @@ impl Datastore {
     }
 
     /// Open the existing datastore at `root_dir`.
+    ///
+    /// A datastore with an older schema version is migrated to the current one.
     pub fn open(root_dir: &Path, cache_size: i64) -> Result<Self> {
         debug!("Attempting to open existing datastore at {}", root_dir.display());
 
-        let ds = Self::open_impl(root_dir, cache_size)?;
+        let mut ds = Self::open_impl(root_dir, cache_size)?;
         ds.check_schema_version()?;
+        if ds.schema_version()? < CURRENT_SCHEMA_VERSION {
+            ds.migrate_0_77()
+                .context("Failed to migrate database schema")?;
+        }
 
         let scratch_dir = ds.scratch_dir();
         std::fs::create_dir_all(&scratch_dir).with_context(|| {
@@ -111,7 +152,7 @@ impl Datastore {
 
         let mut ds = Self::open_impl(root_dir, cache_size)?;
 
-        ds.migrate_0_70()
+        ds.migrate_0_77()
             .context("Failed to initialize database schema")?;
 
         Self::open(root_dir, cache_size)
@@ -149,6 +190,35 @@ impl Datastore {
         // self.conn.execute("pragma wal_checkpoint(truncate)", [])?;
         Ok(())
     }
+
+    /// Get the size in bytes of this datastore's sqlite database on disk, including its
+    /// write-ahead log.
+    pub fn database_size(&self) -> Result<u64> {
+        let db_path = self.root_dir.join("datastore.db");
+        let mut size = std::fs::metadata(&db_path)
+            .with_context(|| format!("Failed to read metadata of {}", db_path.display()))?
+            .len();
+        match std::fs::metadata(self.root_dir.join("datastore.db-wal")) {
+            Ok(md) => size += md.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("Failed to read metadata of write-ahead log"),
+        }
+        Ok(size)
+    }
+
+    /// Rebuild the datastore's sqlite database, reclaiming the space left unused by deleted or
+    /// replaced data.
+    ///
+    /// This rewrites the entire database, so it can take a while for large datastores, and
+    /// temporarily needs as much free disk space as the database itself.
+    pub fn compact(&mut self) -> Result<()> {
+        let _span = debug_span!("Datastore::compact", "{}", self.root_dir.display()).entered();
+        self.conn.execute("vacuum", [])?;
+        // Without a checkpoint, the rewritten database would remain in the write-ahead log
+        self.conn
+            .query_row("pragma wal_checkpoint(truncate)", [], |_row| Ok(()))?;
+        Ok(())
+    }
 }
 
 /// A datastore-specific ID of a blob; simply a newtype-like wrapper around an i64.
@@ -308,6 +378,7 @@ impl<'ds> Transaction<'ds> {
         &'ds self,
     ) -> Result<impl FnMut(BlobIdInt, &'ds Match, &'ds Option<f64>) -> rusqlite::Result<bool>> {
         let mut record_snippet = self.mk_record_snippet()?;
+        let now = unix_timestamp(SystemTime::now());
 
         let mut get_finding_id = self.inner.prepare_cached(indoc! {r#"
             select f.id
@@ -341,7 +412,10 @@ impl<'ds> Transaction<'ds> {
                 finding_id = ?3,
                 before_snippet_id = ?4,
                 matching_snippet_id = ?5,
-                after_snippet_id = ?6
+                after_snippet_id = ?6,
+                before_truncated = ?7,
+                after_truncated = ?8,
+                updated_at = ?9
             where
                 id = ?1 and
                 (
@@ -349,7 +423,9 @@ impl<'ds> Transaction<'ds> {
                     finding_id != ?3 or
                     before_snippet_id != ?4 or
                     matching_snippet_id != ?5 or
-                    after_snippet_id != ?6
+                    after_snippet_id != ?6 or
+                    before_truncated != ?7 or
+                    after_truncated != ?8
                 )
         "#})?;
 
@@ -362,9 +438,13 @@ impl<'ds> Transaction<'ds> {
                 end_byte,
                 before_snippet_id,
                 matching_snippet_id,
-                after_snippet_id
+                after_snippet_id,
+                before_truncated,
+                after_truncated,
+                created_at,
+                updated_at
             )
-            select ?, ?, ?, ?, ?, ?, ?, ?
+            select ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11
             returning id
         "#})?;
 
@@ -389,6 +469,12 @@ impl<'ds> Transaction<'ds> {
             on conflict do update set score = excluded.score
         "#})?;
 
+        let mut set_context = self.inner.prepare_cached(indoc! {r#"
+            insert into match_context (match_id, context)
+            values (?, ?)
+            on conflict do update set context = excluded.context
+        "#})?;
+
         let f = move |BlobIdInt(blob_id), m: &'ds Match, score: &'ds Option<f64>| {
             let start_byte = m.location.offset_span.start;
             let end_byte = m.location.offset_span.end;
@@ -437,6 +523,9 @@ impl<'ds> Transaction<'ds> {
                     before_snippet_id,
                     matching_snippet_id,
                     after_snippet_id,
+                    snippet.before_truncated,
+                    snippet.after_truncated,
+                    now,
                 ))?;
                 (match_id, false)
             } else {
@@ -451,6 +540,9 @@ impl<'ds> Transaction<'ds> {
                         before_snippet_id,
                         matching_snippet_id,
                         after_snippet_id,
+                        snippet.before_truncated,
+                        snippet.after_truncated,
+                        now,
                     ),
                     val_from_row,
                 )?;
@@ -461,6 +553,10 @@ impl<'ds> Transaction<'ds> {
                 set_score.execute((match_id, score))?;
             }
 
+            if let Some(context) = &m.context {
+                set_context.execute((match_id, context))?;
+            }
+
             Ok(new)
         };
 
@@ -485,8 +581,16 @@ impl<'ds> Transaction<'ds> {
                 record_provenance(blob_id, p).context("Failed to record blob provenance")?;
             }
 
-            // record matches
+            // record matches, skipping any that are malformed rather than storing data that
+            // would cause errors when reporting
             for (s, m) in ms {
+                if let Err(e) = m
+                    .check_span(md.num_bytes)
+                    .and_then(|()| m.check_source_span())
+                {
+                    warn!("Skipping invalid match {} in blob {}: {e}", m.structural_id, md.id);
+                    continue;
+                }
                 if record_match(blob_id, m, s).context("Failed to record match")? {
                     num_matches_added += 1;
                 }
@@ -506,6 +610,33 @@ impl Datastore {
         Ok(Transaction { inner })
     }
 
+    /// Record a single match and its provenance in a test, as if it had been found by scanning.
+    ///
+    /// The rule that produced the match must already have been recorded using
+    /// `Transaction::record_rules`. The size of the match's blob is taken to be the smallest size
+    /// consistent with the match's span and snippets.
+    ///
+    /// Returns whether the match was newly added.
+    #[cfg(test)]
+    pub(crate) fn insert_test_match(
+        &mut self,
+        match_val: &Match,
+        provenance: Provenance,
+    ) -> Result<bool> {
+        let blob_metadata = BlobMetadata {
+            id: match_val.blob_id,
+            num_bytes: match_val.location.offset_span.end + match_val.snippet.after.len(),
+            mime_essence: None,
+            charset: None,
+        };
+        let batch = [(provenance.into(), blob_metadata, vec![(None, match_val.clone())])];
+
+        let tx = self.begin()?;
+        let num_added = tx.record(&batch)?;
+        tx.commit()?;
+        Ok(num_added > 0)
+    }
+
     /// How many matches are there, total, in the datastore?
     pub fn get_num_matches(&self) -> Result<u64> {
         let mut stmt = self.conn.prepare_cached(indoc! {r#"
@@ -524,11 +655,149 @@ impl Datastore {
         Ok(num_findings)
     }
 
-    /// Get a summary of all recorded findings.
+    /// How many matches are redundant to some other match?
+    pub fn get_num_redundant_matches(&self) -> Result<u64> {
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select count(distinct match_id) from match_redundancy
+        "#})?;
+        let num_redundant: u64 = stmt.query_row((), val_from_row)?;
+        Ok(num_redundant)
+    }
+
+    /// Get the distinct algorithms that produced the structural ids of the matches in the
+    /// datastore, in sorted order.
+    ///
+    /// Non-SHA-1 structural ids are prefixed by the name of their algorithm; see
+    /// `StructuralIdAlgo`.
+    pub fn get_structural_id_algos(&self) -> Result<Vec<StructuralIdAlgo>> {
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select distinct substr(structural_id, 1, instr(structural_id, ':') - 1)
+            from match
+        "#})?;
+        let mut algos = Vec::new();
+        for prefix in stmt.query_map((), val_from_row::<String>)? {
+            let prefix = prefix?;
+            let algo = if prefix.is_empty() {
+                StructuralIdAlgo::Sha1
+            } else {
+                match StructuralIdAlgo::from_name(&prefix) {
+                    Some(algo) => algo,
+                    None => bail!("Unknown structural id algorithm {prefix:?}"),
+                }
+            };
+            algos.push(algo);
+        }
+        algos.sort();
+        algos.dedup();
+        Ok(algos)
+    }
+
+    /// Check that this datastore and the other one do not have matches with different structural
+    /// ID algorithms.
+    fn check_same_structural_id_algos(&self, other: &Datastore) -> Result<()> {
+        let algos = self.get_structural_id_algos()?;
+        let other_algos = other.get_structural_id_algos()?;
+        if !algos.is_empty() && !other_algos.is_empty() && algos != other_algos {
+            let names = |algos: &[StructuralIdAlgo]| -> String {
+                algos
+                    .iter()
+                    .map(|a| a.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            bail!(
+                "Datastores have matches with different structural ID algorithms: {} and {}",
+                names(&algos),
+                names(&other_algos),
+            );
+        }
+        Ok(())
+    }
+
+    /// Get the schema version of the datastore.
+    ///
+    /// An opened datastore always has the schema version supported by this version of Nosey Parker.
+    pub fn schema_version(&self) -> Result<u64> {
+        let user_version = self
+            .conn
+            .pragma_query_value(None, "user_version", val_from_row)?;
+        Ok(user_version)
+    }
+
+    /// Get a snapshot of the overall contents of the datastore.
+    ///
+    /// The counts are computed using a single query.
+    pub fn stats(&self) -> Result<DatastoreStats> {
+        let _span = debug_span!("Datastore::stats", "{}", self.root_dir.display()).entered();
+
+        let database_size = self.database_size()?;
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select
+                (select user_version from pragma_user_version),
+                (select count(*) from match),
+                (select count(*) from finding),
+                (select count(distinct rule_id) from finding),
+                (select count(distinct provenance) from blob_provenance),
+                (select count(*) from match_status)
+                    + (select count(*) from match_comment)
+                    + (select count(*) from finding_comment)
+        "#})?;
+        let stats = stmt.query_row((), |row| {
+            Ok(DatastoreStats {
+                schema_version: row.get(0)?,
+                num_matches: row.get(1)?,
+                num_findings: row.get(2)?,
+                distinct_rules: row.get(3)?,
+                num_provenance: row.get(4)?,
+                num_annotations: row.get(5)?,
+                database_size,
+            })
+        })?;
+        Ok(stats)
+    }
+
+    /// Call the given function on each distinct provenance entry in the datastore, in sorted
+    /// order.
+    ///
+    /// Entries are read incrementally from the database, so this is suitable for very large
+    /// datastores.
+    pub fn visit_provenance<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(Provenance) -> Result<()>,
+    {
+        let _span =
+            debug_span!("Datastore::visit_provenance", "{}", self.root_dir.display()).entered();
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select distinct provenance
+            from blob_provenance
+            order by provenance
+        "#})?;
+        let entries = stmt.query_map((), val_from_row)?;
+        for entry in entries {
+            f(entry?)?;
+        }
+        Ok(())
+    }
+
+    /// Get all distinct provenance entries in the datastore, in sorted order.
+    pub fn list_provenance(&self) -> Result<Vec<Provenance>> {
+        let mut entries = Vec::new();
+        self.visit_provenance(|p| {
+            entries.push(p);
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Get a summary of all recorded findings, with one entry per rule, ordered by rule name.
     pub fn get_summary(&self) -> Result<FindingSummary> {
         let _span = debug_span!("Datastore::get_summary", "{}", self.root_dir.display()).entered();
 
-        let mut stmt = self.conn.prepare_cached("select * from finding_summary")?;
+        let mut stmt = self
+            .conn
+            .prepare_cached("select * from finding_summary order by rule_name")?;
         let entries = stmt.query_map((), |row| {
             Ok(FindingSummaryEntry {
                 rule_name: row.get(0)?,
@@ -536,8 +805,9 @@ impl Datastore {
                 total_count: row.get(3)?,
                 accept_count: row.get(4)?,
                 reject_count: row.get(5)?,
-                mixed_count: row.get(6)?,
-                unlabeled_count: row.get(7)?,
+                needs_review_count: row.get(6)?,
+                mixed_count: row.get(7)?,
+                unlabeled_count: row.get(8)?,
             })
         })?;
         let es = collect(entries)?;
@@ -545,6 +815,9 @@ impl Datastore {
     }
 
     /// Get annotations from this datastore.
+    ///
+    /// Annotations that cannot be read, e.g., because of a corrupted datastore, are skipped with
+    /// a warning that names the match or finding they belong to.
     pub fn get_annotations(&self) -> Result<Annotations> {
         let _span =
             debug_span!("Datastore::get_annotations", "{}", self.root_dir.display()).entered();
@@ -566,21 +839,21 @@ impl Datastore {
             where md.status is not null or md.comment is not null
         "#})?;
         let entries = stmt.query_map((), |row| {
-            Ok(MatchAnnotation {
-                finding_id: row.get(0)?,
-                rule_name: row.get(1)?,
-                rule_text_id: row.get(2)?,
-                rule_structural_id: row.get(3)?,
-                match_id: row.get(4)?,
-                blob_id: row.get(5)?,
-                start_byte: row.get(6)?,
-                end_byte: row.get(7)?,
-                groups: row.get(8)?,
-                status: row.get(9)?,
-                comment: row.get(10)?,
-            })
+            let match_id: String = row.get(4)?;
+            let annotation = match_annotation_from_row(row);
+            Ok((match_id, annotation))
         })?;
-        let match_annotations = collect(entries)?;
+        // A malformed row is skipped rather than failing, so that the remaining annotations can
+        // still be retrieved
+        let mut match_annotations = Vec::new();
+        for entry in entries {
+            match entry? {
+                (_, Ok(annotation)) => match_annotations.push(annotation),
+                (match_id, Err(e)) => {
+                    warn!("Skipping malformed annotation for match {match_id}: {e}")
+                }
+            }
+        }
 
         let mut stmt = self.conn.prepare_cached(indoc! {r#"
             select
@@ -594,16 +867,19 @@ impl Datastore {
             where md.comment is not null
         "#})?;
         let entries = stmt.query_map((), |row| {
-            Ok(FindingAnnotation {
-                finding_id: row.get(0)?,
-                rule_name: row.get(1)?,
-                rule_text_id: row.get(2)?,
-                rule_structural_id: row.get(3)?,
-                groups: row.get(4)?,
-                comment: row.get(5)?,
-            })
+            let finding_id: String = row.get(0)?;
+            let annotation = finding_annotation_from_row(row);
+            Ok((finding_id, annotation))
         })?;
-        let finding_annotations = collect(entries)?;
+        let mut finding_annotations = Vec::new();
+        for entry in entries {
+            match entry? {
+                (_, Ok(annotation)) => finding_annotations.push(annotation),
+                (finding_id, Err(e)) => {
+                    warn!("Skipping malformed annotation for finding {finding_id}: {e}")
+                }
+            }
+        }
 
         Ok(Annotations {
             match_annotations,
@@ -611,33 +887,36 @@ impl Datastore {
         })
     }
 
-    pub fn import_annotations(&mut self, annotations: &Annotations) -> Result<()> {
-        #[derive(Default, Debug)]
-        struct Stats {
-            n_imported: usize,
-            n_conflicting: usize,
-            n_existing: usize,
-            n_missing: usize,
-        }
-
-        impl std::fmt::Display for Stats {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(
-                    f,
-                    "{} existing; {} missing; {} conflicting; {} imported",
-                    self.n_existing, self.n_missing, self.n_conflicting, self.n_imported
-                )
-            }
+    /// Import the given annotations into this datastore.
+    ///
+    /// Annotations whose matches or findings are not in the datastore are ignored. Annotations
+    /// that conflict with existing ones are handled according to `policy`.
+    pub fn import_annotations(
+        &mut self,
+        annotations: &Annotations,
+        policy: ImportPolicy,
+    ) -> Result<AnnotationImportStats> {
+        use rusqlite::{types::FromSql, CachedStatement, OptionalExtension, ToSql};
+
+        /// Get the existing value for the given id using the given getter query, which takes a
+        /// single `&Id` parameter and returns an id and a value.
+        fn get_existing<Id, Val>(getter: &mut CachedStatement, ann_id: &Id) -> Result<Option<Val>>
+        where
+            Id: ToSql,
+            Val: FromSql,
+        {
+            Ok(getter.query_row((ann_id,), |r| r.get(1)).optional()?)
         }
 
-        use rusqlite::{types::FromSql, CachedStatement, ToSql};
-
         /// This complicated helper function factors out some common "import a single annotation"
         /// logic that is common to finding comments, match comments, and match statuses.
         /// Better than repeating the code verbatim three times...?
+        #[allow(clippy::too_many_arguments)]
         fn do_import<Ann, Id, Val>(
             annotation_type: &str,        // human-readable name of annotation type
-            stats: &mut Stats,            // stats object to update
+            policy: ImportPolicy,         // how to handle conflicts
+            skip: bool,                   // whether the annotation conflicts and is to be skipped
+            stats: &mut ImportStats,      // stats object to update
             getter: &mut CachedStatement, // sql getter query, takes a single `&Id` parameter
             setter: &mut CachedStatement, // sql setter query, takes an `&Id` and a `&Val` parameter
             ann: &Ann,                    // the annotation being imported
@@ -649,32 +928,33 @@ impl Datastore {
             Id: ToSql,
             Val: FromSql + ToSql + Eq + std::fmt::Debug,
         {
-            use rusqlite::OptionalExtension; // for .optional()
-
-            let existing: Option<(u64, Val)> = getter
-                .query_row((ann_id,), |r| {
-                    let id: u64 = r.get(0)?;
-                    let val: Val = r.get(1)?;
-                    Ok((id, val))
-                })
-                .optional()?;
+            let existing: Option<Val> = get_existing(getter, ann_id)?;
             match existing {
-                Some((_id, val)) if &val == ann_val => {
-                    stats.n_existing += 1;
+                Some(val) if &val == ann_val => {
+                    stats.existing += 1;
                     trace!("did not import {annotation_type}: already present: {ann:#?}");
                 }
-                Some((_id, val)) => {
-                    stats.n_conflicting += 1;
+                Some(val) if policy == ImportPolicy::Overwrite => {
+                    setter.execute((ann_id, ann_val))?;
+                    stats.overwritten += 1;
+                    debug!("imported {annotation_type}: overwrote {val:?}: {ann:#?}");
+                }
+                Some(val) => {
+                    stats.conflicting += 1;
                     debug!("did not import {annotation_type}: conflict: {val:?} {ann:#?}");
                 }
+                None if skip => {
+                    stats.conflicting += 1;
+                    debug!("did not import {annotation_type}: annotation conflicts: {ann:#?}");
+                }
                 None => {
                     let n_set = setter.execute((ann_id, ann_val))?;
                     if n_set == 1 {
-                        stats.n_imported += 1;
+                        stats.imported += 1;
                         trace!("imported {annotation_type}: new: {ann:#?}");
                     } else {
                         assert_eq!(n_set, 0);
-                        stats.n_missing += 1;
+                        stats.missing += 1;
                         debug!("did not import {annotation_type}: not found: {ann:#?}");
                     }
                 }
@@ -685,13 +965,43 @@ impl Datastore {
 
         // Ok, now with that preamble out of the way, let's actually import the annotations
 
+        const GET_MATCH_COMMENT: &str = indoc! {r#"
+            select m.id, mc.comment
+            from
+                match m
+                inner join match_comment mc on (mc.match_id = m.id)
+            where m.structural_id = ?
+        "#};
+
+        const GET_MATCH_STATUS: &str = indoc! {r#"
+            select m.id, ms.status
+            from
+                match m
+                inner join match_status ms on (ms.match_id = m.id)
+            where m.structural_id = ?
+        "#};
+
         let tx = self
             .conn
             .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
 
-        let mut finding_comment_stats = Stats::default();
-        let mut match_comment_stats = Stats::default();
-        let mut match_status_stats = Stats::default();
+        let mut stats = AnnotationImportStats::default();
+
+        // Determine which match annotations to skip entirely, before importing any of them
+        let mut skipped_match_ids = HashSet::new();
+        if policy == ImportPolicy::Skip {
+            let mut get_comment = tx.prepare_cached(GET_MATCH_COMMENT)?;
+            let mut get_status = tx.prepare_cached(GET_MATCH_STATUS)?;
+            for ma in annotations.match_annotations.iter() {
+                let comment: Option<String> = get_existing(&mut get_comment, &ma.match_id)?;
+                let status: Option<Status> = get_existing(&mut get_status, &ma.match_id)?;
+                let conflicts = matches!((&comment, &ma.comment), (Some(c1), Some(c2)) if c1 != c2)
+                    || matches!((status, ma.status), (Some(s1), Some(s2)) if s1 != s2);
+                if conflicts {
+                    skipped_match_ids.insert(ma.match_id.as_str());
+                }
+            }
+        }
 
         // Import finding comments
         {
@@ -713,7 +1023,9 @@ impl Datastore {
             for fa in annotations.finding_annotations.iter() {
                 do_import(
                     "finding comment",
-                    &mut finding_comment_stats,
+                    policy,
+                    false,
+                    &mut stats.finding_comments,
                     &mut getter,
                     &mut setter,
                     &fa,
@@ -725,13 +1037,7 @@ impl Datastore {
 
         // Import match comments
         {
-            let mut getter = tx.prepare_cached(indoc! {r#"
-                select m.id, mc.comment
-                from
-                    match m
-                    inner join match_comment mc on (mc.match_id = m.id)
-                where m.structural_id = ?
-            "#})?;
+            let mut getter = tx.prepare_cached(GET_MATCH_COMMENT)?;
 
             let mut setter = tx.prepare_cached(indoc! {r#"
                 insert or replace into match_comment (match_id, comment)
@@ -748,7 +1054,9 @@ impl Datastore {
 
                 do_import(
                     "match comment",
-                    &mut match_comment_stats,
+                    policy,
+                    skipped_match_ids.contains(ma.match_id.as_str()),
+                    &mut stats.match_comments,
                     &mut getter,
                     &mut setter,
                     &ma,
@@ -760,13 +1068,7 @@ impl Datastore {
 
         // Import match statuses
         {
-            let mut getter = tx.prepare_cached(indoc! {r#"
-                select m.id, ms.status
-                from
-                    match m
-                    inner join match_status ms on (ms.match_id = m.id)
-                where m.structural_id = ?
-            "#})?;
+            let mut getter = tx.prepare_cached(GET_MATCH_STATUS)?;
 
             let mut setter = tx.prepare_cached(indoc! {r#"
                 insert or replace into match_status (match_id, status)
@@ -783,7 +1085,9 @@ impl Datastore {
 
                 do_import(
                     "match status",
-                    &mut match_status_stats,
+                    policy,
+                    skipped_match_ids.contains(ma.match_id.as_str()),
+                    &mut stats.match_statuses,
                     &mut getter,
                     &mut setter,
                     &ma,
@@ -801,229 +1105,667 @@ impl Datastore {
             self.get_num_matches()?,
             self.root_dir.display()
         );
-        info!("Finding comment annotations: {finding_comment_stats}");
-        info!("Match comment annotations: {match_comment_stats}");
-        info!("Match status annotations: {match_status_stats}");
+        info!("Finding comment annotations: {}", stats.finding_comments);
+        info!("Match comment annotations: {}", stats.match_comments);
+        info!("Match status annotations: {}", stats.match_statuses);
 
-        Ok(())
+        Ok(stats)
     }
 
-    /// Get metadata for all groups of identical matches recorded within this datastore.
-    pub fn get_finding_metadata(
-        &self,
-        suppress_redundant_matches: bool,
-    ) -> Result<Vec<FindingMetadata>> {
+    /// Set the status of every match in the finding with the given identifier, and optionally their
+    /// comment too, returning the number of matches updated.
+    ///
+    /// Any existing statuses and comments on those matches are replaced. Matches in other findings
+    /// are not affected.
+    pub fn set_finding_status(
+        &mut self,
+        finding_id: &str,
+        status: Status,
+        comment: Option<&str>,
+    ) -> Result<u64> {
         let _span =
-            debug_span!("Datastore::get_finding_metadata", "{}", self.root_dir.display()).entered();
+            debug_span!("Datastore::set_finding_status", "{}", self.root_dir.display()).entered();
 
-        let query_str = format!(
+        let tx = self.conn.transaction()?;
+
+        let num_updated = tx.execute(
             indoc! {r#"
-                select
-                    finding_id,
-                    groups,
-                    rule_structural_id,
-                    rule_text_id,
-                    rule_name,
-                    num_matches,
-                    num_redundant_matches,
-                    comment,
-                    match_statuses,
-                    mean_score
-                from finding_denorm
-                where {}
-                order by rule_name, rule_structural_id, mean_score desc, groups
+                insert or replace into match_status (match_id, status)
+                select m.id, ?2
+                from match m inner join finding f on (m.finding_id = f.id)
+                where f.finding_id = ?1
             "#},
-            if suppress_redundant_matches {
-                "num_matches != num_redundant_matches"
-            } else {
-                "true"
-            }
-        );
-        let mut stmt = self.conn.prepare_cached(&query_str)?;
-        let entries = stmt.query_map((), |row| {
-            Ok(FindingMetadata {
-                finding_id: row.get(0)?,
-                groups: row.get(1)?,
-                rule_structural_id: row.get(2)?,
-                rule_text_id: row.get(3)?,
-                rule_name: row.get(4)?,
-                num_matches: row.get(5)?,
-                num_redundant_matches: row.get(6)?,
-                comment: row.get(7)?,
-                statuses: row.get(8)?,
-                mean_score: row.get(9)?,
-            })
-        })?;
-        collect(entries)
+            (finding_id, status),
+        )?;
+
+        if let Some(comment) = comment {
+            tx.execute(
+                indoc! {r#"
+                    insert or replace into match_comment (match_id, comment)
+                    select m.id, ?2
+                    from match m inner join finding f on (m.finding_id = f.id)
+                    where f.finding_id = ?1
+                "#},
+                (finding_id, comment),
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(num_updated as u64)
     }
 
-    /// Get up to `max_matches` matches that belong to the finding with the given finding metadata.
-    /// Each match will have up to `max_provenance_entries`.
+    /// Write the annotations in this datastore to the given writer as a single JSON object.
     ///
-    /// A value of `None` for either limit value means "no limit".
-    pub fn get_finding_data(
-        &self,
-        metadata: &FindingMetadata,
-        max_matches: Option<usize>,
-        max_provenance_entries: Option<usize>,
-        suppress_redundant_matches: bool,
-    ) -> Result<FindingData> {
-        let _span =
-            debug_span!("Datastore::get_finding_data", "{}", self.root_dir.display()).entered();
-
-        let match_limit: i64 = match max_matches {
-            Some(max_matches) => max_matches
-                .try_into()
-                .expect("max_matches should be convertible"),
-            None => -1,
-        };
+    /// The output can be read back with `import_annotations` after deserializing it as an
+    /// `Annotations` value, making it suitable for sharing triage work between datastores.
+    pub fn export_annotations<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        let annotations = self.get_annotations()?;
+        serde_json::to_writer(&mut writer, &annotations)?;
+        writer.flush()?;
+        Ok(())
+    }
 
-        let suppress_redundant = if suppress_redundant_matches {
-            "m.id not in (select match_id from match_redundancy)"
-        } else {
-            "true"
-        };
+    /// Write the entire contents of this datastore to the given writer as a JSON Lines bundle.
+    ///
+    /// The bundle includes all recorded rules, blob metadata, provenance, matches, and
+    /// annotations. It can be read back with `import_json`, on this or another system.
+    /// Entries are written as they are read from the database, so this is suitable for very large
+    /// datastores.
+    pub fn export_json<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        let _span = debug_span!("Datastore::export_json", "{}", self.root_dir.display()).entered();
+
+        self.visit_bundle_entries(&mut |entry| {
+            serde_json::to_writer(&mut writer, &entry)?;
+            writeln!(writer)?;
+            Ok(())
+        })?;
 
-        let query_str = format!(
-            indoc! {r#"
-            select
-                m.blob_id,
-                m.start_byte,
-                m.end_byte,
-                m.start_line,
-                m.start_column,
-                m.end_line,
-                m.end_column,
+        writer.flush()?;
+        Ok(())
+    }
 
-                m.before_snippet,
-                m.matching_snippet,
-                m.after_snippet,
+    /// Call the given function on each entry of a bundle of the entire contents of this
+    /// datastore, in the order they are written by `export_json`.
+    fn visit_bundle_entries(&self, f: &mut dyn FnMut(BundleEntry) -> Result<()>) -> Result<()> {
+        let mut get_rules = self.conn.prepare_cached(indoc! {r#"
+            select syntax from rule order by structural_id
+        "#})?;
+        let rules = get_rules.query_map((), val_from_row::<String>)?;
+        for syntax in rules {
+            let syntax: RuleSyntax =
+                serde_json::from_str(&syntax?).context("Failed to parse recorded rule")?;
+            f(BundleEntry::Rule { syntax })?;
+        }
 
-                m.groups,
+        let mut get_blobs = self.conn.prepare_cached(&format!(
+            "select {} from blob_denorm b order by b.blob_id",
+            BLOB_METADATA_COLUMNS
+        ))?;
+        let blobs = get_blobs.query_map((), |row| blob_metadata_from_row(row, 0))?;
+        for metadata in blobs {
+            let metadata = metadata?;
+            let provenance = self.get_provenance_set(&metadata, None)?;
+            let matches = self
+                .get_blob_matches(&metadata.id)?
+                .into_iter()
+                .map(|(score, match_val)| BundleMatch { match_val, score })
+                .collect();
+            f(BundleEntry::Blob {
+                metadata,
+                provenance,
+                matches,
+            })?;
+        }
 
-                b.size,
-                b.mime_essence,
-                b.charset,
+        let annotations = self.get_annotations()?;
+        f(BundleEntry::Annotations { annotations })?;
 
-                m.id,
-                m.score,
-                m.comment,
-                m.status,
-                m.structural_id
+        Ok(())
+    }
 
-            from match_denorm m
-            inner join blob_denorm b on (m.blob_id = b.blob_id)
-            where m.groups = ?1 and m.rule_structural_id = ?2 and {}
-            order by m.blob_id, m.start_byte, m.end_byte
-            limit ?3
-        "#},
-            suppress_redundant
-        );
+    /// Read a JSON Lines bundle written by `export_json` into this datastore, returning the number
+    /// of matches that were newly added.
+    ///
+    /// The content of the bundle is merged with any content already in this datastore. Match and
+    /// finding identifiers, scores, statuses, and comments are preserved; the times that imported
+    /// matches were first and last recorded are set to the time of the import.
+    pub fn import_json<R: std::io::BufRead>(&mut self, reader: R) -> Result<u64> {
+        let _span = debug_span!("Datastore::import_json", "{}", self.root_dir.display()).entered();
+
+        self.import_bundle_entries(ImportPolicy::default(), |f| {
+            for (line_num, line) in reader.lines().enumerate() {
+                let line = line.context("Failed to read bundle")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let entry: BundleEntry = serde_json::from_str(&line).with_context(|| {
+                    format!("Failed to parse bundle entry on line {}", line_num + 1)
+                })?;
+                f(entry)?;
+            }
+            Ok(())
+        })
+    }
 
-        let mut get_blob_metadata_and_match = self.conn.prepare_cached(&query_str)?;
+    /// Copy the entire contents of the given datastore into this one, returning the number of
+    /// matches that were newly added.
+    ///
+    /// This is equivalent to exporting `src` with `export_json` and importing the result with
+    /// `import_json`, without an intermediate bundle. Matches that are already in this datastore,
+    /// as determined by their structural IDs, are not duplicated. Annotations from `src` that
+    /// conflict with existing ones are handled according to `policy`.
+    ///
+    /// Finding IDs computed with different structural ID algorithms never coincide, so this is an
+    /// error if the datastores have matches with different algorithms.
+    pub fn merge(&mut self, src: &Datastore, policy: ImportPolicy) -> Result<u64> {
+        let _span = debug_span!("Datastore::merge", "{}", self.root_dir.display()).entered();
 
-        let entries = get_blob_metadata_and_match.query_map(
-            (&metadata.groups, &metadata.rule_structural_id, match_limit),
-            |row| {
-                let blob_id = row.get(0)?;
-                let m = Match {
-                    blob_id,
-                    location: Location {
-                        offset_span: OffsetSpan {
-                            start: row.get(1)?,
-                            end: row.get(2)?,
-                        },
-                        source_span: SourceSpan {
-                            start: SourcePoint {
-                                line: row.get(3)?,
-                                column: row.get(4)?,
-                            },
-                            end: SourcePoint {
-                                line: row.get(5)?,
-                                column: row.get(6)?,
-                            },
-                        },
-                    },
-                    snippet: Snippet {
-                        before: BString::new(row.get(7)?),
-                        matching: BString::new(row.get(8)?),
-                        after: BString::new(row.get(9)?),
-                    },
-                    groups: row.get(10)?,
-                    rule_structural_id: metadata.rule_structural_id.clone(),
-                    rule_name: metadata.rule_name.clone(),
-                    rule_text_id: metadata.rule_text_id.clone(),
-                    structural_id: row.get(18)?,
-                };
-                let num_bytes: usize = row.get(11)?;
-                let mime_essence: Option<String> = row.get(12)?;
-                let charset: Option<String> = row.get(13)?;
-                let b = BlobMetadata {
-                    id: blob_id,
-                    num_bytes,
-                    mime_essence,
-                    charset,
-                };
-                let id = MatchIdInt(row.get(14)?);
-                let m_score = row.get(15)?;
-                let m_comment = row.get(16)?;
-                let m_status = row.get(17)?;
-                Ok((b, id, m, m_score, m_comment, m_status))
-            },
-        )?;
-        let mut es = Vec::new();
-        for e in entries {
-            let (md, id, m, match_score, match_comment, match_status) = e?;
-            let ps = self.get_provenance_set(&md, max_provenance_entries)?;
-            let redundant_to = self.get_redundant_to(id)?;
-            es.push(FindingDataEntry {
-                provenance: ps,
-                blob_metadata: md,
-                match_id: id,
-                match_val: m,
-                match_comment,
-                match_score,
-                match_status,
-                redundant_to,
-            });
+        if self.root_dir.canonicalize()? == src.root_dir.canonicalize()? {
+            bail!("Cannot merge a datastore into itself");
         }
-        Ok(es)
+        self.check_same_structural_id_algos(src)?;
+
+        self.import_bundle_entries(policy, |f| src.visit_bundle_entries(f))
     }
 
-    fn get_provenance_set(
-        &self,
-        metadata: &BlobMetadata,
-        max_provenance_entries: Option<usize>,
-    ) -> Result<ProvenanceSet> {
-        let max_provenance_entries: i64 = match max_provenance_entries {
-            Some(m) => m.try_into().expect("max_matches should be convertible"),
-            None => -1,
-        };
-        let mut get = self.conn.prepare_cached(indoc! {r#"
-            select provenance
-            from blob_provenance_denorm
-            where blob_id = ?
-            order by provenance
-            limit ?
-        "#})?;
+    /// Record the bundle entries that `visit_entries` passes to its argument into this datastore,
+    /// then import their annotations using the given policy and redo the analysis of matches,
+    /// returning the number of matches that were newly added.
+    fn import_bundle_entries<F>(&mut self, policy: ImportPolicy, visit_entries: F) -> Result<u64>
+    where
+        F: FnOnce(&mut dyn FnMut(BundleEntry) -> Result<()>) -> Result<()>,
+    {
+        let mut annotations = Vec::new();
+        let mut num_matches_added = 0;
 
-        let ps = get.query_map((metadata.id, max_provenance_entries), val_from_row)?;
+        let tx = self.begin()?;
+        visit_entries(&mut |entry| {
+            match entry {
+                BundleEntry::Rule { syntax } => {
+                    tx.record_rules(&[Rule::new(syntax)])?;
+                }
+                BundleEntry::Blob {
+                    metadata,
+                    provenance,
+                    matches,
+                } => {
+                    let matches = matches
+                        .into_iter()
+                        .map(|m| (m.score, m.match_val))
+                        .collect();
+                    num_matches_added += tx.record(&[(provenance, metadata, matches)])?;
+                }
+                BundleEntry::Annotations { annotations: a } => annotations.push(a),
+            }
+            Ok(())
+        })?;
+        tx.commit()?;
 
-        let results = collect(ps)?;
-        match ProvenanceSet::try_from_iter(results) {
-            Some(ps) => Ok(ps),
-            None => bail!("At least 1 provenance entry must be provided"),
+        for a in annotations.iter() {
+            self.import_annotations(a, policy)?;
         }
+        self.check_match_redundancies()?;
+        self.analyze()?;
+
+        Ok(num_matches_added)
     }
 
-    /// Get the structural IDs of matches that the given one is considered redundant to
-    fn get_redundant_to(&self, match_id: MatchIdInt) -> Result<Vec<String>> {
-        let mut get = self.conn.prepare_cached(indoc! {r#"
-            select m.structural_id
-            from
-                match_redundancy mr
-                inner join match m on (mr.redundant_to = m.id)
-            where mr.match_id = ?
+    /// Get the pattern of the rule with the given structural identifier, if that rule has been
+    /// recorded within this datastore.
+    pub fn get_rule_pattern(&self, rule_structural_id: &str) -> Result<Option<String>> {
+        use rusqlite::OptionalExtension; // for .optional()
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select syntax ->> '$.pattern' from rule where structural_id = ?
+        "#})?;
+        let pattern: Option<Option<String>> = stmt
+            .query_row((rule_structural_id,), val_from_row)
+            .optional()?;
+        Ok(pattern.flatten())
+    }
+
+    /// Get metadata for all groups of identical matches recorded within this datastore.
+    ///
+    /// Findings that have no matches satisfying `filter` are omitted.
+    ///
+    /// This reads all the findings into memory at once; see `visit_finding_metadata` for an
+    /// incremental alternative.
+    pub fn get_finding_metadata(&self, filter: &MatchFilter) -> Result<Vec<FindingMetadata>> {
+        let mut entries = Vec::new();
+        self.visit_finding_metadata(filter, |md| {
+            entries.push(md);
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Call the given function on the metadata for each group of identical matches recorded
+    /// within this datastore, in the same order as `get_finding_metadata`.
+    ///
+    /// Findings that have no matches satisfying `filter` are omitted.
+    ///
+    /// Entries are read incrementally from the database, so only one finding is held in memory at
+    /// a time. This is suitable for datastores with very many findings, at the cost of keeping a
+    /// read statement open on the datastore until the function has been called on every entry.
+    pub fn visit_finding_metadata<F>(&self, filter: &MatchFilter, mut f: F) -> Result<()>
+    where
+        F: FnMut(FindingMetadata) -> Result<()>,
+    {
+        self.query_finding_metadata(filter, None, None, &mut f)
+    }
+
+    /// Get metadata for the findings that have matches that were recorded or changed at or after
+    /// the given time.
+    ///
+    /// This is intended for periodically fetching new results from a long-lived datastore: each
+    /// call can pass the time just before the previous call. Timestamps have a granularity of one
+    /// second, so a finding may be returned by more than one such call.
+    pub fn findings_since(&self, since: SystemTime) -> Result<Vec<FindingMetadata>> {
+        let filter = MatchFilter {
+            updated_since: Some(since),
+            ..Default::default()
+        };
+        self.get_finding_metadata(&filter)
+    }
+
+    /// Get the IDs of findings that have regressed, in sorted order.
+    ///
+    /// A finding has regressed if it has `reject` matches, no `accept` matches, and at least one
+    /// match without a status. This typically means that a previously dismissed secret has been
+    /// found again in new content, e.g., because it was re-committed. If `since` is given, only
+    /// findings with a match without a status that was first recorded at or after that time are
+    /// included.
+    pub fn get_regressed_finding_ids(&self, since: Option<SystemTime>) -> Result<Vec<String>> {
+        let _span =
+            debug_span!("Datastore::get_regressed_finding_ids", "{}", self.root_dir.display())
+                .entered();
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select f.finding_id
+            from finding f
+            where
+                exists (
+                    select 1 from match_denorm m
+                    where m.finding_id = f.finding_id and m.status = 'reject'
+                )
+                and not exists (
+                    select 1 from match_denorm m
+                    where m.finding_id = f.finding_id and m.status = 'accept'
+                )
+                and exists (
+                    select 1 from match_denorm m
+                    where m.finding_id = f.finding_id and m.status is null and m.created_at >= ?
+                )
+            order by f.finding_id
+        "#})?;
+        let since = since.map_or(i64::MIN, unix_timestamp);
+        let entries = stmt.query_map((since,), val_from_row)?;
+        collect(entries)
+    }
+
+    /// Get metadata for the findings in this datastore that are not in the given baseline
+    /// datastore, in the same order as `get_finding_metadata`.
+    ///
+    /// Findings are compared by their IDs. A finding that is in the baseline is not included, even
+    /// if it has matches in this datastore that the baseline does not: the secret it represents
+    /// was already known.
+    ///
+    /// Finding IDs computed with different structural ID algorithms never coincide, so this is an
+    /// error if the datastores have matches with different algorithms.
+    pub fn diff(&self, baseline: &Datastore) -> Result<Vec<FindingMetadata>> {
+        let _span = debug_span!("Datastore::diff", "{}", self.root_dir.display()).entered();
+
+        let new_ids = self.new_finding_ids(baseline)?;
+        if new_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let new_ids = serde_json::to_string(&new_ids)?;
+
+        let mut stmt = self.conn.prepare_cached(&format!(
+            indoc! {r#"
+                select {}
+                from finding_denorm
+                where finding_id in (select value from json_each(?))
+                order by rule_name, rule_structural_id, mean_score desc, groups, finding_id
+            "#},
+            FINDING_METADATA_COLUMNS
+        ))?;
+        let entries = stmt.query_map((new_ids,), finding_metadata_from_row)?;
+        collect(entries)
+    }
+
+    /// Get the IDs of the findings in this datastore that are not in the given baseline datastore.
+    ///
+    /// This is like `diff`, but only reads finding IDs from the two datastores.
+    pub fn new_finding_ids(&self, baseline: &Datastore) -> Result<HashSet<String>> {
+        self.check_same_structural_id_algos(baseline)?;
+
+        let finding_ids = |ds: &Datastore| -> Result<HashSet<String>> {
+            let mut stmt = ds.conn.prepare_cached("select finding_id from finding")?;
+            let ids = stmt
+                .query_map((), val_from_row)?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(ids)
+        };
+        let baseline_ids = finding_ids(baseline)?;
+        let mut ids = finding_ids(self)?;
+        ids.retain(|id| !baseline_ids.contains(id));
+        Ok(ids)
+    }
+
+    /// Is there a finding with the given ID in this datastore?
+    pub fn has_finding(&self, finding_id: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select exists (select 1 from finding where finding_id = ?)
+        "#})?;
+        let exists: bool = stmt.query_row((finding_id,), val_from_row)?;
+        Ok(exists)
+    }
+
+    /// Get a page of up to `limit` findings that remain after filtering, starting just after the
+    /// given cursor, or at the first finding if no cursor is given.
+    ///
+    /// Findings are returned in the same order as `get_finding_metadata`. If the page is full, a
+    /// cursor for fetching the following page is also returned. Fetching a page only reads the
+    /// findings on that page, no matter how many pages precede it.
+    pub fn get_finding_metadata_page(
+        &self,
+        filter: &MatchFilter,
+        after: Option<&FindingCursor>,
+        limit: usize,
+    ) -> Result<(Vec<FindingMetadata>, Option<FindingCursor>)> {
+        let mut page = Vec::new();
+        self.query_finding_metadata(filter, after, Some(limit), &mut |md| {
+            page.push(md);
+            Ok(())
+        })?;
+        let next = if page.len() == limit {
+            page.last().map(FindingCursor::after)
+        } else {
+            None
+        };
+        Ok((page, next))
+    }
+
+    fn query_finding_metadata(
+        &self,
+        filter: &MatchFilter,
+        after: Option<&FindingCursor>,
+        limit: Option<usize>,
+        f: &mut dyn FnMut(FindingMetadata) -> Result<()>,
+    ) -> Result<()> {
+        let _span = debug_span!("Datastore::visit_finding_metadata", "{}", self.root_dir.display())
+            .entered();
+
+        // The cursor's parameters come first, followed by those of the match filter
+        let cursor_params = after.map(FindingCursor::sql_params);
+        let cursor_filter = match after {
+            Some(_) => FindingCursor::sql_condition(),
+            None => "true",
+        };
+
+        let (finding_filter, filter_params) = if filter.needs_match_condition() {
+            self.load_ignored_match_ids(filter)?;
+            let first_param = cursor_params.as_ref().map_or(0, |ps| ps.len()) + 1;
+            let (match_condition, params) = filter.sql_condition(first_param);
+            let finding_filter = format!(
+                indoc! {r#"
+                    exists (
+                        select 1 from match_denorm m
+                        where m.finding_id = finding_denorm.finding_id and {}
+                    )"#},
+                match_condition
+            );
+            (finding_filter, params)
+        } else if filter.suppress_redundant {
+            ("num_matches != num_redundant_matches".to_string(), Vec::new())
+        } else {
+            ("true".to_string(), Vec::new())
+        };
+        let limit = match limit {
+            Some(limit) => limit.to_string(),
+            None => "-1".to_string(),
+        };
+
+        let query_str = format!(
+            indoc! {r#"
+                select {}
+                from finding_denorm
+                where ({}) and {}
+                order by rule_name, rule_structural_id, mean_score desc, groups, finding_id
+                limit {}
+            "#},
+            FINDING_METADATA_COLUMNS, finding_filter, cursor_filter, limit
+        );
+        let params: Vec<&dyn rusqlite::ToSql> = cursor_params
+            .iter()
+            .flatten()
+            .copied()
+            .chain(filter_params.iter().map(|p| p as &dyn rusqlite::ToSql))
+            .collect();
+        let mut stmt = self.conn.prepare_cached(&query_str)?;
+        let entries = stmt.query_map(params.as_slice(), finding_metadata_from_row)?;
+        for entry in entries {
+            f(entry?)?;
+        }
+        Ok(())
+    }
+
+    /// Get up to `max_matches` matches that belong to the finding with the given finding metadata.
+    /// Each match will have up to `max_provenance_entries`.
+    ///
+    /// A value of `None` for either limit value means "no limit".
+    ///
+    /// Matches that do not satisfy `filter` are omitted.
+    pub fn get_finding_data(
+        &self,
+        metadata: &FindingMetadata,
+        max_matches: Option<usize>,
+        max_provenance_entries: Option<usize>,
+        filter: &MatchFilter,
+    ) -> Result<FindingData> {
+        self.get_finding_data_page(metadata, 0, max_matches, max_provenance_entries, filter)
+    }
+
+    /// Like `get_finding_data`, but skips the first `offset` matches of the finding.
+    ///
+    /// Matches are returned in a consistent order, so a large finding can be read incrementally
+    /// by increasing the offset by `max_matches` on each call.
+    pub fn get_finding_data_page(
+        &self,
+        metadata: &FindingMetadata,
+        offset: usize,
+        max_matches: Option<usize>,
+        max_provenance_entries: Option<usize>,
+        filter: &MatchFilter,
+    ) -> Result<FindingData> {
+        let _span =
+            debug_span!("Datastore::get_finding_data", "{}", self.root_dir.display()).entered();
+
+        let match_limit: i64 = match max_matches {
+            Some(max_matches) => max_matches
+                .try_into()
+                .expect("max_matches should be convertible"),
+            None => -1,
+        };
+        let match_offset: i64 = offset.try_into().expect("offset should be convertible");
+
+        self.load_ignored_match_ids(filter)?;
+        let (match_condition, filter_params) = filter.sql_condition(5);
+        let query_str = format!(
+            indoc! {r#"
+            select
+                {},
+
+                {},
+
+                m.id,
+                m.score,
+                m.comment,
+                m.status
+
+            from match_denorm m
+            inner join blob_denorm b on (m.blob_id = b.blob_id)
+            where m.groups = ?1 and m.rule_structural_id = ?2 and {}
+            order by m.blob_id, m.start_byte, m.end_byte
+            limit ?3 offset ?4
+        "#},
+            MATCH_COLUMNS, BLOB_METADATA_COLUMNS, match_condition
+        );
+
+        let params: Vec<&dyn rusqlite::ToSql> = [
+            &metadata.groups as &dyn rusqlite::ToSql,
+            &metadata.rule_structural_id,
+            &match_limit,
+            &match_offset,
+        ]
+        .into_iter()
+        .chain(filter_params.iter().map(|p| p as &dyn rusqlite::ToSql))
+        .collect();
+
+        let mut get_blob_metadata_and_match = self.conn.prepare_cached(&query_str)?;
+
+        let entries = get_blob_metadata_and_match.query_map(params.as_slice(), |row| {
+            let m = match_from_row(row)?;
+            let b = blob_metadata_from_row(row, 18)?;
+            let id = MatchIdInt(row.get(22)?);
+            let m_score = row.get(23)?;
+            let m_comment = row.get(24)?;
+            let m_status = row.get(25)?;
+            Ok((b, id, m, m_score, m_comment, m_status))
+        })?;
+        let mut es = Vec::new();
+        for e in entries {
+            let (md, id, m, match_score, match_comment, match_status) = e?;
+            m.check_span(md.num_bytes)
+                .with_context(|| format!("Invalid match {} in blob {}", m.structural_id, md.id))?;
+            let ps = self.get_provenance_set(&md, max_provenance_entries)?;
+            let redundant_to = self.get_redundant_to(id)?;
+            es.push(FindingDataEntry {
+                provenance: ps,
+                blob_metadata: md,
+                match_id: id,
+                match_val: m,
+                match_comment,
+                match_score,
+                match_status,
+                redundant_to,
+            });
+        }
+        Ok(es)
+    }
+
+    /// Get all the matches found in the blob with the given ID, regardless of rule, ordered by
+    /// their location within the blob.
+    pub fn get_matches_by_blob_id(&self, blob_id: &BlobId) -> Result<Vec<Match>> {
+        let matches = self.get_blob_matches(blob_id)?;
+        Ok(matches.into_iter().map(|(_score, m)| m).collect())
+    }
+
+    /// Get the provenance entries recorded for the blob with the given ID.
+    pub fn get_provenance_by_blob_id(&self, blob_id: &BlobId) -> Result<Vec<Provenance>> {
+        let mut get = self.conn.prepare_cached(indoc! {r#"
+            select provenance
+            from blob_provenance_denorm
+            where blob_id = ?
+            order by provenance
+        "#})?;
+        let ps = get.query_map((blob_id,), val_from_row)?;
+        collect(ps)
+    }
+
+    /// Get the chain of blobs that the blob with the given ID was derived from, according to the
+    /// `parent_*` fields of its extended provenance entries.
+    ///
+    /// The first entry describes the blob's immediate parent, and the last one describes the
+    /// originating blob, whose provenance can be looked up with `get_provenance_by_blob_id`. When
+    /// a blob has several parents, the first one in provenance order is followed. The result is
+    /// empty if the blob was not derived from another one.
+    pub fn get_blob_lineage(&self, blob_id: &BlobId) -> Result<Vec<BlobLineage>> {
+        let mut lineage: Vec<BlobLineage> = Vec::new();
+        let mut seen = HashSet::from([*blob_id]);
+        let mut current = *blob_id;
+        loop {
+            let parent = self
+                .get_provenance_by_blob_id(&current)?
+                .into_iter()
+                .find_map(|p| match p {
+                    Provenance::Extended(e) => e.parent(),
+                    _ => None,
+                });
+            match parent {
+                // Stop at a cycle rather than following it forever
+                Some(parent) if seen.insert(parent.parent_blob) => {
+                    current = parent.parent_blob;
+                    lineage.push(parent);
+                }
+                _ => break,
+            }
+        }
+        Ok(lineage)
+    }
+
+    /// Get the matches in the given blob, along with their scores.
+    fn get_blob_matches(&self, blob_id: &BlobId) -> Result<Vec<(Option<f64>, Match)>> {
+        let _span =
+            debug_span!("Datastore::get_blob_matches", "{}", self.root_dir.display()).entered();
+
+        let mut get = self.conn.prepare_cached(&format!(
+            indoc! {r#"
+                select {}, m.score
+                from match_denorm m
+                where m.blob_id = ?
+                order by m.start_byte, m.end_byte, m.structural_id
+            "#},
+            MATCH_COLUMNS
+        ))?;
+
+        let entries = get.query_map((blob_id,), |row| Ok((row.get(18)?, match_from_row(row)?)))?;
+        collect(entries)
+    }
+
+    fn get_provenance_set(
+        &self,
+        metadata: &BlobMetadata,
+        max_provenance_entries: Option<usize>,
+    ) -> Result<ProvenanceSet> {
+        let max_provenance_entries: i64 = match max_provenance_entries {
+            Some(m) => m.try_into().expect("max_matches should be convertible"),
+            None => -1,
+        };
+        let mut get = self.conn.prepare_cached(indoc! {r#"
+            select provenance
+            from blob_provenance_denorm
+            where blob_id = ?
+            order by provenance
+            limit ?
+        "#})?;
+
+        let ps = get.query_map((metadata.id, max_provenance_entries), val_from_row)?;
+
+        let results = collect(ps)?;
+        match ProvenanceSet::try_from_iter(results) {
+            Some(ps) => Ok(ps),
+            None => {
+                // A blob without provenance shouldn't happen, but rather than failing the entire
+                // report because of one such blob, substitute a placeholder entry
+                warn!("No provenance found for blob {}; using placeholder provenance", metadata.id);
+                Ok(ProvenanceSet::single(Provenance::from_extended(
+                    serde_json::json!({ "display": "unknown" }),
+                )))
+            }
+        }
+    }
+
+    /// Get the structural IDs of matches that the given one is considered redundant to
+    fn get_redundant_to(&self, match_id: MatchIdInt) -> Result<Vec<String>> {
+        let mut get = self.conn.prepare_cached(indoc! {r#"
+            select m.structural_id
+            from
+                match_redundancy mr
+                inner join match m on (mr.redundant_to = m.id)
+            where mr.match_id = ?
             order by m.structural_id
         "#})?;
 
@@ -1035,10 +1777,45 @@ impl Datastore {
         let db_path = root_dir.join("datastore.db");
         let conn = Self::new_connection(&db_path, cache_size)?;
         let root_dir = root_dir.to_path_buf();
-        let ds = Self { root_dir, conn };
+        let ds = Self {
+            root_dir,
+            conn,
+            loaded_ignored_match_ids: RefCell::new(Vec::new()),
+        };
         Ok(ds)
     }
 
+    /// Load the ignored match IDs of the given filter into the `ignored_match_id` temporary table,
+    /// which the condition from `MatchFilter::sql_condition` refers to.
+    ///
+    /// The IDs are kept in a table rather than bound as query parameters, as there may be more of
+    /// them than SQLite allows in a single statement. The table is only rewritten when the IDs
+    /// differ from the ones last loaded.
+    fn load_ignored_match_ids(&self, filter: &MatchFilter) -> Result<()> {
+        let ids = &filter.ignored_match_ids;
+        if ids.is_empty() || *self.loaded_ignored_match_ids.borrow() == *ids {
+            return Ok(());
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute_batch(indoc! {r#"
+            create temp table if not exists ignored_match_id (structural_id text primary key) strict;
+            delete from temp.ignored_match_id;
+        "#})?;
+        {
+            let mut insert = tx.prepare_cached(indoc! {r#"
+                insert or ignore into temp.ignored_match_id (structural_id) values (?1)
+            "#})?;
+            for id in ids {
+                insert.execute((id,))?;
+            }
+        }
+        tx.commit()?;
+
+        *self.loaded_ignored_match_ids.borrow_mut() = ids.clone();
+        Ok(())
+    }
+
     fn new_connection(path: &Path, cache_size: i64) -> Result<Connection> {
         let conn = Connection::open(path)?;
 
@@ -1051,21 +1828,38 @@ impl Datastore {
     }
 
     fn check_schema_version(&self) -> Result<()> {
-        let user_version: u64 = self
-            .conn
-            .pragma_query_value(None, "user_version", val_from_row)?;
-        if user_version != CURRENT_SCHEMA_VERSION {
+        let user_version = self.schema_version()?;
+        if user_version > CURRENT_SCHEMA_VERSION {
+            bail!(
+                "Unsupported schema version {user_version} (expected {}): \
+                  the datastore was created by a newer version of Nosey Parker",
+                CURRENT_SCHEMA_VERSION
+            );
+        }
+        if user_version < OLDEST_SCHEMA_VERSION {
             bail!(
                 "Unsupported schema version {user_version} (expected {}): \
-                  datastores from other versions of Nosey Parker are not supported",
+                  datastores from older versions of Nosey Parker are not supported; \
+                  rescanning the inputs with a new datastore will be required",
                 CURRENT_SCHEMA_VERSION
             );
         }
         Ok(())
     }
 
-    fn migrate_0_70(&mut self) -> Result<()> {
-        let _span = debug_span!("Datastore::migrate_0_70", "{}", self.root_dir.display()).entered();
+    fn migrate_0_77(&mut self) -> Result<()> {
+        let _span = debug_span!("Datastore::migrate_0_77", "{}", self.root_dir.display()).entered();
+
+        // Some migrations rebuild tables that other tables refer to, which requires foreign key
+        // enforcement to be disabled. It cannot be changed within a transaction, so it is done
+        // here, and the constraints are checked before the migrations are committed.
+        self.conn.pragma_update(None, "foreign_keys", "off")?;
+        let result = self.migrate_schema();
+        self.conn.pragma_update(None, "foreign_keys", "on")?;
+        result
+    }
+
+    fn migrate_schema(&mut self) -> Result<()> {
         let tx = self.conn.transaction()?;
 
         let get_user_version = || -> Result<u64> {
@@ -1078,11 +1872,11 @@ impl Datastore {
             Ok(())
         };
 
-        let user_version: u64 = get_user_version()?;
-        if user_version > 0 && user_version < CURRENT_SCHEMA_VERSION {
+        let mut user_version: u64 = get_user_version()?;
+        if user_version > 0 && user_version < OLDEST_SCHEMA_VERSION {
             bail!(
                 "This datastore has schema version {user_version}. \
-                   Datastores from other Nosey Parker versions are not supported. \
+                   Datastores from Nosey Parker versions this old are not supported. \
                    Rescanning the inputs with a new datastore will be required."
             );
         }
@@ -1095,93 +1889,258 @@ impl Datastore {
             debug!("Migrating database schema from version {user_version} to {new_user_version}");
             tx.execute_batch(CURRENT_SCHEMA)?;
             set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        for &(old_user_version, migration) in SCHEMA_MIGRATIONS {
+            if user_version != old_user_version {
+                continue;
+            }
+            let new_user_version = old_user_version + 1;
+            info!(
+                "Migrating datastore at {} from schema version {user_version} to {new_user_version}",
+                self.root_dir.display()
+            );
+            tx.execute_batch(migration)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
         }
 
         assert_eq!(get_user_version()?, CURRENT_SCHEMA_VERSION);
+        if tx.prepare("pragma foreign_key_check")?.exists(())? {
+            bail!("Foreign key constraints are violated after migrating to version {user_version}");
+        }
         tx.commit()?;
 
         Ok(())
     }
 
-    /// Analyze the recorded matches to determine which matches are redundant.
-    /// This populates the `match_redundancy` table.
-    /// This information is needed for suppressing redundant matches at reporting time.
-    pub fn check_match_redundancies(&mut self) -> Result<()> {
-        self.conn.execute(indoc! {r#"
-            insert or ignore into match_redundancy (match_id, redundant_to)
-            with
-                match_overlap_metadata as (
-                    select
-                        match.id,
-                        finding.rule_id in generic_rule_id generic,
-                        finding.rule_id in fuzzy_rule_id fuzzy,
-                        rpl.length pattern_len,
-                        json_array_length(finding.groups) num_groups,
-                        length(finding.groups) groups_len,
-                        match.end_byte - match.start_byte match_len
-                    from
-                        match
-                        inner join finding on (match.finding_id = finding.id)
-                        inner join rule_pattern_length rpl on (finding.rule_id = rpl.rule_id)
-                ),
+    /// Delete all the matches and findings produced by the rule with the given structural
+    /// identifier, returning the number of matches deleted.
+    ///
+    /// Annotations on the deleted matches and findings are deleted too. Provenance entries for
+    /// blobs that no longer have any matches as a result are also deleted, as are source spans
+    /// and snippets that are no longer referenced by any match. The rule itself remains recorded.
+    pub fn delete_matches_by_rule(&mut self, rule_structural_id: &str) -> Result<u64> {
+        let _span = debug_span!("Datastore::delete_matches_by_rule", "{}", self.root_dir.display())
+            .entered();
 
-                ordered_overlapping_match_ids as (
-                    select m1.id m1_id, m2.id m2_id
-                    from
-                        match m1
-                        inner join match m2 on (
-                                m1.blob_id = m2.blob_id
-                            and m1.id != m2.id
-                            and (m1.start_byte <= m2.start_byte and m2.start_byte < m1.end_byte)
-                            -- require at least 20% of both matches to be overlapping to be considered as an overlap
-                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m1.end_byte - m1.start_byte as real) >= 0.20)
-                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m2.end_byte - m2.start_byte as real) >= 0.20)
-                        )
-                ),
+        let tx = self.conn.transaction()?;
 
-                overlapping_match_ids (m1_id, m2_id) as (
-                    select distinct * from (
-                        select m1_id, m2_id from ordered_overlapping_match_ids
-                            union all
-                        select m2_id, m1_id from ordered_overlapping_match_ids
+        // Orphaned provenance has to be determined while the rule's matches still exist
+        tx.execute(
+            indoc! {r#"
+                delete from blob_provenance
+                where
+                    blob_id in (
+                        select m.blob_id
+                        from match m
+                            inner join finding f on (m.finding_id = f.id)
+                            inner join rule r on (f.rule_id = r.id)
+                        where r.structural_id = ?1
                     )
-                )
-
-            select
-                o.m1_id,
-                o.m2_id
-            from
-                overlapping_match_ids o
-                inner join match_overlap_metadata md1 on (o.m1_id = md1.id)
-                inner join match_overlap_metadata md2 on (o.m2_id = md2.id)
-            where
-                -- a generic match can only replace another generic one
-                (not md2.generic or md1.generic)
-                    and
-                -- a match can only replace one if it has at least as many groups
-                (md2.num_groups >= md1.num_groups)
-                    and
-                (
-                    -- a match can replace another with the same number of groups if any of the following apply:
-                    -- * its group content is longer
-                    -- * it is not fuzzy
-                    -- * its group length is the same but it matches a shorter amount of overall input
-                    -- * it has a longer pattern
-                    not (md2.num_groups = md1.num_groups) or (
-                        md2.groups_len > md1.groups_len
-                            or
-                        not md2.fuzzy
-                            or
-                        (md2.groups_len = md1.groups_len and md2.match_len < md1.match_len)
-                            or
-                        md2.pattern_len > md1.pattern_len
+                    and not exists (
+                        select 1
+                        from match m
+                            inner join finding f on (m.finding_id = f.id)
+                            inner join rule r on (f.rule_id = r.id)
+                        where m.blob_id = blob_provenance.blob_id and r.structural_id != ?1
                     )
-                )
-        "#}, [])?;
+            "#},
+            (rule_structural_id,),
+        )?;
 
-        Ok(())
-    }
-}
+        let rule_match_ids = indoc! {r#"
+            select m.id
+            from match m
+                inner join finding f on (m.finding_id = f.id)
+                inner join rule r on (f.rule_id = r.id)
+            where r.structural_id = ?1
+        "#};
+        for table in [
+            "match_status",
+            "match_comment",
+            "match_score",
+            "match_context",
+        ] {
+            tx.execute(
+                &format!("delete from {table} where match_id in ({rule_match_ids})"),
+                (rule_structural_id,),
+            )?;
+        }
+        tx.execute(
+            &format!(
+                "delete from match_redundancy \
+                 where match_id in ({rule_match_ids}) or redundant_to in ({rule_match_ids})"
+            ),
+            (rule_structural_id,),
+        )?;
+
+        let num_deleted = tx.execute(
+            &format!("delete from match where id in ({rule_match_ids})"),
+            (rule_structural_id,),
+        )?;
+
+        let rule_finding_ids = indoc! {r#"
+            select f.id
+            from finding f
+                inner join rule r on (f.rule_id = r.id)
+            where r.structural_id = ?1
+        "#};
+        tx.execute(
+            &format!("delete from finding_comment where finding_id in ({rule_finding_ids})"),
+            (rule_structural_id,),
+        )?;
+        tx.execute(
+            &format!("delete from finding where id in ({rule_finding_ids})"),
+            (rule_structural_id,),
+        )?;
+
+        tx.execute_batch(indoc! {r#"
+            delete from blob_source_span
+            where not exists (
+                select 1 from match m
+                where
+                    m.blob_id = blob_source_span.blob_id
+                    and m.start_byte = blob_source_span.start_byte
+                    and m.end_byte = blob_source_span.end_byte
+            );
+
+            delete from snippet
+            where
+                id not in (select before_snippet_id from match)
+                and id not in (select matching_snippet_id from match)
+                and id not in (select after_snippet_id from match);
+        "#})?;
+
+        tx.commit()?;
+        Ok(num_deleted as u64)
+    }
+
+    /// Redo the analysis of recorded matches that is otherwise performed at the end of a scan.
+    ///
+    /// The `match_redundancy` table is cleared and repopulated from the current matches in a
+    /// single transaction, so if this fails, the previous analysis is kept. The sqlite database is
+    /// then analyzed. Running this more than once has no further effect.
+    pub fn reanalyze(&mut self) -> Result<()> {
+        let _span = debug_span!("Datastore::reanalyze", "{}", self.root_dir.display()).entered();
+        let tx = self.conn.transaction()?;
+        tx.execute("delete from match_redundancy", [])?;
+        Self::check_match_redundancies_impl(&tx, "true")?;
+        tx.commit()?;
+        self.analyze()
+    }
+
+    /// Analyze the recorded matches to determine which matches are redundant.
+    /// This populates the `match_redundancy` table.
+    /// This information is needed for suppressing redundant matches at reporting time.
+    pub fn check_match_redundancies(&mut self) -> Result<()> {
+        Self::check_match_redundancies_impl(&self.conn, "true")
+    }
+
+    /// Like `check_match_redundancies`, but only examine the blobs that have matches that were
+    /// first recorded at or after the given time.
+    ///
+    /// Whether a match is redundant depends only on the other matches in its blob, so after
+    /// recording new matches into a datastore whose earlier matches have already been checked,
+    /// this gives the same result as `check_match_redundancies` while examining far fewer
+    /// matches.
+    pub fn check_match_redundancies_since(&mut self, since: SystemTime) -> Result<()> {
+        let _span =
+            debug_span!("Datastore::check_match_redundancies_since", "{}", self.root_dir.display())
+                .entered();
+        Self::check_match_redundancies_impl(
+            &self.conn,
+            &format!(
+                "m1.blob_id in (select blob_id from match where created_at >= {})",
+                unix_timestamp(since)
+            ),
+        )
+    }
+
+    /// Populate the `match_redundancy` table using the given connection, only examining pairs of
+    /// matches `m1` and `m2` that satisfy the given SQL condition.
+    fn check_match_redundancies_impl(conn: &Connection, condition: &str) -> Result<()> {
+        let query = format!(
+            indoc! {r#"
+                insert or ignore into match_redundancy (match_id, redundant_to)
+                with
+                    match_overlap_metadata as (
+                        select
+                            match.id,
+                            finding.rule_id in generic_rule_id generic,
+                            finding.rule_id in fuzzy_rule_id fuzzy,
+                            rpl.length pattern_len,
+                            json_array_length(finding.groups) num_groups,
+                            length(finding.groups) groups_len,
+                            match.end_byte - match.start_byte match_len
+                        from
+                            match
+                            inner join finding on (match.finding_id = finding.id)
+                            inner join rule_pattern_length rpl on (finding.rule_id = rpl.rule_id)
+                    ),
+
+                    ordered_overlapping_match_ids as (
+                        select m1.id m1_id, m2.id m2_id
+                        from
+                            match m1
+                            inner join match m2 on (
+                                    m1.blob_id = m2.blob_id
+                                and m1.id != m2.id
+                                and (m1.start_byte <= m2.start_byte and m2.start_byte < m1.end_byte)
+                                -- require at least 20% of both matches to be overlapping to be considered as an overlap
+                                and (cast(m1.end_byte - m2.start_byte as real) / cast(m1.end_byte - m1.start_byte as real) >= 0.20)
+                                and (cast(m1.end_byte - m2.start_byte as real) / cast(m2.end_byte - m2.start_byte as real) >= 0.20)
+                            )
+                        where {}
+                    ),
+
+                    overlapping_match_ids (m1_id, m2_id) as (
+                        select distinct * from (
+                            select m1_id, m2_id from ordered_overlapping_match_ids
+                                union all
+                            select m2_id, m1_id from ordered_overlapping_match_ids
+                        )
+                    )
+
+                select
+                    o.m1_id,
+                    o.m2_id
+                from
+                    overlapping_match_ids o
+                    inner join match_overlap_metadata md1 on (o.m1_id = md1.id)
+                    inner join match_overlap_metadata md2 on (o.m2_id = md2.id)
+                where
+                    -- a generic match can only replace another generic one
+                    (not md2.generic or md1.generic)
+                        and
+                    -- a match can only replace one if it has at least as many groups
+                    (md2.num_groups >= md1.num_groups)
+                        and
+                    (
+                        -- a match can replace another with the same number of groups if any of the following apply:
+                        -- * its group content is longer
+                        -- * it is not fuzzy
+                        -- * its group length is the same but it matches a shorter amount of overall input
+                        -- * it has a longer pattern
+                        not (md2.num_groups = md1.num_groups) or (
+                            md2.groups_len > md1.groups_len
+                                or
+                            not md2.fuzzy
+                                or
+                            (md2.groups_len = md1.groups_len and md2.match_len < md1.match_len)
+                                or
+                            md2.pattern_len > md1.pattern_len
+                        )
+                    )
+            "#},
+            condition
+        );
+        conn.execute(&query, [])?;
+
+        Ok(())
+    }
+}
 
 // -------------------------------------------------------------------------------------------------
 // Implementation Utilities
@@ -1198,6 +2157,142 @@ where
     Ok(entries)
 }
 
+/// The columns of the `finding_denorm` view that are decoded by `finding_metadata_from_row`.
+const FINDING_METADATA_COLUMNS: &str = indoc! {r#"
+    finding_id,
+    groups,
+    rule_structural_id,
+    rule_text_id,
+    rule_name,
+    num_matches,
+    num_redundant_matches,
+    comment,
+    match_statuses,
+    mean_score,
+    coalesce(json_extract(rule_syntax, '$.references'), '[]')"#};
+
+/// Decode a `FindingMetadata` from a row of a query whose columns are `FINDING_METADATA_COLUMNS`.
+fn finding_metadata_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FindingMetadata> {
+    let rule_references: String = row.get(10)?;
+    let rule_references = serde_json::from_str(&rule_references).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(10, rusqlite::types::Type::Text, e.into())
+    })?;
+    Ok(FindingMetadata {
+        finding_id: row.get(0)?,
+        groups: row.get(1)?,
+        rule_structural_id: row.get(2)?,
+        rule_text_id: row.get(3)?,
+        rule_name: row.get(4)?,
+        rule_references,
+        num_matches: row.get(5)?,
+        num_redundant_matches: row.get(6)?,
+        comment: row.get(7)?,
+        statuses: row.get(8)?,
+        mean_score: row.get(9)?,
+    })
+}
+
+/// The columns of the `match_denorm` view, aliased as `m`, that are decoded by `match_from_row`.
+const MATCH_COLUMNS: &str = indoc! {r#"
+    m.blob_id,
+    m.start_byte,
+    m.end_byte,
+    m.start_line,
+    m.start_column,
+    m.end_line,
+    m.end_column,
+    m.before_snippet,
+    m.matching_snippet,
+    m.after_snippet,
+    m.groups,
+    m.rule_structural_id,
+    m.rule_name,
+    m.rule_text_id,
+    m.structural_id,
+    m.context,
+    m.before_truncated,
+    m.after_truncated"#};
+
+/// Decode a `Match` from a row of a query whose first columns are `MATCH_COLUMNS`.
+fn match_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Match> {
+    Ok(Match {
+        blob_id: row.get(0)?,
+        location: Location {
+            offset_span: OffsetSpan {
+                start: row.get(1)?,
+                end: row.get(2)?,
+            },
+            source_span: SourceSpan {
+                start: SourcePoint {
+                    line: row.get(3)?,
+                    column: row.get(4)?,
+                },
+                end: SourcePoint {
+                    line: row.get(5)?,
+                    column: row.get(6)?,
+                },
+            },
+        },
+        snippet: Snippet {
+            before: BString::new(row.get(7)?),
+            matching: BString::new(row.get(8)?),
+            after: BString::new(row.get(9)?),
+            before_truncated: row.get(16)?,
+            after_truncated: row.get(17)?,
+        },
+        groups: row.get(10)?,
+        rule_structural_id: row.get(11)?,
+        rule_name: row.get(12)?,
+        rule_text_id: row.get(13)?,
+        structural_id: row.get(14)?,
+        context: row.get(15)?,
+    })
+}
+
+/// The columns of the `blob_denorm` view, aliased as `b`, that are decoded by
+/// `blob_metadata_from_row`.
+const BLOB_METADATA_COLUMNS: &str = "b.blob_id, b.size, b.mime_essence, b.charset";
+
+/// Decode a `BlobMetadata` from a row of a query that selects `BLOB_METADATA_COLUMNS` starting at
+/// the given column index.
+fn blob_metadata_from_row(row: &rusqlite::Row<'_>, start: usize) -> rusqlite::Result<BlobMetadata> {
+    Ok(BlobMetadata {
+        id: row.get(start)?,
+        num_bytes: row.get(start + 1)?,
+        mime_essence: row.get(start + 2)?,
+        charset: row.get(start + 3)?,
+    })
+}
+
+/// Decode a `MatchAnnotation` from a row of the query in `Datastore::get_annotations`.
+fn match_annotation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MatchAnnotation> {
+    Ok(MatchAnnotation {
+        finding_id: row.get(0)?,
+        rule_name: row.get(1)?,
+        rule_text_id: row.get(2)?,
+        rule_structural_id: row.get(3)?,
+        match_id: row.get(4)?,
+        blob_id: row.get(5)?,
+        start_byte: row.get(6)?,
+        end_byte: row.get(7)?,
+        groups: row.get(8)?,
+        status: row.get(9)?,
+        comment: row.get(10)?,
+    })
+}
+
+/// Decode a `FindingAnnotation` from a row of the query in `Datastore::get_annotations`.
+fn finding_annotation_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FindingAnnotation> {
+    Ok(FindingAnnotation {
+        finding_id: row.get(0)?,
+        rule_name: row.get(1)?,
+        rule_text_id: row.get(2)?,
+        rule_structural_id: row.get(3)?,
+        groups: row.get(4)?,
+        comment: row.get(5)?,
+    })
+}
+
 /// Convert a row into a single value.
 ///
 /// This function exists to work around an ergonomic deficiency in Rust's type system, which
@@ -1210,6 +2305,14 @@ where
     row.get(0)
 }
 
+/// Convert a time into the number of seconds since the Unix epoch, as stored in the datastore.
+pub(crate) fn unix_timestamp(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
 /// A combinator for "upsert"-like behavior that sqlite doesn't nicely natively support.
 ///
 /// This takes two SQL statement arguments: a getter and a setter.
@@ -1259,8 +2362,22 @@ where
 }
 
 /// Get a path for a local clone of the given git URL underneath `root`.
+///
+/// The path is determined by the URL's scheme, host, port, and path segments, so distinct URLs get
+/// distinct paths. An error is returned if the resulting path would not be strictly within `root`.
 fn clone_destination(root: &std::path::Path, repo: &GitUrl) -> Result<std::path::PathBuf> {
-    Ok(root.join(repo.to_path_buf()))
+    use std::path::Component;
+
+    // `GitUrl` already rejects `..` path segments, but a clone is written to this path, so be
+    // certain that nothing in the URL can make it escape `root`.
+    let relative = repo.to_path_buf();
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        bail!("Refusing to clone {repo}: its destination would be outside the clones directory");
+    }
+    Ok(root.join(relative))
 }
 
 #[cfg(test)]
@@ -1291,5 +2408,1713 @@ mod test {
     clone_destination_success_tests! {
         https_01: ("rel_root", "https://example.com/testrepo.git") => "rel_root/https/example.com/testrepo.git",
         https_02: ("/abs_root", "https://example.com/testrepo.git") => "/abs_root/https/example.com/testrepo.git",
+        https_03: ("rel_root", "https://example.com:8443/testrepo.git") => "rel_root/https/example.com:8443/testrepo.git",
+        https_04: ("rel_root", "https://example.com/org/testrepo.git") => "rel_root/https/example.com/org/testrepo.git",
+        https_05: ("rel_root", "https://example.com/%2e%2e/%2E%2E/boom.git") => "rel_root/https/example.com/boom.git",
+    }
+
+    #[test]
+    fn clone_destination_distinct() {
+        use super::clone_destination;
+        use crate::git_url::GitUrl;
+        use std::collections::HashSet;
+        use std::path::Path;
+        use std::str::FromStr;
+
+        let urls = [
+            "https://example.com/testrepo.git",
+            "https://example.com/testrepo",
+            "https://example.com:8443/testrepo.git",
+            "https://example.org/testrepo.git",
+            "https://example.com/other/testrepo.git",
+        ];
+        let root = Path::new("clones");
+        let dests: HashSet<_> = urls
+            .iter()
+            .map(|url| {
+                let repo = GitUrl::from_str(url).unwrap();
+                clone_destination(root, &repo).unwrap()
+            })
+            .collect();
+        assert_eq!(dests.len(), urls.len());
+    }
+
+    #[test]
+    fn clone_destination_contained() {
+        use super::clone_destination;
+        use crate::git_url::GitUrl;
+        use std::path::{Component, Path};
+        use std::str::FromStr;
+
+        let root = Path::new("/clones");
+        for url in [
+            "https://example.com/../../../etc/passwd",
+            "https://example.com/%2e%2e/%2e%2e/etc/passwd",
+            "https://example.com/..%2f..%2fetc%2fpasswd",
+            "https://example.com/a/..\\..\\..\\etc",
+            "https://example.com/./././boom.git",
+            "https://[::1]/boom.git",
+        ] {
+            let Ok(repo) = GitUrl::from_str(url) else {
+                continue;
+            };
+            let dest = clone_destination(root, &repo).unwrap();
+            let rel = dest.strip_prefix(root).unwrap();
+            assert!(
+                rel.components().all(|c| matches!(c, Component::Normal(_))),
+                "{url} maps to {}",
+                dest.display()
+            );
+        }
+    }
+
+    /// Helpers shared by the datastore tests below
+    mod fixture {
+        use super::super::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Groups;
+        use noseyparker_rules::RuleSyntax;
+        use tempfile::TempDir;
+
+        pub fn mk_rule() -> Rule {
+            Rule::new(RuleSyntax {
+                id: "test.1".to_string(),
+                name: "test".to_string(),
+                pattern: "(test)".to_string(),
+                examples: vec![],
+                negative_examples: vec![],
+                references: vec![],
+                categories: vec![],
+                description: None,
+                match_filenames: false,
+            })
+        }
+
+        pub fn mk_match(rule: &Rule) -> Match {
+            let point = SourcePoint { line: 1, column: 0 };
+            Match {
+                blob_id: BlobId::compute_from_bytes(b"test"),
+                location: Location {
+                    offset_span: OffsetSpan { start: 0, end: 4 },
+                    source_span: SourceSpan {
+                        start: point,
+                        end: point,
+                    },
+                },
+                groups: Groups(smallvec::smallvec![crate::match_type::Group::unnamed("test")]),
+                snippet: Snippet {
+                    before: "".into(),
+                    matching: "test".into(),
+                    after: "".into(),
+                    before_truncated: false,
+                    after_truncated: false,
+                },
+                structural_id: "0".repeat(40),
+                rule_structural_id: rule.structural_id().to_owned(),
+                rule_text_id: rule.id().to_owned(),
+                rule_name: rule.name().to_owned(),
+                context: None,
+            }
+        }
+
+        /// Create a new datastore in a temporary directory.
+        ///
+        /// The directory is removed when the returned `TempDir` is dropped.
+        pub fn mk_datastore() -> Result<(TempDir, Datastore)> {
+            let dir = TempDir::new()?;
+            let ds = Datastore::create(&dir.path().join("datastore"), -1024)?;
+            Ok((dir, ds))
+        }
+
+        /// Create a new datastore as with `mk_datastore`, with the rule from `mk_rule` recorded.
+        pub fn mk_datastore_with_rule() -> Result<(TempDir, Datastore, Rule)> {
+            let (dir, mut ds) = mk_datastore()?;
+            let rule = mk_rule();
+            let tx = ds.begin()?;
+            tx.record_rules(std::slice::from_ref(&rule))?;
+            tx.commit()?;
+            Ok((dir, ds, rule))
+        }
+
+        /// The schema for `OLDEST_SCHEMA_VERSION`, as it was when that version was current
+        const OLDEST_SCHEMA: &str = include_str!("datastore/migrations/schema_70.sql");
+
+        /// Create a datastore with the given older schema version in a temporary directory,
+        /// containing the rule from `mk_rule` and the match from `mk_match`.
+        ///
+        /// The datastore is populated using the oldest supported schema and then migrated to the
+        /// given version, without opening it as a `Datastore`.
+        pub fn mk_old_datastore(version: u64) -> Result<(TempDir, Match)> {
+            let dir = TempDir::new()?;
+            let conn = Connection::open(dir.path().join("datastore.db"))?;
+            conn.execute_batch(OLDEST_SCHEMA)?;
+            conn.pragma_update(None, "user_version", OLDEST_SCHEMA_VERSION)?;
+
+            let rule = mk_rule();
+            let m = mk_match(&rule);
+            conn.execute(
+                "insert into rule (id, structural_id, name, text_id, syntax) values (1, ?, ?, ?, ?)",
+                (rule.structural_id(), rule.name(), rule.id(), rule.json_syntax()),
+            )?;
+            conn.execute("insert into blob (id, blob_id, size) values (1, ?, 4)", (&m.blob_id,))?;
+            conn.execute(
+                "insert into blob_provenance (blob_id, provenance) values (1, ?)",
+                (Provenance::from_file("input.txt".into()),),
+            )?;
+            conn.execute(
+                "insert into finding (id, finding_id, rule_id, groups) values (1, ?, 1, ?)",
+                (m.finding_id(), &m.groups),
+            )?;
+            conn.execute_batch(indoc! {r#"
+                insert into blob_source_span
+                    (blob_id, start_byte, end_byte, start_line, start_column, end_line, end_column)
+                values (1, 0, 4, 1, 0, 1, 0);
+
+                insert into snippet (id, snippet) values (1, x''), (2, cast('test' as blob));
+            "#})?;
+            conn.execute(
+                indoc! {r#"
+                    insert into match (
+                        id, structural_id, finding_id, blob_id, start_byte, end_byte,
+                        before_snippet_id, matching_snippet_id, after_snippet_id
+                    )
+                    values (1, ?, 1, 1, 0, 4, 1, 2, 1)
+                "#},
+                (&m.structural_id,),
+            )?;
+
+            // Some migrations rebuild tables, which requires foreign keys to be off, as when
+            // migrating an opened datastore
+            conn.pragma_update(None, "foreign_keys", false)?;
+            for &(old_version, migration) in SCHEMA_MIGRATIONS {
+                if old_version < version {
+                    conn.execute_batch(migration)?;
+                }
+            }
+            conn.pragma_update(None, "foreign_keys", true)?;
+            conn.pragma_update(None, "user_version", version)?;
+
+            Ok((dir, m))
+        }
+    }
+
+    /// Tests of the datastore's database schema and storage
+    mod database {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Group;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn lookups_use_indexes() -> Result<()> {
+            let (_dir, ds) = mk_datastore()?;
+
+            let queries = [
+                "select id from rule where structural_id = ?",
+                "select id from finding where finding_id = ?",
+                "select id from match where finding_id = ?",
+                "select id from match where blob_id = ?",
+                "select id from match where structural_id = ?",
+                "select blob_id from match where created_at >= ?",
+                "select provenance from blob_provenance where blob_id = ?",
+            ];
+            for query in queries {
+                let mut stmt = ds.conn.prepare(&format!("explain query plan {query}"))?;
+                let details: Vec<String> = stmt
+                    .query_map((0,), |row| row.get(3))?
+                    .collect::<rusqlite::Result<_>>()?;
+                assert!(
+                    details.iter().all(|d| d.contains(" USING ")),
+                    "query `{query}` does not use an index: {details:?}"
+                );
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn other_schema_version_is_refused() -> Result<()> {
+            let (_dir, ds) = mk_datastore()?;
+            assert_eq!(ds.schema_version()?, CURRENT_SCHEMA_VERSION);
+            let root_dir = ds.root_dir().to_owned();
+
+            let set_version = |version: u64| -> Result<()> {
+                let conn = Connection::open(root_dir.join("datastore.db"))?;
+                conn.pragma_update(None, "user_version", version)?;
+                Ok(())
+            };
+
+            set_version(OLDEST_SCHEMA_VERSION - 1)?;
+            let err = Datastore::open(&root_dir, -1024).err().unwrap();
+            assert!(format!("{err:#}").contains("not supported"), "{err:#}");
+
+            set_version(CURRENT_SCHEMA_VERSION + 1)?;
+            let err = Datastore::open(&root_dir, -1024).err().unwrap();
+            assert!(format!("{err:#}").contains("newer version"), "{err:#}");
+
+            set_version(CURRENT_SCHEMA_VERSION)?;
+            let ds2 = Datastore::open(&root_dir, -1024)?;
+            assert_eq!(ds2.schema_version()?, CURRENT_SCHEMA_VERSION);
+
+            Ok(())
+        }
+
+        #[test]
+        fn old_schema_versions_are_migrated() -> Result<()> {
+            let (_dir, ds) = mk_datastore()?;
+            let objects = |conn: &Connection| -> Result<Vec<(String, String)>> {
+                let mut stmt = conn.prepare(
+                    "select type, name from sqlite_schema where name not like 'sqlite_%' order by 1, 2",
+                )?;
+                let objects = stmt.query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?;
+                Ok(objects.collect::<rusqlite::Result<_>>()?)
+            };
+            let expected_objects = objects(&ds.conn)?;
+
+            for version in OLDEST_SCHEMA_VERSION..CURRENT_SCHEMA_VERSION {
+                let (dir, m) = mk_old_datastore(version)?;
+                let ds = Datastore::open(dir.path(), -1024)?;
+                assert_eq!(ds.schema_version()?, CURRENT_SCHEMA_VERSION, "version {version}");
+                assert_eq!(objects(&ds.conn)?, expected_objects, "version {version}");
+
+                let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+                assert_eq!(metadata.len(), 1, "version {version}");
+                assert_eq!(metadata[0].finding_id, m.finding_id(), "version {version}");
+                let data =
+                    ds.get_finding_data(&metadata[0], None, None, &MatchFilter::default())?;
+                assert_eq!(data.len(), 1, "version {version}");
+                assert_eq!(data[0].match_val.structural_id, m.structural_id, "version {version}");
+                assert_eq!(data[0].match_val.blob_id, m.blob_id, "version {version}");
+                assert!(!data[0].match_val.snippet.before_truncated, "version {version}");
+                assert!(!data[0].match_val.snippet.after_truncated, "version {version}");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn migrated_finding_ids_allow_other_algos() -> Result<()> {
+            for version in OLDEST_SCHEMA_VERSION..CURRENT_SCHEMA_VERSION {
+                let (dir, m) = mk_old_datastore(version)?;
+                let mut ds = Datastore::open(dir.path(), -1024)?;
+
+                let mut m2 = m.clone();
+                m2.structural_id = format!("sha256:{}", "a".repeat(64));
+                m2.groups = crate::match_type::Groups(smallvec::smallvec![Group::unnamed("tset")]);
+                assert!(m2.finding_id().starts_with("sha256:"));
+                assert!(ds.insert_test_match(&m2, Provenance::from_file("input.txt".into()))?);
+                assert_eq!(ds.get_num_findings()?, 2, "version {version}");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn text_statuses_are_migrated() -> Result<()> {
+            for (name, status) in [
+                ("accept", Status::Accept),
+                ("reject", Status::Reject),
+                ("needs_review", Status::NeedsReview),
+            ] {
+                let (dir, m) = mk_old_datastore(73)?;
+                let conn = Connection::open(dir.path().join("datastore.db"))?;
+                conn.execute("insert into match_status (match_id, status) values (1, ?)", (name,))?;
+                drop(conn);
+
+                let ds = Datastore::open(dir.path(), -1024)?;
+                assert_eq!(ds.schema_version()?, CURRENT_SCHEMA_VERSION);
+                let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+                assert_eq!(metadata.len(), 1);
+                assert_eq!(metadata[0].finding_id, m.finding_id());
+                assert_eq!(metadata[0].statuses.as_slice(), [status]);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn compact_keeps_data() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for i in 0..100 {
+                let mut m = mk_match(&rule);
+                m.blob_id = BlobId::compute_from_bytes(format!("blob {i}").as_bytes());
+                m.structural_id = format!("{i:040x}");
+                ds.insert_test_match(&m, Provenance::from_file(format!("{i}.txt").into()))?;
+            }
+            assert_eq!(ds.delete_matches_by_rule(rule.structural_id())?, 100);
+            ds.insert_test_match(&mk_match(&rule), Provenance::from_file("input.txt".into()))?;
+
+            let before = ds.database_size()?;
+            ds.compact()?;
+            let after = ds.database_size()?;
+            assert!(after < before, "{after} should be less than {before}");
+
+            assert_eq!(ds.get_num_matches()?, 1);
+            assert_eq!(ds.list_provenance()?, vec![Provenance::from_file("input.txt".into())]);
+
+            Ok(())
+        }
+    }
+
+    /// Tests of grouping matches into findings and reading finding metadata
+    mod findings {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Groups;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn distinct_groups_are_distinct_findings() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            m2.structural_id = "b".repeat(40);
+            assert_ne!(m1.finding_id(), m2.finding_id());
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            ds.insert_test_match(&m2, Provenance::from_file("input.txt".into()))?;
+
+            assert_eq!(ds.get_num_matches()?, 2);
+            assert_eq!(ds.get_num_findings()?, 2);
+            ds.analyze()?;
+            let mut finding_ids: Vec<String> = ds
+                .get_finding_metadata(&MatchFilter::default())?
+                .into_iter()
+                .map(|md| md.finding_id)
+                .collect();
+            finding_ids.sort();
+            let mut expected = vec![m1.finding_id(), m2.finding_id()];
+            expected.sort();
+            assert_eq!(finding_ids, expected);
+
+            Ok(())
+        }
+
+        #[test]
+        fn seeded_match_is_reported() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m = mk_match(&rule);
+            assert!(ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?);
+            assert!(!ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?);
+
+            assert_eq!(ds.get_num_matches()?, 1);
+            assert_eq!(ds.get_num_findings()?, 1);
+            assert_eq!(
+                ds.stats()?,
+                DatastoreStats {
+                    schema_version: CURRENT_SCHEMA_VERSION,
+                    num_matches: 1,
+                    num_findings: 1,
+                    distinct_rules: 1,
+                    num_provenance: 1,
+                    num_annotations: 0,
+                    database_size: ds.database_size()?,
+                }
+            );
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(metadata[0].finding_id, m.finding_id());
+            assert_eq!(
+                ds.get_rule_pattern(&metadata[0].rule_structural_id)?
+                    .as_deref(),
+                Some("(test)")
+            );
+            assert_eq!(ds.get_rule_pattern("no such rule")?, None);
+
+            let data = ds.get_finding_data(&metadata[0], None, None, &MatchFilter::default())?;
+            assert_eq!(data.len(), 1);
+            assert_eq!(data[0].match_val.structural_id, m.structural_id);
+            assert_eq!(data[0].provenance.len(), 1);
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_num_matches() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for (i, content) in ["test 1", "test 2", "test 3"].iter().enumerate() {
+                let mut m = mk_match(&rule);
+                m.blob_id = BlobId::compute_from_bytes(content.as_bytes());
+                m.structural_id = i.to_string().repeat(40);
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            }
+            ds.analyze()?;
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(metadata[0].num_matches, 3);
+            assert_eq!(metadata[0].num_redundant_matches, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_mean_score() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // Three scored and unscored matches of one finding, and an unscored match of another
+            let mut batch = Vec::new();
+            for (i, score) in [Some(0.25), None, Some(0.75)].into_iter().enumerate() {
+                let mut m = mk_match(&rule);
+                m.blob_id = BlobId::compute_from_bytes(format!("test {i}").as_bytes());
+                m.structural_id = i.to_string().repeat(40);
+                batch.push((score, m));
+            }
+            let mut unscored = mk_match(&rule);
+            unscored.groups =
+                Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            unscored.structural_id = "b".repeat(40);
+            batch.push((None, unscored));
+
+            let tx = ds.begin()?;
+            for (score, m) in batch {
+                let blob_metadata = BlobMetadata {
+                    id: m.blob_id,
+                    num_bytes: 4,
+                    mime_essence: None,
+                    charset: None,
+                };
+                let provenance = Provenance::from_file("input.txt".into()).into();
+                tx.record(&[(provenance, blob_metadata, vec![(score, m)])])?;
+            }
+            tx.commit()?;
+            ds.analyze()?;
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            let mean_scores: Vec<(usize, Option<f64>)> = metadata
+                .iter()
+                .map(|md| (md.num_matches, md.mean_score))
+                .collect();
+            assert_eq!(mean_scores, [(3, Some(0.5)), (1, None)]);
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_groups() -> Result<()> {
+            use crate::match_type::Group;
+
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let mut m = mk_match(&rule);
+            m.groups = Groups(smallvec::smallvec![
+                Group::unnamed("AKIADEADBEEF"),
+                Group {
+                    value: "s3cr3t".into(),
+                    name: Some("secret".into()),
+                },
+            ]);
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(metadata[0].groups, m.groups);
+
+            let data = ds.get_finding_data(&metadata[0], None, None, &MatchFilter::default())?;
+            assert_eq!(data.len(), 1);
+            assert_eq!(data[0].match_val.groups, metadata[0].groups);
+
+            Ok(())
+        }
+
+        #[test]
+        fn structural_id_algos() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            assert_eq!(ds.get_structural_id_algos()?, []);
+
+            let m1 = mk_match(&rule);
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            assert_eq!(ds.get_structural_id_algos()?, [StructuralIdAlgo::Sha1]);
+
+            let mut m2 = mk_match(&rule);
+            m2.structural_id = format!("blake3:{}", "0".repeat(64));
+            m2.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            assert_ne!(m1.finding_id(), m2.finding_id());
+            ds.insert_test_match(&m2, Provenance::from_file("input.txt".into()))?;
+            assert_eq!(
+                ds.get_structural_id_algos()?,
+                [StructuralIdAlgo::Sha1, StructuralIdAlgo::Blake3]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_data_pages() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for i in 0..5 {
+                let mut m = mk_match(&rule);
+                m.blob_id = BlobId::compute_from_bytes(format!("blob {i}").as_bytes());
+                m.structural_id = format!("{i:040x}");
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            let filter = MatchFilter::default();
+            let metadata = ds.get_finding_metadata(&filter)?;
+            assert_eq!(metadata.len(), 1);
+            let blob_ids = |data: FindingData| -> Vec<BlobId> {
+                data.into_iter().map(|e| e.match_val.blob_id).collect()
+            };
+
+            let all = blob_ids(ds.get_finding_data(&metadata[0], None, None, &filter)?);
+            assert_eq!(all.len(), 5);
+
+            let page = |offset, limit| -> Result<Vec<BlobId>> {
+                let data =
+                    ds.get_finding_data_page(&metadata[0], offset, Some(limit), None, &filter)?;
+                Ok(blob_ids(data))
+            };
+            assert_eq!(page(0, 2)?, &all[0..2]);
+            assert_eq!(page(2, 2)?, &all[2..4]);
+            assert_eq!(page(4, 2)?, &all[4..5]);
+            assert!(page(5, 2)?.is_empty());
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_metadata_pages() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for (i, secret) in ["alpha", "bravo", "charlie", "delta", "echo"]
+                .iter()
+                .enumerate()
+            {
+                let mut m = mk_match(&rule);
+                m.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed(*secret)]);
+                m.structural_id = i.to_string().repeat(40);
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            // The second filter has parameters of its own, in addition to those of the cursor
+            let ignoring_bravo = MatchFilter {
+                updated_since: Some(UNIX_EPOCH),
+                ignored_match_ids: vec!["1".repeat(40)],
+                ..Default::default()
+            };
+            for (filter, num_findings) in [(MatchFilter::default(), 5), (ignoring_bravo, 4)] {
+                let all_ids: Vec<String> = ds
+                    .get_finding_metadata(&filter)?
+                    .into_iter()
+                    .map(|md| md.finding_id)
+                    .collect();
+                assert_eq!(all_ids.len(), num_findings);
+
+                let mut paged_ids = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let (page, next) = ds.get_finding_metadata_page(&filter, cursor.as_ref(), 2)?;
+                    paged_ids.extend(page.into_iter().map(|md| md.finding_id));
+                    match next {
+                        Some(next) => cursor = Some(FindingCursor::from_token(&next.to_token())?),
+                        None => break,
+                    }
+                }
+                assert_eq!(paged_ids, all_ids);
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn has_finding() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+
+            assert!(ds.has_finding(&m.finding_id())?);
+            assert!(!ds.has_finding(&m.structural_id)?);
+            Ok(())
+        }
+
+        #[test]
+        fn many_ignored_match_ids() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for (i, secret) in ["alpha", "bravo", "charlie"].iter().enumerate() {
+                let mut m = mk_match(&rule);
+                m.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed(*secret)]);
+                m.structural_id = i.to_string().repeat(40);
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            // More IDs than SQLite allows as parameters of a single statement
+            let mut ignored_match_ids: Vec<String> =
+                (0..40_000).map(|i| format!("unknown {i}")).collect();
+            ignored_match_ids.push("1".repeat(40));
+            let filter = MatchFilter {
+                ignored_match_ids,
+                ..Default::default()
+            };
+            let metadata = ds.get_finding_metadata(&filter)?;
+            assert_eq!(metadata.len(), 2);
+            for md in &metadata {
+                assert_eq!(ds.get_finding_data(md, None, None, &filter)?.len(), 1);
+            }
+
+            // A different set of IDs replaces the previous one
+            let filter = MatchFilter {
+                ignored_match_ids: vec!["0".repeat(40), "2".repeat(40)],
+                ..Default::default()
+            };
+            assert_eq!(ds.get_finding_metadata(&filter)?.len(), 1);
+
+            Ok(())
+        }
+
+        #[test]
+        fn finding_metadata_pages_with_scores() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // Scores that are not exactly representable, some of them tied
+            let scores = [Some(1.0 / 3.0), Some(0.1), None, Some(1.0 / 3.0), Some(0.7)];
+            let tx = ds.begin()?;
+            for (i, score) in scores.into_iter().enumerate() {
+                let mut m = mk_match(&rule);
+                m.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed(format!(
+                    "secret {i}"
+                ))]);
+                m.structural_id = i.to_string().repeat(40);
+                let blob_metadata = BlobMetadata {
+                    id: m.blob_id,
+                    num_bytes: 4,
+                    mime_essence: None,
+                    charset: None,
+                };
+                let provenance = Provenance::from_file("input.txt".into()).into();
+                tx.record(&[(provenance, blob_metadata, vec![(score, m)])])?;
+            }
+            tx.commit()?;
+
+            let filter = MatchFilter::default();
+            let all_ids: Vec<String> = ds
+                .get_finding_metadata(&filter)?
+                .into_iter()
+                .map(|md| md.finding_id)
+                .collect();
+            assert_eq!(all_ids.len(), scores.len());
+
+            for page_size in [1, 2] {
+                let mut paged_ids = Vec::new();
+                let mut cursor = None;
+                loop {
+                    let (page, next) =
+                        ds.get_finding_metadata_page(&filter, cursor.as_ref(), page_size)?;
+                    paged_ids.extend(page.into_iter().map(|md| md.finding_id));
+                    match next {
+                        Some(next) => cursor = Some(FindingCursor::from_token(&next.to_token())?),
+                        None => break,
+                    }
+                }
+                assert_eq!(paged_ids, all_ids);
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn visit_finding_metadata_streams_findings() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            for i in 0..3 {
+                let mut m = mk_match(&rule);
+                m.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed(format!(
+                    "secret {i}"
+                ))]);
+                m.structural_id = format!("{i:040x}");
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            let filter = MatchFilter::default();
+            let expected: Vec<String> = ds
+                .get_finding_metadata(&filter)?
+                .into_iter()
+                .map(|md| md.finding_id)
+                .collect();
+            assert_eq!(expected.len(), 3);
+
+            let mut visited = Vec::new();
+            ds.visit_finding_metadata(&filter, |md| {
+                visited.push(md.finding_id);
+                Ok(())
+            })?;
+            assert_eq!(visited, expected);
+
+            // An error from the function stops the visit
+            let mut num_visited = 0;
+            let res = ds.visit_finding_metadata(&filter, |_md| {
+                num_visited += 1;
+                bail!("stop")
+            });
+            assert!(res.is_err());
+            assert_eq!(num_visited, 1);
+
+            Ok(())
+        }
+
+        #[test]
+        fn findings_since_uses_match_times() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let before = SystemTime::now() - std::time::Duration::from_secs(60);
+            let after = SystemTime::now() + std::time::Duration::from_secs(3600);
+            assert!(ds.findings_since(before)?.is_empty());
+
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+
+            let metadata = ds.findings_since(before)?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(metadata[0].finding_id, m.finding_id());
+            assert!(ds.findings_since(after)?.is_empty());
+
+            Ok(())
+        }
+
+        #[test]
+        fn diff() -> Result<()> {
+            let (_baseline_dir, mut baseline, rule) = mk_datastore_with_rule()?;
+            let (_dir, mut ds, _rule) = mk_datastore_with_rule()?;
+
+            let m1 = mk_match(&rule);
+            baseline.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+
+            // A new match of the finding from the baseline, and a match of a new finding
+            let mut m1_new = mk_match(&rule);
+            m1_new.structural_id = "a".repeat(40);
+            m1_new.blob_id = BlobId::compute_from_bytes(b"other test");
+            assert_eq!(m1.finding_id(), m1_new.finding_id());
+            let mut m2 = mk_match(&rule);
+            m2.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            m2.structural_id = "b".repeat(40);
+            for m in [&m1, &m1_new, &m2] {
+                ds.insert_test_match(m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            let new_ids: Vec<String> = ds
+                .diff(&baseline)?
+                .into_iter()
+                .map(|md| md.finding_id)
+                .collect();
+            assert_eq!(new_ids, vec![m2.finding_id()]);
+            assert_eq!(baseline.diff(&ds)?.len(), 0);
+            assert_eq!(ds.diff(&ds)?.len(), 0);
+            assert_eq!(ds.new_finding_ids(&baseline)?, HashSet::from([m2.finding_id()]));
+            assert!(baseline.new_finding_ids(&ds)?.is_empty());
+
+            Ok(())
+        }
+    }
+
+    /// Tests of the per-rule summary of findings
+    mod summary {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Groups;
+        use noseyparker_rules::RuleSyntax;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn summary_counts_statuses() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // Record a match with the given group in the given blob, assigning it the given status
+            let mut num_recorded = 0;
+            let mut record = |group: &str, blob: &[u8], status: Option<Status>| -> Result<()> {
+                num_recorded += 1;
+                let mut m = mk_match(&rule);
+                m.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed(group)]);
+                m.blob_id = BlobId::compute_from_bytes(blob);
+                m.structural_id = format!("{num_recorded:040x}");
+                ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+                if let Some(status) = status {
+                    ds.conn.execute(
+                        indoc! {r#"
+                            insert into match_status (match_id, status)
+                            select id, ?2 from match where structural_id = ?1
+                        "#},
+                        (&m.structural_id, status),
+                    )?;
+                }
+                Ok(())
+            };
+            record("accepted", b"blob 1", Some(Status::Accept))?;
+            record("rejected", b"blob 1", Some(Status::Reject))?;
+            record("mixed", b"blob 1", Some(Status::Accept))?;
+            record("mixed", b"blob 2", Some(Status::Reject))?;
+            record("partly labeled", b"blob 1", Some(Status::Reject))?;
+            record("partly labeled", b"blob 2", None)?;
+            record("unlabeled", b"blob 1", None)?;
+            record("deferred", b"blob 1", Some(Status::NeedsReview))?;
+            record("deferred", b"blob 2", None)?;
+            record("reviewed", b"blob 1", Some(Status::NeedsReview))?;
+            record("reviewed", b"blob 2", Some(Status::Accept))?;
+
+            let summary = ds.get_summary()?;
+            assert_eq!(summary.0.len(), 1);
+            let entry = &summary.0[0];
+            assert_eq!(entry.distinct_count, 7);
+            assert_eq!(entry.total_count, 11);
+            assert_eq!(entry.accept_count, 1);
+            assert_eq!(entry.reject_count, 2);
+            assert_eq!(entry.needs_review_count, 1);
+            assert_eq!(entry.mixed_count, 2);
+            assert_eq!(entry.unlabeled_count, 1);
+            assert_eq!(
+                entry.accept_count
+                    + entry.reject_count
+                    + entry.needs_review_count
+                    + entry.mixed_count
+                    + entry.unlabeled_count,
+                entry.distinct_count
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn summary_ordered_by_rule_name() -> Result<()> {
+            let names = ["zeta", "Alpha", "mu", "beta"];
+
+            // Record one match for each of the named rules, in the given order, and get the rule
+            // names from the summary
+            let summary_names = |names: &[&str]| -> Result<Vec<String>> {
+                let (_dir, mut ds) = mk_datastore()?;
+                for (i, name) in names.iter().enumerate() {
+                    let rule = Rule::new(RuleSyntax {
+                        id: format!("test.{name}"),
+                        name: name.to_string(),
+                        pattern: format!("({name})"),
+                        ..mk_rule().syntax().clone()
+                    });
+                    let tx = ds.begin()?;
+                    tx.record_rules(std::slice::from_ref(&rule))?;
+                    tx.commit()?;
+                    let mut m = mk_match(&rule);
+                    m.structural_id = format!("{i:040x}");
+                    ds.insert_test_match(&m, Provenance::from_file(name.into()))?;
+                }
+                let summary = ds.get_summary()?;
+                Ok(summary.0.into_iter().map(|e| e.rule_name).collect())
+            };
+
+            let forward = summary_names(&names)?;
+            let mut reversed_names = names;
+            reversed_names.reverse();
+            let reversed = summary_names(&reversed_names)?;
+
+            assert_eq!(forward, ["Alpha", "beta", "mu", "zeta"]);
+            assert_eq!(reversed, forward);
+            Ok(())
+        }
+    }
+
+    /// Tests of filtering matches when reading findings
+    mod match_filter {
+        use super::super::*;
+        use super::fixture::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn comment_matches_are_excluded() -> Result<()> {
+            use crate::lexical_context::LexicalContext;
+
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let mut m = mk_match(&rule);
+            m.context = Some(LexicalContext::Comment);
+            ds.insert_test_match(&m, Provenance::from_file("input.js".into()))?;
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            assert_eq!(metadata.len(), 1);
+            let data = ds.get_finding_data(&metadata[0], None, None, &MatchFilter::default())?;
+            assert_eq!(data[0].match_val.context, Some(LexicalContext::Comment));
+
+            let filter = MatchFilter {
+                exclude_comments: true,
+                ..Default::default()
+            };
+            assert!(ds.get_finding_metadata(&filter)?.is_empty());
+            let data = ds.get_finding_data(&metadata[0], None, None, &filter)?;
+            assert!(data.is_empty());
+
+            Ok(())
+        }
+
+        #[test]
+        fn input_group_filters_matches() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_input_group("backend"))?;
+
+            let filter = |name: &str| MatchFilter {
+                input_group: Some(name.to_string()),
+                ..Default::default()
+            };
+
+            let metadata = ds.get_finding_metadata(&filter("backend"))?;
+            assert_eq!(metadata.len(), 1);
+            let data = ds.get_finding_data(&metadata[0], None, None, &filter("backend"))?;
+            assert_eq!(data.len(), 1);
+
+            assert!(ds.get_finding_metadata(&filter("frontend"))?.is_empty());
+            assert!(ds.get_finding_metadata(&filter("it's"))?.is_empty());
+
+            Ok(())
+        }
+    }
+
+    /// Tests of recording, reading, and deleting individual matches
+    mod matches {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use noseyparker_rules::RuleSyntax;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn invalid_match_is_skipped() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let mut m = mk_match(&rule);
+            m.snippet.matching = "tes".into();
+            assert!(!ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?);
+
+            assert_eq!(ds.get_num_matches()?, 0);
+            assert_eq!(ds.get_num_findings()?, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn matches_by_blob_id() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let other_blob_id = BlobId::compute_from_bytes(b"other content");
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = other_blob_id;
+            m2.snippet.after = "test".into();
+            m2.structural_id = "b".repeat(40);
+            let mut m3 = mk_match(&rule);
+            m3.blob_id = other_blob_id;
+            m3.location.offset_span = OffsetSpan { start: 4, end: 8 };
+            m3.structural_id = "c".repeat(40);
+            for m in [&m1, &m2, &m3] {
+                ds.insert_test_match(m, Provenance::from_file("input.txt".into()))?;
+            }
+
+            let structural_ids = |blob_id: &BlobId| -> Result<Vec<String>> {
+                let matches = ds.get_matches_by_blob_id(blob_id)?;
+                assert!(matches.iter().all(|m| &m.blob_id == blob_id));
+                Ok(matches.into_iter().map(|m| m.structural_id).collect())
+            };
+            assert_eq!(structural_ids(&m1.blob_id)?, [m1.structural_id.as_str()]);
+            assert_eq!(
+                structural_ids(&other_blob_id)?,
+                [m2.structural_id.as_str(), m3.structural_id.as_str()]
+            );
+            assert!(structural_ids(&BlobId::compute_from_bytes(b"unseen"))?.is_empty());
+
+            let m = &ds.get_matches_by_blob_id(&m1.blob_id)?[0];
+            assert_eq!(m.rule_structural_id, rule.structural_id());
+            assert_eq!(m.rule_name, rule.name());
+            assert_eq!(m.snippet.matching, m1.snippet.matching);
+
+            Ok(())
+        }
+
+        #[test]
+        fn snippet_truncation_is_recorded() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let mut m = mk_match(&rule);
+            m.snippet.after = "test".into();
+            m.snippet.after_truncated = true;
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            let snippet = &ds.get_matches_by_blob_id(&m.blob_id)?[0].snippet;
+            assert!(!snippet.before_truncated);
+            assert!(snippet.after_truncated);
+
+            // Recording the match again with a different truncation updates it
+            m.snippet.after_truncated = false;
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            let snippet = &ds.get_matches_by_blob_id(&m.blob_id)?[0].snippet;
+            assert!(!snippet.after_truncated);
+
+            Ok(())
+        }
+
+        #[test]
+        fn corrupt_match_is_an_error() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            ds.insert_test_match(&mk_match(&rule), Provenance::from_file("input.txt".into()))?;
+
+            let filter = MatchFilter::default();
+            let metadata = ds.get_finding_metadata(&filter)?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(
+                ds.get_finding_data(&metadata[0], None, None, &filter)?
+                    .len(),
+                1
+            );
+
+            ds.conn.execute_batch(indoc! {r#"
+                pragma ignore_check_constraints = on;
+                insert into match_status (match_id, status) values (1, 7);
+            "#})?;
+            assert!(ds
+                .get_finding_data(&metadata[0], None, None, &filter)
+                .is_err());
+
+            Ok(())
+        }
+
+        #[test]
+        fn delete_matches_by_rule_keeps_other_rules() -> Result<()> {
+            let (_dir, mut ds, rule1) = mk_datastore_with_rule()?;
+
+            let rule2 = Rule::new(RuleSyntax {
+                id: "test.2".to_string(),
+                name: "other test".to_string(),
+                pattern: "(other)".to_string(),
+                ..rule1.syntax().clone()
+            });
+            let tx = ds.begin()?;
+            tx.record_rules(std::slice::from_ref(&rule2))?;
+            tx.commit()?;
+
+            // rule 1 matches in a blob shared with rule 2, and in a blob of its own
+            let m1 = mk_match(&rule1);
+            let mut m2 = mk_match(&rule1);
+            m2.blob_id = BlobId::compute_from_bytes(b"only rule 1");
+            m2.structural_id = "b".repeat(40);
+            let mut m3 = mk_match(&rule2);
+            m3.structural_id = "c".repeat(40);
+            ds.insert_test_match(&m1, Provenance::from_file("shared.txt".into()))?;
+            ds.insert_test_match(&m2, Provenance::from_file("rule1.txt".into()))?;
+            ds.insert_test_match(&m3, Provenance::from_file("shared.txt".into()))?;
+            ds.conn
+                .execute("insert into match_status (match_id, status) values (1, 1)", ())?;
+            ds.conn.execute(
+                "insert into finding_comment (finding_id, comment) values (1, 'noisy')",
+                (),
+            )?;
+            assert_eq!(ds.get_num_matches()?, 3);
+            assert_eq!(ds.get_num_findings()?, 2);
+
+            assert_eq!(ds.delete_matches_by_rule(rule1.structural_id())?, 2);
+            assert_eq!(ds.get_num_matches()?, 1);
+            assert_eq!(ds.get_num_findings()?, 1);
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            assert_eq!(metadata.len(), 1);
+            assert_eq!(metadata[0].finding_id, m3.finding_id());
+            assert_eq!(ds.list_provenance()?, vec![Provenance::from_file("shared.txt".into())]);
+            let annotations = ds.get_annotations()?;
+            assert!(annotations.match_annotations.is_empty());
+            assert!(annotations.finding_annotations.is_empty());
+
+            // deleting again has no effect
+            assert_eq!(ds.delete_matches_by_rule(rule1.structural_id())?, 0);
+            assert_eq!(ds.get_num_matches()?, 1);
+
+            Ok(())
+        }
+
+        #[test]
+        fn match_redundancies_since() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // Record a pair of overlapping matches in the given blob
+            let insert_overlapping = |ds: &mut Datastore, content: &[u8]| -> Result<()> {
+                let mut m1 = mk_match(&rule);
+                m1.blob_id = BlobId::compute_from_bytes(content);
+                m1.structural_id = format!("{}1", m1.blob_id.hex());
+                m1.snippet.after = "x".into();
+                let mut m2 = m1.clone();
+                m2.location.offset_span = OffsetSpan { start: 1, end: 5 };
+                m2.snippet.after = "".into();
+                m2.structural_id = format!("{}2", m1.blob_id.hex());
+                ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+                ds.insert_test_match(&m2, Provenance::from_file("input.txt".into()))?;
+                Ok(())
+            };
+
+            // Matches from an earlier scan, whose redundancies have not been checked
+            insert_overlapping(&mut ds, b"old content")?;
+            ds.conn.execute("update match set created_at = 0", [])?;
+
+            let since = SystemTime::now() - std::time::Duration::from_secs(1);
+            insert_overlapping(&mut ds, b"new content")?;
+
+            ds.check_match_redundancies_since(since)?;
+            let num_new_redundant = ds.get_num_redundant_matches()?;
+            assert!(num_new_redundant > 0);
+
+            ds.check_match_redundancies()?;
+            assert_eq!(ds.get_num_redundant_matches()?, 2 * num_new_redundant);
+
+            Ok(())
+        }
+
+        #[test]
+        fn match_redundancies_since_keeps_other_rules() -> Result<()> {
+            let (_dir, mut ds, rule1) = mk_datastore_with_rule()?;
+
+            let rule2 = Rule::new(RuleSyntax {
+                id: "test.2".to_string(),
+                name: "other test".to_string(),
+                pattern: "(other)".to_string(),
+                ..rule1.syntax().clone()
+            });
+            let tx = ds.begin()?;
+            tx.record_rules(std::slice::from_ref(&rule2))?;
+            tx.commit()?;
+
+            // Record a pair of overlapping matches of the given rule in the given blob
+            let insert_overlapping =
+                |ds: &mut Datastore, rule: &Rule, content: &[u8]| -> Result<()> {
+                    let mut m1 = mk_match(rule);
+                    m1.blob_id = BlobId::compute_from_bytes(content);
+                    m1.structural_id = format!("{}1", m1.blob_id.hex());
+                    m1.snippet.after = "x".into();
+                    let mut m2 = m1.clone();
+                    m2.location.offset_span = OffsetSpan { start: 1, end: 5 };
+                    m2.snippet.after = "".into();
+                    m2.structural_id = format!("{}2", m1.blob_id.hex());
+                    ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+                    ds.insert_test_match(&m2, Provenance::from_file("input.txt".into()))?;
+                    Ok(())
+                };
+
+            // The redundancy rows of the matches of the given rule
+            let redundancies = |ds: &Datastore, rule: &Rule| -> Result<Vec<(i64, i64)>> {
+                let mut stmt = ds.conn.prepare(indoc! {r#"
+                    select mr.match_id, mr.redundant_to
+                    from
+                        match_redundancy mr
+                        inner join match m on (mr.match_id = m.id)
+                        inner join finding f on (m.finding_id = f.id)
+                        inner join rule r on (f.rule_id = r.id)
+                    where r.structural_id = ?
+                    order by mr.match_id, mr.redundant_to
+                "#})?;
+                let rows =
+                    stmt.query_map((rule.structural_id(),), |row| Ok((row.get(0)?, row.get(1)?)))?;
+                collect(rows)
+            };
+
+            // Matches of the other rule from earlier scans, one of which has been checked
+            insert_overlapping(&mut ds, &rule2, b"checked content")?;
+            ds.check_match_redundancies()?;
+            let rule2_redundancies = redundancies(&ds, &rule2)?;
+            assert!(!rule2_redundancies.is_empty());
+            insert_overlapping(&mut ds, &rule2, b"unchecked content")?;
+            ds.conn.execute("update match set created_at = 0", [])?;
+
+            let since = SystemTime::now() - std::time::Duration::from_secs(1);
+            insert_overlapping(&mut ds, &rule1, b"new content")?;
+            ds.check_match_redundancies_since(since)?;
+
+            assert!(!redundancies(&ds, &rule1)?.is_empty());
+            assert_eq!(redundancies(&ds, &rule2)?, rule2_redundancies);
+
+            Ok(())
+        }
+    }
+
+    /// Tests of recording and reading blob provenance
+    mod provenance {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn missing_provenance_uses_placeholder() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            ds.conn.execute("delete from blob_provenance", ())?;
+
+            let metadata = ds.get_finding_metadata(&MatchFilter::default())?;
+            let data = ds.get_finding_data(&metadata[0], None, None, &MatchFilter::default())?;
+            assert_eq!(data.len(), 1);
+            assert_eq!(
+                data[0].provenance.iter().collect::<Vec<_>>(),
+                vec![&Provenance::from_extended(
+                    serde_json::json!({ "display": "unknown" })
+                )]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn provenance_is_deduplicated() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_file("b.txt".into()))?;
+            ds.insert_test_match(&m, Provenance::from_file("a.txt".into()))?;
+            ds.insert_test_match(&m, Provenance::from_file("b.txt".into()))?;
+
+            assert_eq!(
+                ds.list_provenance()?,
+                vec![
+                    Provenance::from_file("a.txt".into()),
+                    Provenance::from_file("b.txt".into()),
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[test]
+        fn unexpected_provenance_is_an_error() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            ds.insert_test_match(&mk_match(&rule), Provenance::from_file("input.txt".into()))?;
+            let filter = MatchFilter::default();
+            let metadata = ds.get_finding_metadata(&filter)?;
+
+            // Valid JSON objects, as required by the schema, that are not valid provenance
+            let provenances = [
+                r#"{"kind": "unknown"}"#,
+                r#"{"kind": "extended"}"#,
+                r#"{"kind": "git_repo", "repo_path": 42}"#,
+            ];
+            for provenance in provenances {
+                ds.conn
+                    .execute("update blob_provenance set provenance = ?", (provenance,))?;
+                assert!(ds
+                    .get_finding_data(&metadata[0], None, None, &filter)
+                    .is_err());
+                assert!(ds.export_json(std::io::sink()).is_err());
+            }
+
+            Ok(())
+        }
+
+        #[test]
+        fn blob_lineage_resolves_root() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // A secret in a blob that was decoded from a blob that was extracted from a file
+            let lineage = |parent_blob: BlobId, transform: &str| {
+                Provenance::from_extended(serde_json::json!({
+                    "parent_blob": parent_blob.hex(),
+                    "parent_transform": transform,
+                    "parent_start_byte": 4,
+                    "parent_end_byte": 20,
+                }))
+            };
+            let outer = mk_match(&rule);
+            let mut middle = mk_match(&rule);
+            middle.blob_id = BlobId::compute_from_bytes(b"middle");
+            middle.structural_id = "b".repeat(40);
+            let mut inner = mk_match(&rule);
+            inner.blob_id = BlobId::compute_from_bytes(b"inner");
+            inner.structural_id = "c".repeat(40);
+            ds.insert_test_match(&outer, Provenance::from_file("outer.zip".into()))?;
+            ds.insert_test_match(&middle, lineage(outer.blob_id, "unzip"))?;
+            ds.insert_test_match(&inner, lineage(middle.blob_id, "base64"))?;
+
+            let chain = ds.get_blob_lineage(&inner.blob_id)?;
+            assert_eq!(chain.len(), 2);
+            assert_eq!(chain[0].parent_blob, middle.blob_id);
+            assert_eq!(chain[0].transform, "base64");
+            assert_eq!(chain[0].span, OffsetSpan { start: 4, end: 20 });
+            assert_eq!(chain[1].parent_blob, outer.blob_id);
+            assert_eq!(chain[1].transform, "unzip");
+            assert_eq!(
+                ds.get_provenance_by_blob_id(&chain[1].parent_blob)?,
+                vec![Provenance::from_file("outer.zip".into())]
+            );
+
+            assert!(ds.get_blob_lineage(&outer.blob_id)?.is_empty());
+
+            // A cycle ends the chain
+            ds.insert_test_match(&outer, lineage(inner.blob_id, "rot13"))?;
+            let chain = ds.get_blob_lineage(&inner.blob_id)?;
+            assert_eq!(chain.len(), 2);
+
+            Ok(())
+        }
+    }
+
+    /// Tests of match statuses and comments
+    mod annotations {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Groups;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn malformed_annotation_is_skipped() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            ds.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+            ds.conn.execute_batch(indoc! {r#"
+                insert into match_status (match_id, status) values (1, 1);
+                insert into match_status (match_id, status) values (2, 0);
+
+                pragma ignore_check_constraints = on;
+                update blob set blob_id = 'not a blob id' where id = 1;
+            "#})?;
+
+            let annotations = ds.get_annotations()?;
+            assert_eq!(annotations.match_annotations.len(), 1);
+            assert_eq!(annotations.match_annotations[0].match_id, m2.structural_id);
+
+            Ok(())
+        }
+
+        #[test]
+        fn import_annotation_policies() -> Result<()> {
+            let rule = mk_rule();
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+
+            // Import annotations that conflict with existing ones into a new datastore using the
+            // given policy, returning the import stats and the resulting annotations
+            let import = |policy| -> Result<(AnnotationImportStats, Annotations)> {
+                let (_dir, mut ds, _rule) = mk_datastore_with_rule()?;
+                ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+                ds.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+                ds.conn.execute_batch(indoc! {r#"
+                    insert into match_status (match_id, status) values (1, 1);
+                    insert into match_comment (match_id, comment) values (1, 'old');
+                    insert into match_status (match_id, status) values (2, 1);
+                    insert into finding_comment (finding_id, comment) values (1, 'old');
+                "#})?;
+
+                let mut annotations = ds.get_annotations()?;
+                for ma in annotations.match_annotations.iter_mut() {
+                    if ma.match_id == m1.structural_id {
+                        // same status, conflicting comment
+                        ma.comment = Some("new".into());
+                    } else {
+                        // conflicting status, new comment
+                        ma.status = Some(Status::Accept);
+                        ma.comment = Some("new".into());
+                    }
+                }
+                annotations.finding_annotations[0].comment = "new".into();
+
+                let stats = ds.import_annotations(&annotations, policy)?;
+                let annotations = ds.get_annotations()?;
+                Ok((stats, annotations))
+            };
+
+            let values = |annotations: &Annotations, match_id: &str| {
+                let ma = annotations
+                    .match_annotations
+                    .iter()
+                    .find(|ma| ma.match_id == match_id)
+                    .unwrap();
+                (ma.status, ma.comment.clone())
+            };
+            let stats = |imported, existing, conflicting, overwritten| ImportStats {
+                imported,
+                existing,
+                conflicting,
+                overwritten,
+                missing: 0,
+            };
+
+            let (s, a) = import(ImportPolicy::PreferExisting)?;
+            assert_eq!(s.finding_comments, stats(0, 0, 1, 0));
+            assert_eq!(s.match_comments, stats(1, 0, 1, 0));
+            assert_eq!(s.match_statuses, stats(0, 1, 1, 0));
+            assert_eq!(values(&a, &m1.structural_id), (Some(Status::Reject), Some("old".into())));
+            assert_eq!(values(&a, &m2.structural_id), (Some(Status::Reject), Some("new".into())));
+            assert_eq!(a.finding_annotations[0].comment, "old");
+
+            let (s, a) = import(ImportPolicy::Skip)?;
+            assert_eq!(s.finding_comments, stats(0, 0, 1, 0));
+            assert_eq!(s.match_comments, stats(0, 0, 2, 0));
+            assert_eq!(s.match_statuses, stats(0, 1, 1, 0));
+            assert_eq!(values(&a, &m1.structural_id), (Some(Status::Reject), Some("old".into())));
+            assert_eq!(values(&a, &m2.structural_id), (Some(Status::Reject), None));
+            assert_eq!(a.finding_annotations[0].comment, "old");
+
+            let (s, a) = import(ImportPolicy::Overwrite)?;
+            assert_eq!(s.finding_comments, stats(0, 0, 0, 1));
+            assert_eq!(s.match_comments, stats(1, 0, 0, 1));
+            assert_eq!(s.match_statuses, stats(0, 1, 0, 1));
+            assert_eq!(values(&a, &m1.structural_id), (Some(Status::Reject), Some("new".into())));
+            assert_eq!(values(&a, &m2.structural_id), (Some(Status::Accept), Some("new".into())));
+            assert_eq!(a.finding_annotations[0].comment, "new");
+
+            Ok(())
+        }
+
+        #[test]
+        fn export_annotations_roundtrip() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            ds.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+            ds.conn.execute_batch(indoc! {r#"
+                insert into match_status (match_id, status) values (1, 0);
+                insert into match_comment (match_id, comment) values (1, 'first');
+                insert into match_status (match_id, status) values (2, 1);
+                insert into finding_comment (finding_id, comment) values (1, 'finding');
+            "#})?;
+
+            let mut exported = Vec::new();
+            ds.export_annotations(&mut exported)?;
+            let before = serde_json::to_value(ds.get_annotations()?)?;
+
+            ds.conn.execute_batch(indoc! {r#"
+                delete from match_status;
+                delete from match_comment;
+                delete from finding_comment;
+            "#})?;
+            assert!(ds.get_annotations()?.match_annotations.is_empty());
+
+            let annotations: Annotations = serde_json::from_slice(&exported)?;
+            ds.import_annotations(&annotations, ImportPolicy::default())?;
+            let after = serde_json::to_value(ds.get_annotations()?)?;
+            assert_eq!(before, after);
+
+            Ok(())
+        }
+
+        #[test]
+        fn set_finding_status_updates_only_that_finding() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            // Two matches of the same finding in different blobs, and one match of another finding
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+            let mut m3 = mk_match(&rule);
+            m3.blob_id = BlobId::compute_from_bytes(b"more content");
+            m3.structural_id = "c".repeat(40);
+            m3.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            ds.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+            ds.insert_test_match(&m3, Provenance::from_file("more.txt".into()))?;
+            assert_eq!(m1.finding_id(), m2.finding_id());
+            assert_ne!(m1.finding_id(), m3.finding_id());
+
+            let num_updated =
+                ds.set_finding_status(&m1.finding_id(), Status::Reject, Some("false positive"))?;
+            assert_eq!(num_updated, 2);
+
+            let annotations = ds.get_annotations()?.match_annotations;
+            assert_eq!(annotations.len(), 2);
+            for ma in annotations.iter() {
+                assert_eq!(ma.finding_id, m1.finding_id());
+                assert_eq!(ma.status, Some(Status::Reject));
+                assert_eq!(ma.comment.as_deref(), Some("false positive"));
+            }
+
+            // Setting the status again without a comment keeps the existing comments
+            let num_updated = ds.set_finding_status(&m1.finding_id(), Status::Accept, None)?;
+            assert_eq!(num_updated, 2);
+            let annotations = ds.get_annotations()?.match_annotations;
+            assert_eq!(annotations.len(), 2);
+            for ma in annotations.iter() {
+                assert_eq!(ma.status, Some(Status::Accept));
+                assert_eq!(ma.comment.as_deref(), Some("false positive"));
+            }
+
+            assert_eq!(ds.set_finding_status(&"d".repeat(40), Status::Accept, None)?, 0);
+
+            Ok(())
+        }
+
+        #[test]
+        fn import_status_for_unlabeled_match() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+            let m = mk_match(&rule);
+            ds.insert_test_match(&m, Provenance::from_file("input.txt".into()))?;
+            assert!(ds.get_annotations()?.match_annotations.is_empty());
+
+            let annotations = Annotations {
+                match_annotations: vec![MatchAnnotation {
+                    finding_id: m.finding_id(),
+                    rule_name: m.rule_name.clone(),
+                    rule_text_id: m.rule_text_id.clone(),
+                    rule_structural_id: m.rule_structural_id.clone(),
+                    match_id: m.structural_id.clone(),
+                    blob_id: m.blob_id,
+                    start_byte: m.location.offset_span.start,
+                    end_byte: m.location.offset_span.end,
+                    groups: m.groups.clone(),
+                    status: Some(Status::Accept),
+                    comment: None,
+                }],
+                finding_annotations: vec![],
+            };
+            let stats = ds.import_annotations(&annotations, ImportPolicy::default())?;
+            assert_eq!(stats.match_statuses.imported, 1);
+            assert_eq!(stats.match_comments, ImportStats::default());
+
+            let stored = ds.get_annotations()?.match_annotations;
+            assert_eq!(stored.len(), 1);
+            assert_eq!(stored[0].match_id, m.structural_id);
+            assert_eq!(stored[0].status, Some(Status::Accept));
+            assert_eq!(stored[0].comment, None);
+
+            let filter = MatchFilter::default();
+            let metadata = ds.get_finding_metadata(&filter)?;
+            assert!(metadata[0].statuses.only(Status::Accept));
+
+            Ok(())
+        }
+
+        #[test]
+        fn rejected_finding_with_new_match_is_regressed() -> Result<()> {
+            let (_dir, mut ds, rule) = mk_datastore_with_rule()?;
+
+            let m1 = mk_match(&rule);
+            ds.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            ds.conn
+                .execute("insert into match_status (match_id, status) values (1, 1)", ())?;
+            assert!(ds.get_regressed_finding_ids(None)?.is_empty());
+
+            // the same secret, found in another blob
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+            ds.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+            assert_eq!(m1.finding_id(), m2.finding_id());
+
+            let before = SystemTime::now() - std::time::Duration::from_secs(60);
+            let after = SystemTime::now() + std::time::Duration::from_secs(3600);
+            assert_eq!(ds.get_regressed_finding_ids(None)?, [m1.finding_id()]);
+            assert_eq!(ds.get_regressed_finding_ids(Some(before))?, [m1.finding_id()]);
+            assert!(ds.get_regressed_finding_ids(Some(after))?.is_empty());
+
+            Ok(())
+        }
+    }
+
+    /// Tests of copying datastore contents between datastores
+    mod bundles {
+        use super::super::*;
+        use super::fixture::*;
+        use crate::blob_id::BlobId;
+        use crate::match_type::Groups;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn json_bundle_roundtrip() -> Result<()> {
+            let (_src_dir, mut src, rule) = mk_datastore_with_rule()?;
+
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.blob_id = BlobId::compute_from_bytes(b"other content");
+            m2.structural_id = "b".repeat(40);
+            m2.context = Some(crate::lexical_context::LexicalContext::String);
+            src.insert_test_match(&m1, Provenance::from_file("input.txt".into()))?;
+            src.insert_test_match(&m2, Provenance::from_file("other.txt".into()))?;
+            src.conn.execute_batch(indoc! {r#"
+                insert into match_status (match_id, status) values (1, 1);
+                insert into match_comment (match_id, comment) values (2, 'in a test fixture');
+                insert into finding_comment (finding_id, comment) values (1, 'not a secret');
+                insert into match_score (match_id, score) values (2, 0.75);
+            "#})?;
+
+            let mut bundle = Vec::new();
+            src.export_json(&mut bundle)?;
+
+            let (_dst_dir, mut dst) = mk_datastore()?;
+            assert_eq!(dst.import_json(bundle.as_slice())?, 2);
+
+            let filter = MatchFilter::default();
+            let summarize = |ds: &Datastore| -> Result<serde_json::Value> {
+                let mut findings = Vec::new();
+                for md in ds.get_finding_metadata(&filter)? {
+                    let matches: Vec<_> = ds
+                        .get_finding_data(&md, None, None, &filter)?
+                        .into_iter()
+                        .map(|e| {
+                            serde_json::json!({
+                                "match": e.match_val,
+                                "provenance": e.provenance,
+                                "score": e.match_score,
+                                "status": e.match_status,
+                                "comment": e.match_comment,
+                            })
+                        })
+                        .collect();
+                    findings.push(serde_json::json!({ "metadata": md, "matches": matches }));
+                }
+                Ok(serde_json::json!({
+                    "findings": findings,
+                    "annotations": ds.get_annotations()?,
+                }))
+            };
+            let expected = summarize(&src)?;
+            assert_eq!(expected["findings"].as_array().unwrap().len(), 1);
+            assert_eq!(summarize(&dst)?, expected);
+
+            // importing the same bundle again adds nothing
+            assert_eq!(dst.import_json(bundle.as_slice())?, 0);
+            assert_eq!(summarize(&dst)?, expected);
+
+            Ok(())
+        }
+
+        #[test]
+        fn merge() -> Result<()> {
+            let (_src_dir, mut src, rule) = mk_datastore_with_rule()?;
+            let (_dst_dir, mut dst) = mk_datastore()?;
+
+            let m1 = mk_match(&rule);
+            let mut m2 = mk_match(&rule);
+            m2.groups = Groups(smallvec::smallvec![crate::match_type::Group::unnamed("other")]);
+            m2.structural_id = "b".repeat(40);
+            let mut m3 = mk_match(&rule);
+            m3.structural_id = "c".repeat(40);
+            m3.blob_id = BlobId::compute_from_bytes(b"other test");
+            for m in [&m1, &m2] {
+                src.insert_test_match(m, Provenance::from_file("src.txt".into()))?;
+            }
+
+            // The rule is only recorded in the source, and is merged along with the matches
+            assert_eq!(dst.merge(&src, ImportPolicy::default())?, 2);
+            assert_eq!(dst.get_num_matches()?, 2);
+            assert_eq!(dst.get_num_findings()?, 2);
+            assert_eq!(dst.get_rule_pattern(rule.structural_id())?.as_deref(), Some("(test)"));
+
+            // Merging again adds nothing; merging a disjoint match adds it to an existing finding
+            assert_eq!(dst.merge(&src, ImportPolicy::default())?, 0);
+            src.insert_test_match(&m3, Provenance::from_file("src.txt".into()))?;
+            assert_eq!(dst.merge(&src, ImportPolicy::default())?, 1);
+            assert_eq!(dst.get_num_matches()?, 3);
+            assert_eq!(dst.get_num_findings()?, 2);
+            assert_eq!(dst.stats()?.num_provenance, 1);
+
+            let dst2 = Datastore::open(dst.root_dir(), -1024)?;
+            let err = dst.merge(&dst2, ImportPolicy::default()).unwrap_err();
+            assert!(format!("{err:#}").contains("into itself"), "{err:#}");
+
+            Ok(())
+        }
     }
 }
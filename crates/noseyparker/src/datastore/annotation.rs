@@ -112,3 +112,68 @@ impl Annotations {
         Ok(())
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// ImportPolicy
+// -------------------------------------------------------------------------------------------------
+/// How to handle imported annotations that conflict with ones already in a datastore
+///
+/// A conflict occurs when a match or finding already has a status or comment that is different
+/// from the imported one.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ImportPolicy {
+    /// Keep the existing value of each conflicting status or comment; other values of the same
+    /// annotation are still imported
+    #[default]
+    PreferExisting,
+
+    /// Skip annotations that have any conflicting status or comment entirely, importing none of
+    /// their values
+    Skip,
+
+    /// Replace the existing value of each conflicting status or comment with the imported one
+    Overwrite,
+}
+
+// -------------------------------------------------------------------------------------------------
+// ImportStats
+// -------------------------------------------------------------------------------------------------
+/// Counts of the outcomes of importing one kind of annotation value
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportStats {
+    /// Values that were newly added
+    pub imported: usize,
+
+    /// Values that were already present
+    pub existing: usize,
+
+    /// Values that were not imported because of a conflict
+    pub conflicting: usize,
+
+    /// Values that replaced a conflicting existing value
+    pub overwritten: usize,
+
+    /// Values whose match or finding is not in the datastore
+    pub missing: usize,
+}
+
+impl std::fmt::Display for ImportStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} existing; {} missing; {} conflicting; {} overwritten; {} imported",
+            self.existing, self.missing, self.conflicting, self.overwritten, self.imported
+        )
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// AnnotationImportStats
+// -------------------------------------------------------------------------------------------------
+/// The outcome of importing annotations into a datastore
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct AnnotationImportStats {
+    pub finding_comments: ImportStats,
+    pub match_comments: ImportStats,
+    pub match_statuses: ImportStats,
+}
@@ -0,0 +1,44 @@
+use noseyparker_rules::RuleSyntax;
+use serde::{Deserialize, Serialize};
+
+use super::Annotations;
+use crate::blob_metadata::BlobMetadata;
+use crate::match_type::Match;
+use crate::provenance_set::ProvenanceSet;
+
+// -------------------------------------------------------------------------------------------------
+// BundleEntry
+// -------------------------------------------------------------------------------------------------
+/// A single line of a JSON Lines datastore bundle, as written by `Datastore::export_json` and read
+/// by `Datastore::import_json`.
+///
+/// Rule entries are written first, so that the rules of the matches in blob entries are known by
+/// the time they are imported.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub(crate) enum BundleEntry {
+    /// A rule recorded in the datastore
+    Rule { syntax: RuleSyntax },
+
+    /// A blob, along with its provenance and the matches found in it
+    Blob {
+        metadata: BlobMetadata,
+        provenance: ProvenanceSet,
+        matches: Vec<BundleMatch>,
+    },
+
+    /// The match and finding annotations in the datastore
+    Annotations { annotations: Annotations },
+}
+
+// -------------------------------------------------------------------------------------------------
+// BundleMatch
+// -------------------------------------------------------------------------------------------------
+/// A match within a `BundleEntry::Blob` entry
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct BundleMatch {
+    #[serde(rename = "match")]
+    pub match_val: Match,
+
+    pub score: Option<f64>,
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use indoc::indoc;
+use serde::{Deserialize, Serialize};
+
+use super::FindingMetadata;
+
+// -------------------------------------------------------------------------------------------------
+// FindingCursor
+// -------------------------------------------------------------------------------------------------
+/// A position in the ordered sequence of findings in a `Datastore`.
+///
+/// A cursor identifies the last finding of a page returned by
+/// `Datastore::get_finding_metadata_page`, and can be used to fetch the following page without
+/// re-reading the preceding ones. It can be converted to and from an opaque string token, suitable
+/// for handing to clients.
+///
+/// A cursor does not include the finding's capture groups, as those are secrets that should not
+/// appear in tokens. The groups are instead looked up from the finding ID when fetching a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FindingCursor {
+    pub(crate) rule_name: String,
+    pub(crate) rule_structural_id: String,
+    #[serde(with = "f64_bits")]
+    pub(crate) mean_score: Option<f64>,
+    pub(crate) finding_id: String,
+}
+
+impl FindingCursor {
+    /// Create a cursor that refers to the position just after the given finding.
+    pub fn after(metadata: &FindingMetadata) -> Self {
+        Self {
+            rule_name: metadata.rule_name.clone(),
+            rule_structural_id: metadata.rule_structural_id.clone(),
+            mean_score: metadata.mean_score,
+            finding_id: metadata.finding_id.clone(),
+        }
+    }
+
+    /// Encode this cursor as an opaque token.
+    pub fn to_token(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("cursor should serialize"))
+    }
+
+    /// Decode a cursor from a token produced by `to_token`.
+    pub fn from_token(token: &str) -> Result<Self> {
+        let bytes = hex::decode(token).context("Invalid finding cursor token")?;
+        serde_json::from_slice(&bytes).context("Invalid finding cursor token")
+    }
+
+    /// Get a SQL condition on a row of the `finding_denorm` view that is true for findings that
+    /// come after this cursor, in the order used by `Datastore::get_finding_metadata`.
+    ///
+    /// The condition refers to the cursor fields as positional parameters `?1` through `?4`; see
+    /// `sql_params`.
+    pub(crate) fn sql_condition() -> &'static str {
+        indoc! {r#"
+            (rule_name > ?1
+             or (rule_name = ?1 and rule_structural_id > ?2)
+             or (rule_name = ?1 and rule_structural_id = ?2
+                 and ?3 is not null and (mean_score < ?3 or mean_score is null))
+             or (rule_name = ?1 and rule_structural_id = ?2 and mean_score is ?3
+                 and (groups > (select groups from finding where finding_id = ?4)
+                      or (groups = (select groups from finding where finding_id = ?4)
+                          and finding_id > ?4))))"#}
+    }
+
+    /// Get the parameters for the condition from `sql_condition`.
+    pub(crate) fn sql_params(&self) -> [&dyn rusqlite::ToSql; 4] {
+        [
+            &self.rule_name,
+            &self.rule_structural_id,
+            &self.mean_score,
+            &self.finding_id,
+        ]
+    }
+}
+
+/// Serialization of an optional `f64` as its bit pattern, so that it roundtrips exactly.
+mod f64_bits {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Option<f64>, s: S) -> Result<S::Ok, S::Error> {
+        v.map(f64::to_bits).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+        Ok(Option::<u64>::deserialize(d)?.map(f64::from_bits))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn token_roundtrip() {
+        let cursor = FindingCursor {
+            rule_name: "GitHub Personal Access Token".to_string(),
+            rule_structural_id: "a".repeat(40),
+            mean_score: Some(0.1 + 0.2),
+            finding_id: "b".repeat(40),
+        };
+        let token = cursor.to_token();
+        let decoded = FindingCursor::from_token(&token).unwrap();
+        assert_eq!(decoded, cursor);
+        assert_eq!(decoded.mean_score.map(f64::to_bits), cursor.mean_score.map(f64::to_bits));
+    }
+
+    #[test]
+    fn token_roundtrip_no_score() {
+        let cursor = FindingCursor {
+            rule_name: "GitHub Personal Access Token".to_string(),
+            rule_structural_id: "a".repeat(40),
+            mean_score: None,
+            finding_id: "b".repeat(40),
+        };
+        let token = cursor.to_token();
+        assert_eq!(FindingCursor::from_token(&token).unwrap(), cursor);
+    }
+
+    #[test]
+    fn invalid_token() {
+        assert!(FindingCursor::from_token("not a token").is_err());
+        assert!(FindingCursor::from_token(&hex::encode("{}")).is_err());
+    }
+}
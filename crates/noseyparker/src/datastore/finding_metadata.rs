@@ -16,6 +16,9 @@ pub struct FindingMetadata {
     /// The name of the rule that detected each match
     pub rule_name: String,
 
+    /// Freeform references for the rule that detected each match; usually URLs
+    pub rule_references: Vec<String>,
+
     /// The textual identifier of the rule that detected each match
     pub rule_text_id: String,
 
@@ -37,6 +40,6 @@ pub struct FindingMetadata {
     /// A comment assigned to this finding
     pub comment: Option<String>,
 
-    /// The mean score in this group of matches
+    /// The mean score of the matches in this finding that have a score, or `None` if none do
     pub mean_score: Option<f64>,
 }
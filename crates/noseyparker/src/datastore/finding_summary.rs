@@ -25,7 +25,10 @@ pub struct FindingSummaryEntry {
     /// The number of findings with this rule with the `reject` status
     pub reject_count: usize,
 
-    /// The number of findings with this rule with a mixed status, i.e., both `reject` and `accept`
+    /// The number of findings with this rule with the `needs_review` status
+    pub needs_review_count: usize,
+
+    /// The number of findings with this rule with a mixed status, i.e., more than one distinct
     /// status
     pub mixed_count: usize,
 
@@ -0,0 +1,130 @@
+use rusqlite::types::Value;
+use std::time::SystemTime;
+
+use super::unix_timestamp;
+
+// -------------------------------------------------------------------------------------------------
+// MatchFilter
+// -------------------------------------------------------------------------------------------------
+
+/// Criteria for which matches are included when reading findings from a `Datastore`.
+///
+/// Findings that have no matches satisfying these criteria are omitted entirely.
+#[derive(Debug, Clone, Default)]
+pub struct MatchFilter {
+    /// Omit matches that are redundant to some other match
+    pub suppress_redundant: bool,
+
+    /// Omit matches that occur within comments
+    pub exclude_comments: bool,
+
+    /// Include only matches from blobs that were scanned as part of the named input group
+    pub input_group: Option<String>,
+
+    /// Include only matches that were recorded or changed at or after the given time
+    pub updated_since: Option<SystemTime>,
+
+    /// Omit matches with any of the given structural IDs
+    pub ignored_match_ids: Vec<String>,
+}
+
+impl MatchFilter {
+    /// Does this filter need to examine individual matches, or can it be evaluated using
+    /// per-finding match counts alone?
+    pub(crate) fn needs_match_condition(&self) -> bool {
+        self.exclude_comments
+            || self.input_group.is_some()
+            || self.updated_since.is_some()
+            || !self.ignored_match_ids.is_empty()
+    }
+
+    /// Get a SQL condition on a row `m` of the `match_denorm` view that is true for matches that
+    /// satisfy this filter, along with the values of the parameters it refers to.
+    ///
+    /// The condition refers to its parameters positionally, numbered consecutively from
+    /// `first_param`, so that it can be used in a query that has other positional parameters.
+    /// Ignored match IDs are not passed as parameters, but are read from the `ignored_match_id`
+    /// temporary table, which the `Datastore` fills in before running the query.
+    pub(crate) fn sql_condition(&self, first_param: usize) -> (String, Vec<Value>) {
+        let mut conditions = vec!["true".to_string()];
+        let mut params = Vec::new();
+        let mut param = |value: Value| -> String {
+            params.push(value);
+            format!("?{}", first_param + params.len() - 1)
+        };
+
+        if self.suppress_redundant {
+            conditions.push("m.id not in (select match_id from match_redundancy)".into());
+        }
+        if self.exclude_comments {
+            conditions.push("m.context is not 'comment'".into());
+        }
+        if let Some(input_group) = &self.input_group {
+            conditions.push(format!(
+                "m.blob_id in (\
+                    select blob_id from blob_provenance_denorm \
+                    where provenance ->> '$.kind' = 'extended' \
+                    and provenance ->> '$.payload.input_group' = {})",
+                param(Value::Text(input_group.clone()))
+            ));
+        }
+        if let Some(updated_since) = self.updated_since {
+            conditions.push(format!(
+                "m.updated_at >= {}",
+                param(Value::Integer(unix_timestamp(updated_since)))
+            ));
+        }
+        if !self.ignored_match_ids.is_empty() {
+            conditions.push(
+                "m.structural_id not in (select structural_id from temp.ignored_match_id)".into(),
+            );
+        }
+        (conditions.join(" and "), params)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_matches_everything() {
+        let filter = MatchFilter::default();
+        assert!(!filter.needs_match_condition());
+        assert_eq!(filter.sql_condition(1), ("true".to_string(), vec![]));
+    }
+
+    #[test]
+    fn ignored_match_ids() {
+        let filter = MatchFilter {
+            ignored_match_ids: vec!["abc".into(), "it's".into()],
+            ..Default::default()
+        };
+        assert!(filter.needs_match_condition());
+        assert_eq!(
+            filter.sql_condition(1),
+            (
+                "true and m.structural_id not in (select structural_id from temp.ignored_match_id)"
+                    .to_string(),
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn params_are_numbered_from_first_param() {
+        let filter = MatchFilter {
+            input_group: Some("frontend".into()),
+            updated_since: Some(SystemTime::UNIX_EPOCH),
+            ..Default::default()
+        };
+        let (condition, params) = filter.sql_condition(5);
+        assert!(condition.contains("= ?5)"), "{condition}");
+        assert!(condition.ends_with("m.updated_at >= ?6"), "{condition}");
+        assert_eq!(params, [Value::Text("frontend".into()), Value::Integer(0)]);
+    }
+}
@@ -0,0 +1,101 @@
+-- Migrate the datastore schema from version 70 to version 71.
+--
+-- The lexical context of matches is recorded in a new table, and included in
+-- the `match_denorm` view.
+
+CREATE TABLE match_context
+-- This table records the lexical context of matches within source code.
+(
+    -- The integer identifier of the match
+    match_id integer primary key references match(id),
+
+    -- The lexical context of the match
+    context text not null,
+
+    constraint context_valid check (context in ('code', 'comment', 'string'))
+) STRICT;
+
+DROP VIEW match_denorm;
+
+CREATE VIEW match_denorm
+-- A convenience view for matches in denormalized form rather than the
+-- low-level datastore form that involves numerous indirections.
+(
+    id,
+    structural_id,
+    finding_id,
+
+    blob_id,
+
+    start_byte,
+    end_byte,
+
+    start_line,
+    start_column,
+    end_line,
+    end_column,
+
+    rule_name,
+    rule_text_id,
+    rule_structural_id,
+
+    groups,
+
+    before_snippet,
+    matching_snippet,
+    after_snippet,
+
+    status,
+    comment,
+    score,
+    context
+) as
+select
+    m.id,
+    m.structural_id,
+    f.finding_id,
+
+    b.blob_id,
+
+    m.start_byte,
+    m.end_byte,
+
+    bss.start_line,
+    bss.start_column,
+    bss.end_line,
+    bss.end_column,
+
+    r.name,
+    r.text_id,
+    r.structural_id,
+
+    f.groups,
+
+    before_snippet.snippet,
+    matching_snippet.snippet,
+    after_snippet.snippet,
+
+    match_status.status,
+    match_comment.comment,
+    match_score.score,
+    match_context.context
+from
+    match m
+    left outer join finding f on (m.finding_id = f.id)
+    left outer join blob_source_span bss on (
+        m.blob_id = bss.blob_id
+            and
+        m.start_byte = bss.start_byte
+            and
+        m.end_byte = bss.end_byte
+    )
+    left outer join blob b on (m.blob_id = b.id)
+    left outer join rule r on (f.rule_id = r.id)
+    left outer join snippet before_snippet on (m.before_snippet_id = before_snippet.id)
+    left outer join snippet matching_snippet on (m.matching_snippet_id = matching_snippet.id)
+    left outer join snippet after_snippet on (m.after_snippet_id = after_snippet.id)
+    left outer join match_status on (m.id = match_status.match_id)
+    left outer join match_comment on (m.id = match_comment.match_id)
+    left outer join match_score on (m.id = match_score.match_id)
+    left outer join match_context on (m.id = match_context.match_id)
+;
@@ -0,0 +1,240 @@
+-- Migrate the datastore schema from version 73 to version 74.
+--
+-- Match statuses are stored as integer codes rather than text. The type of a
+-- column cannot be changed in place, so the `match_status` table is rebuilt,
+-- along with the views that refer to it.
+
+DROP VIEW match_denorm;
+DROP VIEW finding_denorm;
+DROP VIEW finding_summary;
+
+ALTER TABLE match_status RENAME TO match_status_old;
+
+CREATE TABLE match_status
+-- This table records the triage status of matches.
+(
+    -- The integer identifier of the match
+    match_id integer primary key references match(id),
+
+    -- The assigned status, as an integer code: 0 for `accept`, 1 for `reject`,
+    -- or 2 for `needs_review`
+    status integer not null,
+
+    constraint status_valid check (status in (0, 1, 2))
+) STRICT;
+
+INSERT INTO match_status (match_id, status)
+select
+    match_id,
+    case status
+        when 'accept' then 0
+        when 'reject' then 1
+        when 'needs_review' then 2
+    end
+from match_status_old;
+
+DROP TABLE match_status_old;
+
+CREATE VIEW match_status_denorm
+-- A convenience view for match statuses with their names rather than the
+-- integer codes they are stored as.
+(
+    match_id,
+    status
+) as
+select
+    match_id,
+    case status
+        when 0 then 'accept'
+        when 1 then 'reject'
+        when 2 then 'needs_review'
+        else status
+    end
+from match_status
+;
+
+CREATE VIEW match_denorm
+-- A convenience view for matches in denormalized form rather than the
+-- low-level datastore form that involves numerous indirections.
+(
+    id,
+    structural_id,
+    finding_id,
+
+    blob_id,
+
+    start_byte,
+    end_byte,
+
+    start_line,
+    start_column,
+    end_line,
+    end_column,
+
+    rule_name,
+    rule_text_id,
+    rule_structural_id,
+
+    groups,
+
+    before_snippet,
+    matching_snippet,
+    after_snippet,
+
+    status,
+    comment,
+    score,
+    context,
+
+    created_at,
+    updated_at
+) as
+select
+    m.id,
+    m.structural_id,
+    f.finding_id,
+
+    b.blob_id,
+
+    m.start_byte,
+    m.end_byte,
+
+    bss.start_line,
+    bss.start_column,
+    bss.end_line,
+    bss.end_column,
+
+    r.name,
+    r.text_id,
+    r.structural_id,
+
+    f.groups,
+
+    before_snippet.snippet,
+    matching_snippet.snippet,
+    after_snippet.snippet,
+
+    match_status.status,
+    match_comment.comment,
+    match_score.score,
+    match_context.context,
+
+    m.created_at,
+    m.updated_at
+from
+    match m
+    left outer join finding f on (m.finding_id = f.id)
+    left outer join blob_source_span bss on (
+        m.blob_id = bss.blob_id
+            and
+        m.start_byte = bss.start_byte
+            and
+        m.end_byte = bss.end_byte
+    )
+    left outer join blob b on (m.blob_id = b.id)
+    left outer join rule r on (f.rule_id = r.id)
+    left outer join snippet before_snippet on (m.before_snippet_id = before_snippet.id)
+    left outer join snippet matching_snippet on (m.matching_snippet_id = matching_snippet.id)
+    left outer join snippet after_snippet on (m.after_snippet_id = after_snippet.id)
+    left outer join match_status_denorm match_status on (m.id = match_status.match_id)
+    left outer join match_comment on (m.id = match_comment.match_id)
+    left outer join match_score on (m.id = match_score.match_id)
+    left outer join match_context on (m.id = match_context.match_id)
+;
+
+CREATE VIEW finding_denorm
+-- A convenience view for findings in their fully denormalized form rather
+-- than the low-level datastore form that involves numerous indirection.
+(
+    finding_id,
+    rule_name,
+    rule_text_id,
+    rule_structural_id,
+    rule_syntax,
+    groups,
+    num_matches,
+    num_redundant_matches,
+    mean_score,
+    comment,
+    match_statuses
+)
+as
+select
+    f.finding_id,
+    r.name,
+    r.text_id,
+    r.structural_id,
+    r.syntax,
+    f.groups,
+    count(*),
+    sum(case when m.id in (select match_id from match_redundancy) then 1 else 0 end),
+    avg(ms.score),
+    fc.comment,
+    json_group_array(distinct match_status.status)
+        filter (where match_status.status is not null) match_statuses
+from
+    finding f
+    left outer join match m on (m.finding_id = f.id)
+    left outer join rule r on (f.rule_id = r.id)
+    left outer join match_score ms on (m.id = ms.match_id)
+    left outer join match_status_denorm match_status on (m.id = match_status.match_id)
+    left outer join finding_comment fc on (f.id = fc.finding_id)
+group by f.id
+;
+
+CREATE VIEW finding_summary
+-- A convenience view for a summary of findings in denormalized form.
+(
+    rule_name,
+    rule_structural_id,
+    total_findings,
+    total_matches,
+    accept_findings,
+    reject_findings,
+    needs_review_findings,
+    mixed_findings,
+    unlabeled_findings
+)
+as
+with
+    -- table of relevant per-match information
+    m as (
+        select
+            f.finding_id finding_id,
+            r.name rule_name,
+            r.structural_id rule_structural_id,
+            ms.status match_status
+        from
+            finding f
+            inner join match m on (m.finding_id = f.id)
+            inner join rule r on (f.rule_id = r.id)
+            left outer join match_status_denorm ms on (m.id = ms.match_id)
+    ),
+    -- summarize per-match information by finding
+    f as (
+        select
+            finding_id,
+            rule_name,
+            rule_structural_id,
+            case
+                when count(distinct match_status) > 1 then 'mixed'
+                else max(match_status)
+            end finding_status,
+            count(*) num_matches
+        from m
+        group by finding_id
+    )
+select
+    rule_name,
+    rule_structural_id,
+    count(distinct finding_id) total_findings,
+    sum(num_matches) total_matches,
+    sum(case when finding_status = 'accept' then 1 else 0 end) accept_findings,
+    sum(case when finding_status = 'reject' then 1 else 0 end) reject_findings,
+    sum(case when finding_status = 'needs_review' then 1 else 0 end) needs_review_findings,
+    sum(case when finding_status = 'mixed' then 1 else 0 end) mixed_findings,
+    sum(case when finding_status is null then 1 else 0 end) unlabeled_findings
+from
+    f
+group by rule_name
+;
@@ -0,0 +1,7 @@
+-- Migrate the datastore schema from version 76 to version 77.
+--
+-- Matches are indexed by when they were first recorded, so that the blobs with
+-- matches recorded since a scan started can be found without reading every
+-- match.
+
+CREATE INDEX match_created_at_index on match(created_at);
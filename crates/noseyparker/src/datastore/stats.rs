@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+// -------------------------------------------------------------------------------------------------
+// DatastoreStats
+// -------------------------------------------------------------------------------------------------
+
+/// A snapshot of the overall contents of a `Datastore`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DatastoreStats {
+    /// The schema version of the datastore
+    pub schema_version: u64,
+
+    /// The total number of matches
+    pub num_matches: u64,
+
+    /// The total number of findings
+    pub num_findings: u64,
+
+    /// The number of distinct rules that produced at least one finding
+    pub distinct_rules: u64,
+
+    /// The number of distinct provenance entries, i.e., places where scanned content was found
+    pub num_provenance: u64,
+
+    /// The total number of annotations, i.e., match statuses, match comments, and finding comments
+    pub num_annotations: u64,
+
+    /// The size in bytes of the datastore's sqlite database on disk, including its write-ahead log
+    pub database_size: u64,
+}
@@ -7,22 +7,90 @@ use smallvec::SmallVec;
 // -------------------------------------------------------------------------------------------------
 
 /// A status assigned to a match group
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+///
+/// Statuses are serialized as `snake_case` strings, but are stored in the datastore as integer
+/// codes.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, JsonSchema,
+)]
 #[serde(rename_all = "snake_case")]
-// FIXME(overhaul): use an integer representation for serialization and db
 pub enum Status {
     Accept,
     Reject,
+    NeedsReview,
 }
 
 // -------------------------------------------------------------------------------------------------
 // Statuses
 // -------------------------------------------------------------------------------------------------
-/// A collection of statuses
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-// FIXME(overhaul): use a bitflag representation here?
-pub struct Statuses(pub SmallVec<[Status; 16]>);
+/// A set of distinct statuses, kept in a canonical order
+///
+/// This is serialized as an array of statuses. Duplicates are removed and the statuses are put in
+/// canonical order when deserializing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, JsonSchema)]
+pub struct Statuses(SmallVec<[Status; 3]>);
+
+impl Statuses {
+    /// Add the given status to this set, returning whether it was newly added.
+    pub fn insert(&mut self, status: Status) -> bool {
+        match self.0.binary_search(&status) {
+            Ok(_) => false,
+            Err(idx) => {
+                self.0.insert(idx, status);
+                true
+            }
+        }
+    }
+
+    /// Does this set contain the given status?
+    pub fn contains(&self, status: Status) -> bool {
+        self.0.binary_search(&status).is_ok()
+    }
+
+    /// Does this set contain the given status and no other?
+    pub fn only(&self, status: Status) -> bool {
+        self.0.as_slice() == [status]
+    }
+
+    /// Does this set contain more than one distinct status?
+    pub fn is_mixed(&self) -> bool {
+        self.0.len() > 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Get the statuses in this set, in canonical order.
+    pub fn as_slice(&self) -> &[Status] {
+        self.0.as_slice()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Status> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl FromIterator<Status> for Statuses {
+    fn from_iter<I: IntoIterator<Item = Status>>(iter: I) -> Self {
+        let mut statuses = Self::default();
+        for status in iter {
+            statuses.insert(status);
+        }
+        statuses
+    }
+}
+
+impl<'de> Deserialize<'de> for Statuses {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let statuses: SmallVec<[Status; 3]> = Deserialize::deserialize(deserializer)?;
+        Ok(statuses.into_iter().collect())
+    }
+}
 
 // -------------------------------------------------------------------------------------------------
 // sql
@@ -36,17 +104,25 @@ mod sql {
     impl ToSql for Status {
         fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
             match self {
-                Status::Accept => Ok("accept".into()),
-                Status::Reject => Ok("reject".into()),
+                Status::Accept => Ok(0i64.into()),
+                Status::Reject => Ok(1i64.into()),
+                Status::NeedsReview => Ok(2i64.into()),
             }
         }
     }
 
+    // Statuses are stored as integer codes, but are converted to their names by the datastore's
+    // convenience views, so both forms are accepted here.
     impl FromSql for Status {
         fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
-            match value.as_str()? {
-                "accept" => Ok(Status::Accept),
-                "reject" => Ok(Status::Reject),
+            match value {
+                ValueRef::Integer(0) => Ok(Status::Accept),
+                ValueRef::Integer(1) => Ok(Status::Reject),
+                ValueRef::Integer(2) => Ok(Status::NeedsReview),
+                ValueRef::Integer(i) => Err(FromSqlError::OutOfRange(i)),
+                ValueRef::Text(b"accept") => Ok(Status::Accept),
+                ValueRef::Text(b"reject") => Ok(Status::Reject),
+                ValueRef::Text(b"needs_review") => Ok(Status::NeedsReview),
                 _ => Err(FromSqlError::InvalidType),
             }
         }
@@ -75,3 +151,97 @@ mod sql {
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rusqlite::Connection;
+
+    const ALL_STATUSES: [Status; 3] = [Status::Accept, Status::Reject, Status::NeedsReview];
+
+    const SQL_FORMS: [(Status, i64, &str); 3] = [
+        (Status::Accept, 0, "accept"),
+        (Status::Reject, 1, "reject"),
+        (Status::NeedsReview, 2, "needs_review"),
+    ];
+
+    #[test]
+    fn json_roundtrip() {
+        for status in ALL_STATUSES {
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+        }
+        assert_eq!(serde_json::to_string(&Status::NeedsReview).unwrap(), r#""needs_review""#);
+        assert!(serde_json::from_str::<Status>(r#""maybe""#).is_err());
+    }
+
+    #[test]
+    fn statuses_dedup_and_order() {
+        let statuses: Statuses = [Status::NeedsReview, Status::Accept, Status::NeedsReview]
+            .into_iter()
+            .collect();
+        assert_eq!(statuses.as_slice(), &[Status::Accept, Status::NeedsReview]);
+
+        let mut statuses = Statuses::default();
+        assert!(statuses.insert(Status::Reject));
+        assert!(statuses.insert(Status::Accept));
+        assert!(!statuses.insert(Status::Reject));
+        assert_eq!(statuses.as_slice(), &[Status::Accept, Status::Reject]);
+
+        let statuses: Statuses = serde_json::from_str(r#"["reject", "accept", "reject"]"#).unwrap();
+        assert_eq!(statuses.as_slice(), &[Status::Accept, Status::Reject]);
+        assert_eq!(serde_json::to_string(&statuses).unwrap(), r#"["accept","reject"]"#);
+    }
+
+    #[test]
+    fn statuses_mixed() {
+        let none = Statuses::default();
+        assert!(none.is_empty());
+        assert!(!none.is_mixed());
+        assert!(!none.only(Status::Accept));
+
+        let one: Statuses = [Status::Reject, Status::Reject].into_iter().collect();
+        assert!(!one.is_mixed());
+        assert!(one.only(Status::Reject));
+        assert!(!one.only(Status::Accept));
+        assert!(one.contains(Status::Reject));
+
+        let two: Statuses = [Status::Reject, Status::NeedsReview].into_iter().collect();
+        assert!(two.is_mixed());
+        assert!(!two.only(Status::Reject));
+        assert!(two.contains(Status::NeedsReview));
+        assert!(!two.contains(Status::Accept));
+    }
+
+    #[test]
+    fn sql_roundtrip() {
+        let conn = Connection::open_in_memory().unwrap();
+        for status in ALL_STATUSES {
+            let s: Status = conn.query_row("select ?", (status,), |r| r.get(0)).unwrap();
+            assert_eq!(s, status);
+        }
+        let s: rusqlite::Result<Status> = conn.query_row("select 'maybe'", (), |r| r.get(0));
+        assert!(s.is_err());
+        let s: rusqlite::Result<Status> = conn.query_row("select 7", (), |r| r.get(0));
+        assert!(s.is_err());
+    }
+
+    #[test]
+    fn sql_integer_codes() {
+        let conn = Connection::open_in_memory().unwrap();
+        for (status, code, name) in SQL_FORMS {
+            let c: i64 = conn.query_row("select ?", (status,), |r| r.get(0)).unwrap();
+            assert_eq!(c, code);
+            let s: Status = conn.query_row("select ?", (code,), |r| r.get(0)).unwrap();
+            assert_eq!(s, status);
+
+            // Names are produced by the datastore's convenience views
+            let s: Status = conn.query_row("select ?", (name,), |r| r.get(0)).unwrap();
+            assert_eq!(s, status);
+        }
+    }
+}
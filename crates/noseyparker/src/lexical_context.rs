@@ -0,0 +1,303 @@
+use bstr::ByteSlice;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// -------------------------------------------------------------------------------------------------
+// LexicalContext
+// -------------------------------------------------------------------------------------------------
+/// The lexical context in which a match occurs within source code
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LexicalContext {
+    /// The match occurs outside of any comment or string literal
+    Code,
+
+    /// The match occurs within a comment
+    Comment,
+
+    /// The match occurs within a string literal
+    String,
+}
+
+impl LexicalContext {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LexicalContext::Code => "code",
+            LexicalContext::Comment => "comment",
+            LexicalContext::String => "string",
+        }
+    }
+}
+
+impl std::fmt::Display for LexicalContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// LexicalSyntax
+// -------------------------------------------------------------------------------------------------
+/// The comment and string literal syntax of a family of languages.
+///
+/// This is used to heuristically determine the `LexicalContext` of a match. Only the line
+/// containing the start of the match's secret is examined, so constructs that span lines, such as
+/// multi-line string literals and most block comments, are not recognized.
+#[derive(Debug, Clone, Copy)]
+pub struct LexicalSyntax {
+    /// Markers that begin a comment extending to the end of the line
+    line_comments: &'static [&'static [u8]],
+
+    /// Start and end markers for block comments
+    block_comments: &'static [(&'static [u8], &'static [u8])],
+
+    /// Characters that delimit string literals
+    quotes: &'static [u8],
+
+    /// Whether a line comment marker must be at the start of a line or follow whitespace
+    comment_needs_space: bool,
+
+    /// Whether a line whose first non-whitespace character is `*` is a block comment continuation
+    star_continuation: bool,
+}
+
+const C_LIKE: LexicalSyntax = LexicalSyntax {
+    line_comments: &[b"//"],
+    block_comments: &[(b"/*", b"*/")],
+    quotes: b"\"'`",
+    comment_needs_space: false,
+    star_continuation: true,
+};
+
+/// Like `C_LIKE`, but without `'` as a string delimiter, as it also introduces lifetimes
+const RUST: LexicalSyntax = LexicalSyntax {
+    quotes: b"\"",
+    ..C_LIKE
+};
+
+/// Like `C_LIKE`, but `#` also begins a line comment
+const C_LIKE_HASH: LexicalSyntax = LexicalSyntax {
+    line_comments: &[b"//", b"#"],
+    ..C_LIKE
+};
+
+const HASH: LexicalSyntax = LexicalSyntax {
+    line_comments: &[b"#"],
+    block_comments: &[],
+    quotes: b"\"'",
+    comment_needs_space: true,
+    star_continuation: false,
+};
+
+const SQL_LIKE: LexicalSyntax = LexicalSyntax {
+    line_comments: &[b"--"],
+    block_comments: &[(b"/*", b"*/")],
+    quotes: b"\"'",
+    comment_needs_space: false,
+    star_continuation: false,
+};
+
+const INI: LexicalSyntax = LexicalSyntax {
+    line_comments: &[b";", b"#"],
+    block_comments: &[],
+    quotes: b"\"",
+    comment_needs_space: true,
+    star_continuation: false,
+};
+
+const MARKUP: LexicalSyntax = LexicalSyntax {
+    line_comments: &[],
+    block_comments: &[(b"<!--", b"-->")],
+    quotes: b"\"",
+    comment_needs_space: false,
+    star_continuation: false,
+};
+
+impl LexicalSyntax {
+    /// Guess the syntax of a file from its path.
+    ///
+    /// Returns `None` if the path does not correspond to a known language.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_str()?;
+        match file_name {
+            "Dockerfile" | "Makefile" | "Gemfile" | "Rakefile" | "Vagrantfile" | ".bashrc"
+            | ".zshrc" | ".profile" => return Some(HASH),
+            _ if file_name == ".env" || file_name.starts_with(".env.") => return Some(HASH),
+            _ => {}
+        }
+
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        let syntax = match extension.as_str() {
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "cs" | "java" | "js" | "jsx"
+            | "mjs" | "cjs" | "ts" | "tsx" | "go" | "swift" | "kt" | "kts" | "scala" | "groovy"
+            | "gradle" | "dart" | "m" | "mm" => C_LIKE,
+            "rs" => RUST,
+            "php" | "tf" | "hcl" => C_LIKE_HASH,
+            "py" | "rb" | "sh" | "bash" | "zsh" | "ksh" | "pl" | "pm" | "r" | "yaml" | "yml"
+            | "toml" | "ps1" | "conf" | "tcl" => HASH,
+            "sql" | "lua" | "hs" => SQL_LIKE,
+            "ini" | "cfg" | "properties" => INI,
+            "xml" | "html" | "htm" | "xhtml" | "svg" => MARKUP,
+            _ => return None,
+        };
+        Some(syntax)
+    }
+
+    /// Determine the lexical context at the given byte offset of `input`.
+    pub fn detect(&self, input: &[u8], offset: usize) -> LexicalContext {
+        let line_start = input[..offset].rfind_byte(b'\n').map_or(0, |i| i + 1);
+        let line = &input[line_start..offset];
+
+        if self.star_continuation {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with(b"*") && !trimmed.starts_with(b"*/") {
+                return LexicalContext::Comment;
+            }
+        }
+
+        let mut quote: Option<u8> = None;
+        let mut block_end: Option<&[u8]> = None;
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+
+            if let Some(end) = block_end {
+                if rest.starts_with(end) {
+                    block_end = None;
+                    i += end.len();
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+
+            if let Some(q) = quote {
+                if rest[0] == b'\\' {
+                    i += 2;
+                    continue;
+                }
+                if rest[0] == q {
+                    quote = None;
+                }
+                i += 1;
+                continue;
+            }
+
+            if self.quotes.contains(&rest[0]) {
+                quote = Some(rest[0]);
+                i += 1;
+                continue;
+            }
+
+            if let Some((start, end)) = self
+                .block_comments
+                .iter()
+                .find(|(s, _)| rest.starts_with(s))
+            {
+                block_end = Some(*end);
+                i += start.len();
+                continue;
+            }
+
+            if self.line_comments.iter().any(|c| rest.starts_with(c))
+                && (!self.comment_needs_space || i == 0 || line[i - 1].is_ascii_whitespace())
+            {
+                return LexicalContext::Comment;
+            }
+
+            i += 1;
+        }
+
+        if block_end.is_some() {
+            LexicalContext::Comment
+        } else if quote.is_some() {
+            LexicalContext::String
+        } else {
+            LexicalContext::Code
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// sql
+// -------------------------------------------------------------------------------------------------
+mod sql {
+    use super::*;
+
+    use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+    impl ToSql for LexicalContext {
+        fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+            Ok(self.as_str().into())
+        }
+    }
+
+    impl FromSql for LexicalContext {
+        fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+            match value.as_str()? {
+                "code" => Ok(LexicalContext::Code),
+                "comment" => Ok(LexicalContext::Comment),
+                "string" => Ok(LexicalContext::String),
+                _ => Err(FromSqlError::InvalidType),
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Detect the context of the first occurrence of `needle` in `input`, guessing the syntax
+    /// from `path`.
+    fn detect(path: &str, input: &str, needle: &str) -> Option<LexicalContext> {
+        let offset = input.find(needle).expect("needle should occur in input");
+        LexicalSyntax::from_path(Path::new(path)).map(|s| s.detect(input.as_bytes(), offset))
+    }
+
+    #[test]
+    fn unknown_language() {
+        assert_eq!(detect("notes.txt", "key = SECRET", "SECRET"), None);
+        assert_eq!(detect("noextension", "key = SECRET", "SECRET"), None);
+    }
+
+    #[test]
+    fn c_like() {
+        use LexicalContext::*;
+        assert_eq!(detect("a.js", "const k = SECRET;", "SECRET"), Some(Code));
+        assert_eq!(detect("a.js", "const k = 'SECRET';", "SECRET"), Some(String));
+        assert_eq!(detect("a.js", "const k = \"a\\\"SECRET\";", "SECRET"), Some(String));
+        assert_eq!(detect("a.js", "f(); // SECRET", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.js", "const u = \"http://SECRET\";", "SECRET"), Some(String));
+        assert_eq!(detect("a.js", "/* x */ k = SECRET", "SECRET"), Some(Code));
+        assert_eq!(detect("a.js", "x = 1; /* SECRET */", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.java", "/**\n * key: SECRET\n */", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.rs", "fn f<'a>() { g(\"SECRET\") }", "SECRET"), Some(String));
+    }
+
+    #[test]
+    fn hash() {
+        use LexicalContext::*;
+        assert_eq!(detect("a.py", "# key = SECRET", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.py", "key = 'SECRET'  # real", "SECRET"), Some(String));
+        assert_eq!(detect("a.py", "key = '#' + SECRET", "SECRET"), Some(Code));
+        assert_eq!(detect("a.yaml", "url: http://x#SECRET", "SECRET"), Some(Code));
+        assert_eq!(detect("config/.env", "  # TOKEN=SECRET", "SECRET"), Some(Comment));
+        assert_eq!(detect(".env.local", "TOKEN=SECRET", "SECRET"), Some(Code));
+    }
+
+    #[test]
+    fn other_languages() {
+        use LexicalContext::*;
+        assert_eq!(detect("a.sql", "-- password SECRET", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.ini", "; password = SECRET", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.ini", "password = \"SECRET\"", "SECRET"), Some(String));
+        assert_eq!(detect("a.xml", "<!-- SECRET -->", "SECRET"), Some(Comment));
+        assert_eq!(detect("a.xml", "<k v=\"SECRET\"/>", "SECRET"), Some(String));
+    }
+}
@@ -10,13 +10,15 @@ pub mod git_binary;
 pub mod git_url;
 #[cfg(feature = "github")]
 pub mod github;
+pub mod lexical_context;
 pub mod location;
 pub mod match_type;
 pub mod matcher;
 pub mod matcher_stats;
 pub mod provenance;
 pub mod provenance_set;
-#[cfg(feature = "rule_profiling")]
 pub mod rule_profiling;
 pub mod rules_database;
+pub mod score;
 pub mod snippet;
+pub mod structural_id;
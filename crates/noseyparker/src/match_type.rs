@@ -1,6 +1,5 @@
 use bstr::BString;
 use bstring_serde::BStringBase64;
-use noseyparker_digest::Sha1;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -8,26 +7,162 @@ use std::io::Write;
 use tracing::debug;
 
 use crate::blob_id::BlobId;
-use crate::location::{Location, LocationMapping, OffsetSpan};
+use crate::lexical_context::{LexicalContext, LexicalSyntax};
+use crate::location::{Location, LocationMapping, OffsetSpan, SourcePoint};
 use crate::matcher::BlobMatch;
-use crate::snippet::Snippet;
+use crate::snippet::{after_context, assignment_line_span, before_context, Snippet};
+use crate::structural_id::StructuralIdAlgo;
+
+// -------------------------------------------------------------------------------------------------
+// SpanError
+// -------------------------------------------------------------------------------------------------
+/// An inconsistency between a match's span and the blob or snippet it refers to.
+///
+/// These arise when reconstructing a match from stored data that is malformed, e.g., a match whose
+/// span extends past the end of its blob. Matches are also checked for them before being recorded
+/// in a datastore.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SpanError {
+    #[error("match span {start}..{end} is inverted")]
+    Inverted { start: usize, end: usize },
+
+    #[error("match span {start}..{end} is out of range for blob of {blob_len} bytes")]
+    OutOfRange {
+        start: usize,
+        end: usize,
+        blob_len: usize,
+    },
+
+    #[error("match span {start}..{end} has {span_len} bytes, but the matching snippet has {snippet_len} bytes")]
+    MatchingSnippetLength {
+        start: usize,
+        end: usize,
+        span_len: usize,
+        snippet_len: usize,
+    },
+
+    #[error("{which} snippet has {snippet_len} bytes, but at most {available} bytes are available around match span {start}..{end}")]
+    ContextSnippetLength {
+        which: &'static str,
+        start: usize,
+        end: usize,
+        snippet_len: usize,
+        available: usize,
+    },
+
+    #[error("source span {start}-{end} has line 0, but lines are indexed from 1")]
+    SourceLineZero {
+        start: SourcePoint,
+        end: SourcePoint,
+    },
+
+    #[error("source span {start}-{end} is inverted")]
+    SourceInverted {
+        start: SourcePoint,
+        end: SourcePoint,
+    },
+}
 
 // -------------------------------------------------------------------------------------------------
 // Group
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct Group(#[serde(with = "BStringBase64")] pub BString);
+/// A capture group of a match
+///
+/// A group without a name is serialized as its base64-encoded content, as in earlier versions of
+/// Nosey Parker. A named group is serialized as an object with `value` and `name` fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "GroupRepr")]
+pub struct Group {
+    /// The content of the group
+    pub value: BString,
+
+    /// The name of the group in the rule's pattern, if it has one
+    pub name: Option<String>,
+}
 
 impl Group {
-    pub fn new(m: regex::bytes::Match<'_>) -> Self {
-        Self(BString::from(m.as_bytes()))
+    pub fn new(m: regex::bytes::Match<'_>, name: Option<&str>) -> Self {
+        Self {
+            value: BString::from(m.as_bytes()),
+            name: name.map(str::to_owned),
+        }
+    }
+
+    /// Create a group without a name.
+    pub fn unnamed<T: Into<BString>>(value: T) -> Self {
+        Self {
+            value: value.into(),
+            name: None,
+        }
+    }
+}
+
+/// The base64-encoded content of a group
+struct GroupValue<'a>(&'a BString);
+
+impl Serialize for GroupValue<'_> {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        BStringBase64::serialize(self.0, s)
+    }
+}
+
+impl Serialize for Group {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        match &self.name {
+            None => GroupValue(&self.value).serialize(s),
+            Some(name) => {
+                let mut group = s.serialize_struct("Group", 2)?;
+                group.serialize_field("value", &GroupValue(&self.value))?;
+                group.serialize_field("name", name)?;
+                group.end()
+            }
+        }
+    }
+}
+
+impl JsonSchema for Group {
+    fn schema_name() -> String {
+        "Group".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        GroupRepr::json_schema(gen)
+    }
+}
+
+/// A capture group of a match
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum GroupRepr {
+    /// The base64-encoded content of a group without a name
+    Unnamed(#[serde(with = "BStringBase64")] BString),
+
+    /// A named group
+    Named {
+        #[serde(with = "BStringBase64")]
+        value: BString,
+        name: String,
+    },
+}
+
+impl From<GroupRepr> for Group {
+    fn from(repr: GroupRepr) -> Self {
+        match repr {
+            GroupRepr::Unnamed(value) => Self { value, name: None },
+            GroupRepr::Named { value, name } => Self {
+                value,
+                name: Some(name),
+            },
+        }
     }
 }
 
 // -------------------------------------------------------------------------------------------------
 // Groups
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub struct Groups(pub SmallVec<[Group; 1]>);
 
 // -------------------------------------------------------------------------------------------------
@@ -66,7 +201,7 @@ mod sql {
 // -------------------------------------------------------------------------------------------------
 // Match
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Match {
     /// The blob this match comes from
     pub blob_id: BlobId,
@@ -91,6 +226,9 @@ pub struct Match {
 
     /// The name of the rule that produced this match
     pub rule_name: String,
+
+    /// The lexical context of the match within source code, if it could be determined
+    pub context: Option<LexicalContext>,
 }
 
 impl Match {
@@ -99,39 +237,29 @@ impl Match {
         loc_mapping: &'a LocationMapping,
         blob_match: &'a BlobMatch<'a>,
         snippet_context_bytes: usize,
+        syntax: Option<&LexicalSyntax>,
+        structural_id_algo: StructuralIdAlgo,
     ) -> Self {
         let offset_span = blob_match.matching_input_offset_span;
 
-        // FIXME: have the snippets start from a line break in the input when feasible, and include an ellipsis otherwise to indicate truncation
-        let before_snippet = {
-            let start = offset_span.start.saturating_sub(snippet_context_bytes);
-            let end = offset_span.start;
-            &blob_match.blob.bytes[start..end]
-        };
-
-        let after_snippet = {
-            let start = offset_span.end;
-            let end = offset_span
-                .end
-                .saturating_add(snippet_context_bytes)
-                .min(blob_match.blob.len());
-            &blob_match.blob.bytes[start..end]
-        };
+        let input = &blob_match.blob.bytes;
+        let (before_snippet, before_truncated) =
+            before_context(input, offset_span.start, snippet_context_bytes);
+        let (after_snippet, after_truncated) =
+            after_context(input, offset_span.end, snippet_context_bytes);
         let source_span = loc_mapping.get_source_span(&offset_span);
 
-        debug_assert!(
-            blob_match.captures.len() > 1,
-            "blob {}: no capture groups for rule {}",
-            blob_match.blob.id,
-            blob_match.rule.id()
-        );
+        // A rule with no explicit capture groups uses its entire match as its only group.
+        // Otherwise, the implicit group for the entire match is skipped.
+        let num_implicit_groups = if blob_match.captures.len() > 1 { 1 } else { 0 };
 
         let groups = blob_match
             .captures
             .iter()
+            .zip(blob_match.regex.capture_names())
             .enumerate()
-            .skip(1)
-            .filter_map(move |(group_index, group)| {
+            .skip(num_implicit_groups)
+            .filter_map(move |(group_index, (group, name))| {
                 let group = match group {
                     Some(group) => group,
                     None => {
@@ -144,13 +272,32 @@ impl Match {
                         return None;
                     }
                 };
-                Some(Group::new(group))
+                Some(Group::new(group, name))
             })
             .collect();
 
+        // The lexical context is that of the secret, i.e., the first group. For rules that match a
+        // key before the secret, the start of the entire match is often outside the string literal
+        // that contains the secret.
+        let context = syntax.map(|s| {
+            let match_start = blob_match.captures.get(0).map_or(0, |m| m.start());
+            let secret_start = blob_match
+                .captures
+                .iter()
+                .skip(num_implicit_groups)
+                .flatten()
+                .next()
+                .map_or(offset_span.start, |g| offset_span.start + g.start() - match_start);
+            s.detect(input, secret_start)
+        });
+
         let rule_structural_id = blob_match.rule.structural_id().to_owned();
-        let structural_id =
-            Self::compute_structural_id(&rule_structural_id, &blob_match.blob.id, offset_span);
+        let structural_id = Self::compute_structural_id(
+            structural_id_algo,
+            &rule_structural_id,
+            &blob_match.blob.id,
+            offset_span,
+        );
 
         Match {
             blob_id: blob_match.blob.id,
@@ -159,8 +306,10 @@ impl Match {
             rule_text_id: blob_match.rule.id().to_owned(),
             snippet: Snippet {
                 matching: BString::from(blob_match.matching_input),
-                before: BString::from(before_snippet),
-                after: BString::from(after_snippet),
+                before: before_snippet,
+                after: after_snippet,
+                before_truncated,
+                after_truncated,
             },
             location: Location {
                 offset_span,
@@ -168,16 +317,40 @@ impl Match {
             },
             groups: Groups(groups),
             structural_id,
+            context,
         }
     }
 
-    /// Returns a content-based unique identifier of the match.
+    /// Extend the snippet of this match to cover the entire line containing it, if that line looks
+    /// like a `KEY = value` assignment.
+    ///
+    /// This makes the key of a secret in a config file visible even when the snippet would
+    /// otherwise not include it. The snippet is only ever extended, never shortened. `input` must
+    /// be the content of the blob this match comes from.
+    pub fn extend_snippet_to_assignment(&mut self, input: &[u8]) {
+        let OffsetSpan { start, end } = self.location.offset_span;
+        let line = match assignment_line_span(input, &self.location.offset_span) {
+            Some(line) => line,
+            None => return,
+        };
+        if line.start < start.saturating_sub(self.snippet.before.len()) {
+            self.snippet.before = BString::from(&input[line.start..start]);
+            self.snippet.before_truncated = false;
+        }
+        if line.end > end.saturating_add(self.snippet.after.len()) {
+            self.snippet.after = BString::from(&input[end..line.end.min(input.len())]);
+            self.snippet.after_truncated = false;
+        }
+    }
+
+    /// Returns a content-based unique identifier of the match, computed using the given algorithm.
     fn compute_structural_id(
+        algo: StructuralIdAlgo,
         rule_structural_id: &str,
         blob_id: &BlobId,
         span: OffsetSpan,
     ) -> String {
-        let mut h = Sha1::new();
+        let mut h = algo.hasher();
         write!(
             &mut h,
             "{}\0{}\0{}\0{}",
@@ -188,14 +361,325 @@ impl Match {
         )
         .expect("should be able to compute structural id");
 
-        h.hexdigest()
+        h.finish()
+    }
+
+    /// Check that this match's byte span is consistent with a blob of `blob_len` bytes and with the
+    /// lengths of its snippets.
+    pub fn check_span(&self, blob_len: usize) -> Result<(), SpanError> {
+        let OffsetSpan { start, end } = self.location.offset_span;
+        if start > end {
+            return Err(SpanError::Inverted { start, end });
+        }
+        if end > blob_len {
+            return Err(SpanError::OutOfRange {
+                start,
+                end,
+                blob_len,
+            });
+        }
+
+        let span_len = end - start;
+        let snippet_len = self.snippet.matching.len();
+        if snippet_len != span_len {
+            return Err(SpanError::MatchingSnippetLength {
+                start,
+                end,
+                span_len,
+                snippet_len,
+            });
+        }
+
+        let check_context = |which, snippet_len: usize, available: usize| {
+            if snippet_len > available {
+                Err(SpanError::ContextSnippetLength {
+                    which,
+                    start,
+                    end,
+                    snippet_len,
+                    available,
+                })
+            } else {
+                Ok(())
+            }
+        };
+        check_context("before", self.snippet.before.len(), start)?;
+        check_context("after", self.snippet.after.len(), blob_len - end)?;
+
+        Ok(())
+    }
+
+    /// Check that this match's source span is internally consistent: its lines must be indexed
+    /// from 1, and unless the match is empty, its end must not come before its start.
+    pub fn check_source_span(&self) -> Result<(), SpanError> {
+        let start = self.location.source_span.start;
+        let end = self.location.source_span.end;
+        if start.line == 0 || end.line == 0 {
+            return Err(SpanError::SourceLineZero { start, end });
+        }
+        let OffsetSpan {
+            start: start_byte,
+            end: end_byte,
+        } = self.location.offset_span;
+        if start_byte < end_byte && (end.line, end.column) < (start.line, start.column) {
+            return Err(SpanError::SourceInverted { start, end });
+        }
+        Ok(())
     }
 
+    /// Returns a content-based identifier of the finding this match belongs to.
+    ///
+    /// This is computed using the same algorithm as this match's structural id.
     pub fn finding_id(&self) -> String {
-        let mut h = Sha1::new();
+        use serde::Serializer;
+
+        let mut h = self.structural_id_algo().hasher();
         write!(&mut h, "{}\0", self.rule_structural_id).expect("should be able to write to memory");
-        serde_json::to_writer(&mut h, &self.groups)
+        // Group names are not included, so that findings keep their ids if groups are renamed
+        let values = self.groups.0.iter().map(|g| GroupValue(&g.value));
+        let mut ser = serde_json::Serializer::new(&mut h);
+        ser.collect_seq(values)
             .expect("should be able to serialize groups as JSON");
-        h.hexdigest()
+        h.finish()
+    }
+
+    /// Returns the algorithm that produced this match's structural id.
+    ///
+    /// Identifiers with an unrecognized prefix are assumed to be SHA-1.
+    pub fn structural_id_algo(&self) -> StructuralIdAlgo {
+        StructuralIdAlgo::of_id(&self.structural_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::location::SourceSpan;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    fn mk_match(start: usize, end: usize, before: &str, matching: &str, after: &str) -> Match {
+        let point = SourcePoint { line: 1, column: 0 };
+        Match {
+            blob_id: BlobId::compute_from_bytes(b""),
+            location: Location {
+                offset_span: OffsetSpan { start, end },
+                source_span: SourceSpan {
+                    start: point,
+                    end: point,
+                },
+            },
+            groups: Groups::default(),
+            snippet: Snippet {
+                before: before.into(),
+                matching: matching.into(),
+                after: after.into(),
+                before_truncated: false,
+                after_truncated: false,
+            },
+            structural_id: String::new(),
+            rule_structural_id: String::new(),
+            rule_text_id: String::new(),
+            rule_name: String::new(),
+            context: None,
+        }
+    }
+
+    #[test]
+    fn check_span_whole_blob() {
+        assert_eq!(mk_match(0, 6, "", "secret", "").check_span(6), Ok(()));
+    }
+
+    #[test]
+    fn check_span_at_blob_end() {
+        assert_eq!(mk_match(4, 10, "abcd", "secret", "").check_span(10), Ok(()));
+    }
+
+    #[test]
+    fn check_span_beyond_blob_end() {
+        assert_eq!(
+            mk_match(4, 10, "abcd", "secret", "").check_span(8),
+            Err(SpanError::OutOfRange {
+                start: 4,
+                end: 10,
+                blob_len: 8
+            })
+        );
+    }
+
+    #[test]
+    fn check_span_inverted() {
+        assert_eq!(
+            mk_match(10, 4, "", "", "").check_span(10),
+            Err(SpanError::Inverted { start: 10, end: 4 })
+        );
+    }
+
+    #[test]
+    fn check_span_truncated_matching_snippet() {
+        assert_eq!(
+            mk_match(0, 6, "", "sec", "").check_span(6),
+            Err(SpanError::MatchingSnippetLength {
+                start: 0,
+                end: 6,
+                span_len: 6,
+                snippet_len: 3
+            })
+        );
+    }
+
+    #[test]
+    fn check_span_before_snippet_too_long() {
+        assert_eq!(
+            mk_match(2, 8, "abcd", "secret", "").check_span(8),
+            Err(SpanError::ContextSnippetLength {
+                which: "before",
+                start: 2,
+                end: 8,
+                snippet_len: 4,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn check_span_after_snippet_too_long() {
+        assert_eq!(
+            mk_match(0, 6, "", "secret", "xyz").check_span(8),
+            Err(SpanError::ContextSnippetLength {
+                which: "after",
+                start: 0,
+                end: 6,
+                snippet_len: 3,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn extend_snippet_to_assignment() {
+        let input = "# AWS\naws_secret_access_key = secret # prod\nother = 1\n";
+        let start = input.find("secret #").unwrap();
+        let mut m = mk_match(start, start + 6, "= ", "secret", " #");
+        m.extend_snippet_to_assignment(input.as_bytes());
+        assert_eq!(m.snippet.before, "aws_secret_access_key = ");
+        assert_eq!(m.snippet.after, " # prod");
+
+        // snippets cut off in the middle of the line are replaced by the rest of the line
+        let mut m = mk_match(start, start + 6, "= ", "secret", " #");
+        m.snippet.before_truncated = true;
+        m.snippet.after_truncated = true;
+        m.extend_snippet_to_assignment(input.as_bytes());
+        assert_eq!(m.snippet.before, "aws_secret_access_key = ");
+        assert_eq!(m.snippet.after, " # prod");
+        assert!(!m.snippet.before_truncated);
+        assert!(!m.snippet.after_truncated);
+
+        // a snippet that already covers more than the line is kept as is
+        let mut m = mk_match(start, start + 6, "# AWS\naws_secret_access_key = ", "secret", "");
+        m.extend_snippet_to_assignment(input.as_bytes());
+        assert_eq!(m.snippet.before, "# AWS\naws_secret_access_key = ");
+        assert_eq!(m.snippet.after, " # prod");
+    }
+
+    #[test]
+    fn check_source_span_single_point() {
+        assert_eq!(mk_match(0, 1, "", "s", "").check_source_span(), Ok(()));
+    }
+
+    #[test]
+    fn check_source_span_line_zero() {
+        let mut m = mk_match(0, 6, "", "secret", "");
+        m.location.source_span.start.line = 0;
+        assert_eq!(
+            m.check_source_span(),
+            Err(SpanError::SourceLineZero {
+                start: SourcePoint { line: 0, column: 0 },
+                end: SourcePoint { line: 1, column: 0 },
+            })
+        );
+    }
+
+    #[test]
+    fn check_source_span_inverted() {
+        let mut m = mk_match(0, 6, "", "secret", "");
+        m.location.source_span.start = SourcePoint { line: 2, column: 3 };
+        assert_eq!(
+            m.check_source_span(),
+            Err(SpanError::SourceInverted {
+                start: SourcePoint { line: 2, column: 3 },
+                end: SourcePoint { line: 1, column: 0 },
+            })
+        );
+    }
+
+    #[test_case(StructuralIdAlgo::Sha1, "6182f7b8da94287547d1c9e3bd5a4871182eb575"; "sha1")]
+    #[test_case(
+        StructuralIdAlgo::Sha256,
+        "sha256:95df18b3f589c6b8d7d32986484969d68c4a0d859626406621ec5c372f39c5ab";
+        "sha256"
+    )]
+    #[test_case(
+        StructuralIdAlgo::Blake3,
+        "blake3:d47d762d2844658ba311949863513558f15021b893d3e204304d34f97113729c";
+        "blake3"
+    )]
+    fn structural_id_is_stable(algo: StructuralIdAlgo, expected: &str) {
+        let id = Match::compute_structural_id(
+            algo,
+            &"a".repeat(40),
+            &BlobId::compute_from_bytes(b""),
+            OffsetSpan { start: 10, end: 20 },
+        );
+        assert_eq!(id, expected);
+    }
+
+    #[test]
+    fn ids_differ_across_algos() {
+        let mut m = mk_match(10, 20, "", "0123456789", "");
+        let ids: Vec<(String, String)> = StructuralIdAlgo::ALL
+            .into_iter()
+            .map(|algo| {
+                m.structural_id = Match::compute_structural_id(
+                    algo,
+                    &m.rule_structural_id,
+                    &m.blob_id,
+                    m.location.offset_span,
+                );
+                assert_eq!(m.structural_id_algo(), algo);
+                (m.structural_id.clone(), m.finding_id())
+            })
+            .collect();
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                assert_ne!(a.0, b.0);
+                assert_ne!(a.1, b.1);
+            }
+        }
+    }
+
+    #[test]
+    fn group_serde() {
+        let unnamed = Group::unnamed("secret");
+        let json = serde_json::to_value(&unnamed).unwrap();
+        assert_eq!(json, serde_json::json!("c2VjcmV0"));
+        assert_eq!(serde_json::from_value::<Group>(json).unwrap(), unnamed);
+
+        let named = Group {
+            value: "secret".into(),
+            name: Some("token".into()),
+        };
+        let json = serde_json::to_value(&named).unwrap();
+        assert_eq!(json, serde_json::json!({"value": "c2VjcmV0", "name": "token"}));
+        assert_eq!(serde_json::from_value::<Group>(json).unwrap(), named);
+    }
+
+    #[test]
+    fn finding_id_ignores_group_names() {
+        let mut m = mk_match(0, 6, "", "secret", "");
+        m.groups = Groups(smallvec::smallvec![Group::unnamed("secret")]);
+        let unnamed_id = m.finding_id();
+        m.groups.0[0].name = Some("token".into());
+        assert_eq!(m.finding_id(), unnamed_id);
     }
 }
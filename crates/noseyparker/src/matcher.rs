@@ -1,4 +1,5 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use std::ops::Range;
 use std::sync::Mutex;
 use tracing::error;
 
@@ -47,10 +48,68 @@ pub struct BlobMatch<'a> {
 
     /// The capture groups from the match
     pub captures: regex::bytes::Captures<'a>,
+
+    /// The regex that produced the capture groups, which gives their names
+    pub regex: &'a regex::bytes::Regex,
 }
 
 const DEFAULT_SCRATCH_CAPACITY: usize = 16384;
 
+/// The default number of bytes by which consecutive chunks overlap when chunked scanning is
+/// enabled.
+///
+/// Matches no longer than this are found even if they span a chunk boundary.
+pub const DEFAULT_CHUNK_OVERLAP: usize = 64 * 1024;
+
+// -------------------------------------------------------------------------------------------------
+// Chunking
+// -------------------------------------------------------------------------------------------------
+/// Parameters for scanning large inputs in overlapping chunks.
+#[derive(Debug, Copy, Clone)]
+struct Chunking {
+    /// The approximate size in bytes of each chunk
+    size: usize,
+
+    /// The minimum number of bytes by which consecutive chunks overlap
+    overlap: usize,
+}
+
+impl Chunking {
+    /// Split `input` into overlapping spans of approximately `self.size` bytes.
+    ///
+    /// Chunks are extended to end at a line boundary when one is found within `self.overlap`
+    /// bytes, and start at a line boundary when one is found within `self.overlap` bytes before the
+    /// required overlap.
+    fn spans(&self, input: &[u8]) -> Vec<Range<usize>> {
+        debug_assert!(self.overlap < self.size);
+
+        let len = input.len();
+        let mut spans = Vec::with_capacity(len / (self.size - self.overlap) + 1);
+        let mut start = 0;
+        loop {
+            let mut end = (start + self.size).min(len);
+            if end < len {
+                let limit = (end + self.overlap).min(len);
+                if let Some(i) = input[end..limit].iter().position(|&b| b == b'\n') {
+                    end += i + 1;
+                }
+            }
+            spans.push(start..end);
+            if end >= len {
+                break;
+            }
+
+            let mut next = end - self.overlap;
+            let limit = next.saturating_sub(self.overlap).max(start + 1);
+            if let Some(i) = input[limit..next].iter().rposition(|&b| b == b'\n') {
+                next = limit + i + 1;
+            }
+            start = next;
+        }
+        spans
+    }
+}
+
 struct UserData {
     /// A scratch vector for raw matches from Vectorscan, used to minimize heap allocation
     raw_matches_scratch: Vec<RawMatch>,
@@ -91,6 +150,9 @@ pub struct Matcher<'a> {
 
     /// Data passed to the Vectorscan callback
     user_data: UserData,
+
+    /// If set, inputs larger than the chunk size are scanned in overlapping chunks
+    chunking: Option<Chunking>,
 }
 
 /// This `Drop` implementation updates the `global_stats` with the local stats
@@ -130,24 +192,81 @@ impl<'a> Matcher<'a> {
             raw_matches_scratch: Vec::with_capacity(DEFAULT_SCRATCH_CAPACITY),
         };
         let vs_scanner = vectorscan_rs::BlockScanner::new(&rules_db.vsdb)?;
-        Ok(Matcher {
+        let mut matcher = Matcher {
             vs_scanner,
             rules_db,
             local_stats: MatcherStats::default(),
             global_stats,
             seen_blobs,
             user_data,
-        })
+            chunking: None,
+        };
+        if cfg!(feature = "rule_profiling") {
+            matcher.enable_rule_profiling();
+        }
+        Ok(matcher)
+    }
+
+    /// Enable collection of per-rule profiling statistics in this `Matcher`.
+    ///
+    /// The statistics are made available through `MatcherStats::rule_stats`.
+    /// Clones of this `Matcher` will also collect them.
+    pub fn enable_rule_profiling(&mut self) {
+        self.local_stats
+            .rule_stats
+            .get_or_insert_with(Default::default);
+    }
+
+    /// Enable chunked scanning in this `Matcher`.
+    ///
+    /// Inputs larger than `chunk_size` bytes will be scanned in chunks of approximately that size,
+    /// split at line boundaries where possible, with consecutive chunks overlapping by at least
+    /// `overlap` bytes. This bounds the cost of scanning pathologically large inputs. Matches
+    /// longer than `overlap` bytes that span a chunk boundary may be missed.
+    /// Clones of this `Matcher` will also use chunked scanning.
+    pub fn enable_chunking(&mut self, chunk_size: usize, overlap: usize) -> Result<()> {
+        if chunk_size <= overlap {
+            bail!(
+                "Chunk size ({chunk_size} bytes) must be larger than chunk overlap ({overlap} bytes)"
+            );
+        }
+        self.chunking = Some(Chunking {
+            size: chunk_size,
+            overlap,
+        });
+        Ok(())
     }
 
     fn scan_bytes_raw(&mut self, input: &[u8]) -> Result<()> {
         self.user_data.raw_matches_scratch.clear();
+        match self.chunking {
+            Some(chunking) if input.len() > chunking.size => {
+                for span in chunking.spans(input) {
+                    let offset = span.start as u64;
+                    self.scan_chunk_raw(&input[span], offset)?;
+                }
+
+                // Matches within the overlap between chunks are reported by both chunks.
+                // Keep just the one with the earliest start offset, which gives the second-stage
+                // regex match the most context.
+                let raw_matches_scratch = &mut self.user_data.raw_matches_scratch;
+                raw_matches_scratch.sort_by_key(|m| (m.rule_id, m.end_idx, m.start_idx));
+                raw_matches_scratch.dedup_by_key(|m| (m.rule_id, m.end_idx));
+            }
+            _ => self.scan_chunk_raw(input, 0)?,
+        }
+        Ok(())
+    }
+
+    /// Scan the given input, which starts at `offset` within the overall input being scanned,
+    /// appending raw matches to the scratch buffer.
+    fn scan_chunk_raw(&mut self, input: &[u8], offset: u64) -> Result<()> {
         self.vs_scanner
             .scan(input, |rule_id: u32, from: u64, to: u64, _flags: u32| {
                 self.user_data.raw_matches_scratch.push(RawMatch {
                     rule_id,
-                    start_idx: from,
-                    end_idx: to,
+                    start_idx: from + offset,
+                    end_idx: to + offset,
                 });
                 vectorscan_rs::Scan::Continue
             })?;
@@ -214,11 +333,10 @@ impl<'a> Matcher<'a> {
         // -----------------------------------------------------------------------------------------
         // Update rule raw match stats
         // -----------------------------------------------------------------------------------------
-        #[cfg(feature = "rule_profiling")]
-        for m in raw_matches_scratch.iter() {
-            self.local_stats
-                .rule_stats
-                .increment_match_count(m.rule_id as usize, 1);
+        if let Some(rule_stats) = &mut self.local_stats.rule_stats {
+            for m in raw_matches_scratch.iter() {
+                rule_stats.increment_match_count(m.rule_id as usize, 1);
+            }
         }
 
         // -----------------------------------------------------------------------------------------
@@ -253,8 +371,7 @@ impl<'a> Matcher<'a> {
             .filter_map(|/*raw_match @*/ &RawMatch{ rule_id, start_idx, end_idx }| {
                 let rule_id: usize = rule_id.try_into().unwrap();
 
-                #[cfg(feature = "rule_profiling")]
-                let _rule_profiler = self.local_stats.rule_stats.time_stage2(rule_id);
+                let _rule_profiler = self.local_stats.rule_stats.as_mut().map(|s| s.time_stage2(rule_id));
 
                 let start_idx: usize = start_idx.try_into().unwrap();
                 let end_idx: usize = end_idx.try_into().unwrap();
@@ -310,6 +427,7 @@ impl<'a> Matcher<'a> {
                     matching_input: matching_input.as_bytes(),
                     matching_input_offset_span,
                     captures,
+                    regex: re,
                 };
                 Some(m)
             }).collect();
@@ -347,6 +465,7 @@ mod test {
             references: vec![],
             categories: vec![],
             description: None,
+            match_filenames: false,
         })];
         let rules_db = RulesDatabase::from_rules(rules)?;
         let input = "some test data for vectorscan";
@@ -363,4 +482,157 @@ mod test {
         );
         Ok(())
     }
+
+    fn test_rules_db() -> Result<RulesDatabase> {
+        test_rules_db_with_pattern("test")
+    }
+
+    fn test_rules_db_with_pattern(pattern: &str) -> Result<RulesDatabase> {
+        let rules = vec![Rule::new(RuleSyntax {
+            id: "test.1".to_string(),
+            name: "test".to_string(),
+            pattern: pattern.to_string(),
+            examples: vec![],
+            negative_examples: vec![],
+            references: vec![],
+            categories: vec![],
+            description: None,
+            match_filenames: false,
+        })];
+        RulesDatabase::from_rules(rules)
+    }
+
+    /// A rule without capture groups uses its entire match as its only group
+    #[test]
+    pub fn test_no_capture_groups() -> Result<()> {
+        use crate::location::LocationMapping;
+        use crate::match_type::Match;
+        use crate::provenance::Provenance;
+
+        let rules_db = test_rules_db()?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        let blob = Blob::from_bytes(b"some test data".to_vec());
+        let provenance = ProvenanceSet::from(Provenance::from_file("input.txt".into()));
+        let blob_matches = match matcher.scan_blob(&blob, &provenance)? {
+            ScanResult::New(ms) => ms,
+            _ => panic!("blob should not have been seen before"),
+        };
+        assert_eq!(blob_matches.len(), 1);
+
+        let loc_mapping = LocationMapping::new(&blob.bytes);
+        let m = Match::convert(&loc_mapping, &blob_matches[0], 0, None, Default::default());
+        assert_eq!(m.groups.0.len(), 1);
+        assert_eq!(m.groups.0[0].value, "test");
+        assert_eq!(m.groups.0[0].name, None);
+        Ok(())
+    }
+
+    /// The names of named capture groups are recorded in the match groups
+    #[test]
+    pub fn test_named_capture_groups() -> Result<()> {
+        use crate::location::LocationMapping;
+        use crate::match_type::Match;
+        use crate::provenance::Provenance;
+
+        let rules_db = test_rules_db_with_pattern(r"key=(?P<key_id>[a-z]+):([0-9]+)")?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        let blob = Blob::from_bytes(b"some key=abc:123 data".to_vec());
+        let provenance = ProvenanceSet::from(Provenance::from_file("input.txt".into()));
+        let blob_matches = match matcher.scan_blob(&blob, &provenance)? {
+            ScanResult::New(ms) => ms,
+            _ => panic!("blob should not have been seen before"),
+        };
+        assert_eq!(blob_matches.len(), 1);
+
+        let loc_mapping = LocationMapping::new(&blob.bytes);
+        let m = Match::convert(&loc_mapping, &blob_matches[0], 0, None, Default::default());
+        assert_eq!(m.groups.0.len(), 2);
+        assert_eq!(m.groups.0[0].value, "abc");
+        assert_eq!(m.groups.0[0].name.as_deref(), Some("key_id"));
+        assert_eq!(m.groups.0[1].value, "123");
+        assert_eq!(m.groups.0[1].name, None);
+        Ok(())
+    }
+
+    /// The lexical context of a match is that of its secret, not of the start of the match
+    #[test]
+    pub fn test_keyed_match_context() -> Result<()> {
+        use crate::lexical_context::{LexicalContext, LexicalSyntax};
+        use crate::location::LocationMapping;
+        use crate::match_type::Match;
+        use crate::provenance::Provenance;
+
+        let rules_db = test_rules_db_with_pattern(r#"aws_secret\s*=\s*"([A-Za-z0-9]+)""#)?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        let blob = Blob::from_bytes(b"x = 1\naws_secret = \"abc123\"\n".to_vec());
+        let provenance = ProvenanceSet::from(Provenance::from_file("config.py".into()));
+        let blob_matches = match matcher.scan_blob(&blob, &provenance)? {
+            ScanResult::New(ms) => ms,
+            _ => panic!("blob should not have been seen before"),
+        };
+        assert_eq!(blob_matches.len(), 1);
+
+        let syntax = LexicalSyntax::from_path(std::path::Path::new("config.py"));
+        let loc_mapping = LocationMapping::new(&blob.bytes);
+        let m =
+            Match::convert(&loc_mapping, &blob_matches[0], 0, syntax.as_ref(), Default::default());
+        assert_eq!(m.groups.0[0].value, "abc123");
+        assert_eq!(m.context, Some(LexicalContext::String));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_chunk_spans() {
+        let chunking = Chunking {
+            size: 10,
+            overlap: 4,
+        };
+        assert_eq!(chunking.spans(b"0123456789"), vec![0..10]);
+        assert_eq!(chunking.spans(b"0123456789abcdef"), vec![0..10, 6..16]);
+        assert_eq!(chunking.spans(b"0123456\n89abcdefghij"), vec![0..10, 6..16, 12..20]);
+        assert_eq!(
+            chunking.spans(b"0123456789ab\ndefghijklmnop"),
+            vec![0..13, 9..19, 13..23, 19..26]
+        );
+    }
+
+    #[test]
+    pub fn test_chunked_across_boundary() -> Result<()> {
+        let rules_db = test_rules_db()?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        matcher.enable_chunking(12, 6)?;
+        // `test` spans the end of the first chunk
+        matcher.scan_bytes_raw(b"xxxxxxxxxxtestxxxx")?;
+        let ms = matcher.user_data.raw_matches_scratch.as_slice();
+        assert_eq!(ms.len(), 1);
+        assert_eq!(ms[0].end_idx, 14);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_chunked_within_overlap() -> Result<()> {
+        let rules_db = test_rules_db()?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        matcher.enable_chunking(12, 6)?;
+        // `test` is in both chunks, but is reported just once
+        matcher.scan_bytes_raw(b"xxxxxxxtestxxxxxxx")?;
+        let ms = matcher.user_data.raw_matches_scratch.as_slice();
+        assert_eq!(ms.len(), 1);
+        assert_eq!(ms[0].end_idx, 11);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_chunking_too_small() -> Result<()> {
+        let rules_db = test_rules_db()?;
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        assert!(matcher.enable_chunking(6, 6).is_err());
+        Ok(())
+    }
 }
@@ -8,8 +8,8 @@ pub struct MatcherStats {
     pub bytes_seen: u64,
     pub bytes_scanned: u64,
 
-    #[cfg(feature = "rule_profiling")]
-    pub rule_stats: crate::rule_profiling::RuleProfile,
+    /// Per-rule profiling statistics, present only if rule profiling is enabled
+    pub rule_stats: Option<crate::rule_profiling::RuleProfile>,
 }
 
 impl MatcherStats {
@@ -19,7 +19,10 @@ impl MatcherStats {
         self.bytes_seen += other.bytes_seen;
         self.bytes_scanned += other.bytes_scanned;
 
-        #[cfg(feature = "rule_profiling")]
-        self.rule_stats.update(&other.rule_stats);
+        if let Some(other_rule_stats) = &other.rule_stats {
+            self.rule_stats
+                .get_or_insert_with(Default::default)
+                .update(other_rule_stats);
+        }
     }
 }
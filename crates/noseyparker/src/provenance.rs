@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::blob_id::BlobId;
+use crate::location::OffsetSpan;
+
 // -------------------------------------------------------------------------------------------------
 // Provenance
 // -------------------------------------------------------------------------------------------------
@@ -33,17 +36,21 @@ impl Provenance {
         Provenance::GitRepo(GitRepoProvenance {
             repo_path,
             first_commit: None,
+            latest_commit: None,
         })
     }
 
     /// Create a `Provenance` entry for a blob found within a Git repo's history, with commit
     /// provenance.
     ///
+    /// `latest_commit` is the most recent branch tip commit in which the blob is present, if any.
+    ///
     /// See also `from_git_repo`.
     pub fn from_git_repo_with_first_commit(
         repo_path: Arc<PathBuf>,
         commit_metadata: Arc<CommitMetadata>,
         blob_path: BString,
+        latest_commit: Option<CommitProvenance>,
     ) -> Self {
         let first_commit = Some(CommitProvenance {
             commit_metadata,
@@ -52,6 +59,7 @@ impl Provenance {
         Provenance::GitRepo(GitRepoProvenance {
             repo_path,
             first_commit,
+            latest_commit,
         })
     }
 
@@ -60,6 +68,26 @@ impl Provenance {
         Provenance::Extended(ExtendedProvenance { payload })
     }
 
+    /// Create an extended `Provenance` entry recording that a blob was scanned as part of the
+    /// named input group.
+    pub fn from_input_group(name: &str) -> Self {
+        Self::from_extended(serde_json::json!({ "input_group": name }))
+    }
+
+    /// Create an extended `Provenance` entry for content read from stdin.
+    pub fn from_stdin() -> Self {
+        Self::from_extended(serde_json::json!({ "display": "<stdin>", "stdin": true }))
+    }
+
+    /// Create an extended `Provenance` entry for a match found in the given path itself rather
+    /// than in the content at that path.
+    pub fn from_filename_match(path: &Path) -> Self {
+        Self::from_extended(serde_json::json!({
+            "path": path.to_string_lossy(),
+            "filename_match": true,
+        }))
+    }
+
     /// Get the path for the blob from this `Provenance` entry, if one is specified.
     pub fn blob_path(&self) -> Option<&Path> {
         use bstr::ByteSlice;
@@ -112,6 +140,13 @@ pub struct FileProvenance {
 pub struct GitRepoProvenance {
     pub repo_path: Arc<PathBuf>,
     pub first_commit: Option<CommitProvenance>,
+
+    /// The most recent branch tip commit in which the blob is present.
+    ///
+    /// This is only recorded when requested at scan time. When it was requested but is absent, the
+    /// blob is no longer present at the tip of any branch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_commit: Option<CommitProvenance>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -136,15 +171,16 @@ pub struct CommitProvenance {
 /// Nosey Parker:
 ///
 /// - A `path` field containing a string
-//
-// - XXX A `url` string field that is a syntactically-valid URL
-// - XXX A `time` string field
-// - XXX A `display` string field
-//
-// - XXX A `parent_blob` string field with a hex-encoded blob ID that the associated blob was derived from
-// - XXX A `parent_transform` string field identifying the transform method used to derive the associated blob
-// - XXX A `parent_start_byte` integer field
-// - XXX A `parent_end_byte` integer field
+///
+/// - A `url` field containing a string
+///
+/// - A `time` field containing a string
+///
+/// - A `display` field containing a string, shown instead of the entire value
+///
+/// - A `parent_blob` field containing a hex-encoded blob ID, along with a `parent_transform`
+///   string field and `parent_start_byte` and `parent_end_byte` integer fields, indicating that
+///   the blob was derived from the given span of another blob
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ExtendedProvenance {
     pub payload: serde_json::Value,
@@ -152,15 +188,71 @@ pub struct ExtendedProvenance {
 
 impl std::fmt::Display for ExtendedProvenance {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        std::fmt::Display::fmt(&self.payload, f)
+        match self.display() {
+            Some(display) => f.write_str(display),
+            None => std::fmt::Display::fmt(&self.payload, f),
+        }
     }
 }
 
 impl ExtendedProvenance {
     pub fn path(&self) -> Option<&Path> {
-        let p = self.payload.get("path")?.as_str()?;
+        let p = self.get_str("path")?;
         Some(Path::new(p))
     }
+
+    pub fn url(&self) -> Option<&str> {
+        self.get_str("url")
+    }
+
+    pub fn time(&self) -> Option<&str> {
+        self.get_str("time")
+    }
+
+    pub fn display(&self) -> Option<&str> {
+        self.get_str("display")
+    }
+
+    /// Get the blob that the associated blob was derived from, if all of the `parent_*` fields
+    /// are present and valid.
+    pub fn parent(&self) -> Option<BlobLineage> {
+        let parent_blob = BlobId::from_hex(self.get_str("parent_blob")?).ok()?;
+        let transform = self.get_str("parent_transform")?.to_owned();
+        let span = OffsetSpan {
+            start: self.get_usize("parent_start_byte")?,
+            end: self.get_usize("parent_end_byte")?,
+        };
+        Some(BlobLineage {
+            parent_blob,
+            transform,
+            span,
+        })
+    }
+
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.payload.get(name)?.as_str()
+    }
+
+    fn get_usize(&self, name: &str) -> Option<usize> {
+        self.payload.get(name)?.as_u64()?.try_into().ok()
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// BlobLineage
+// -------------------------------------------------------------------------------------------------
+/// Indicates that a blob was derived from a span of another blob, such as by decoding an embedded
+/// base64 string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobLineage {
+    /// The blob that the derived blob came from
+    pub parent_blob: BlobId,
+
+    /// The name of the transformation that was applied to derive the blob
+    pub transform: String,
+
+    /// The span within the parent blob that the derived blob came from
+    pub span: OffsetSpan,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -208,9 +300,138 @@ mod test {
     #[test_case(json!{null}; "null")]
     #[test_case(json!{["this is a string in an array"]}; "array")]
     #[test_case(json!{{"value": "this is a string in an array"}}; "object")]
+    #[test_case(json!{{"kind": "file", "nested": {"list": [1, {"deeper": null}]}}}; "nested")]
     fn serialize_extended_provenance(val: serde_json::Value) -> Result<()> {
-        let p = Provenance::from_extended(val);
-        let _s = serde_json::to_string(&p)?;
+        let p = Provenance::from_extended(val.clone());
+        let s = serde_json::to_string(&p)?;
+        let p2: Provenance = serde_json::from_str(&s)?;
+        assert_eq!(p2, p);
+        match p2 {
+            Provenance::Extended(e) => assert_eq!(e.payload, val),
+            _ => panic!("extended provenance should deserialize as extended"),
+        }
+
+        Ok(())
+    }
+
+    fn mk_commit_provenance(blob_path: &str) -> CommitProvenance {
+        let commit_metadata: CommitMetadata = serde_json::from_value(json!({
+            "commit_id": "1d4ce3a7c1b3a0c6b46d1ccbd6d3c89a1c2a4f1e",
+            "committer_name": "Committer",
+            "committer_email": "committer@example.com",
+            "committer_timestamp": "1378126038 +0100",
+            "author_name": "Author",
+            "author_email": "author@example.com",
+            "author_timestamp": "1378126000 +0100",
+            "message": "Add secrets\n",
+        }))
+        .unwrap();
+        CommitProvenance {
+            commit_metadata: Arc::new(commit_metadata),
+            blob_path: blob_path.into(),
+        }
+    }
+
+    fn git_repo_provenance_with_commits() -> Provenance {
+        let first_commit = mk_commit_provenance("config/secrets.yaml");
+        Provenance::from_git_repo_with_first_commit(
+            Arc::new(PathBuf::from("repo")),
+            first_commit.commit_metadata,
+            first_commit.blob_path,
+            Some(mk_commit_provenance("config/secrets.yml")),
+        )
+    }
+
+    #[test_case(Provenance::from_file("input.txt".into()); "file")]
+    #[test_case(Provenance::from_git_repo(Arc::new(PathBuf::from("repo"))); "git repo")]
+    #[test_case(git_repo_provenance_with_commits(); "git repo with commits")]
+    #[test_case(Provenance::from_file(r"C:\Users\me\secrets.txt".into()); "file with drive letter")]
+    #[test_case(Provenance::from_file("dir:with:colons/file:1.txt".into()); "file with colons")]
+    #[test_case(Provenance::from_input_group("group"); "extended")]
+    #[test_case(Provenance::from_stdin(); "stdin")]
+    fn provenance_roundtrip(p: Provenance) -> Result<()> {
+        let s = serde_json::to_string(&p)?;
+        assert_eq!(serde_json::from_str::<Provenance>(&s)?, p);
+
+        let conn = rusqlite::Connection::open_in_memory()?;
+        let p2: Provenance = conn.query_row("select ?", (&p,), |r| r.get(0))?;
+        assert_eq!(p2, p);
+
+        Ok(())
+    }
+
+    #[test_case(r"C:\Users\me\secrets.txt"; "drive letter")]
+    #[test_case("dir:with:colons/file:1.txt"; "colons")]
+    fn display_file_provenance_with_colons(path: &str) {
+        let p = Provenance::from_file(path.into());
+        assert_eq!(p.to_string(), format!("file {path}"));
+        assert_eq!(p.blob_path(), Some(Path::new(path)));
+    }
+
+    #[test]
+    fn extended_provenance_fields() {
+        let blob_id = BlobId::compute_from_bytes(b"parent");
+        let p = ExtendedProvenance {
+            payload: json!({
+                "path": "secrets.txt",
+                "url": "https://example.com/secrets.txt",
+                "time": "2024-01-01T00:00:00Z",
+                "display": "secrets.txt from example.com",
+                "parent_blob": blob_id.hex(),
+                "parent_transform": "base64",
+                "parent_start_byte": 10,
+                "parent_end_byte": 42,
+            }),
+        };
+        assert_eq!(p.path(), Some(Path::new("secrets.txt")));
+        assert_eq!(p.url(), Some("https://example.com/secrets.txt"));
+        assert_eq!(p.time(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(p.display(), Some("secrets.txt from example.com"));
+        assert_eq!(
+            p.parent(),
+            Some(BlobLineage {
+                parent_blob: blob_id,
+                transform: "base64".into(),
+                span: OffsetSpan { start: 10, end: 42 },
+            })
+        );
+        assert_eq!(p.to_string(), "secrets.txt from example.com");
+    }
+
+    #[test_case(json!{{}}; "empty object")]
+    #[test_case(json!{"not an object"}; "string")]
+    #[test_case(json!{{"path": 1, "time": 2, "display": [], "parent_blob": "xyz"}}; "wrong types")]
+    fn extended_provenance_fields_absent(payload: serde_json::Value) {
+        let p = ExtendedProvenance {
+            payload: payload.clone(),
+        };
+        assert_eq!(p.path(), None);
+        assert_eq!(p.url(), None);
+        assert_eq!(p.time(), None);
+        assert_eq!(p.display(), None);
+        assert_eq!(p.parent(), None);
+        assert_eq!(p.to_string(), payload.to_string());
+    }
+
+    #[test]
+    fn extended_provenance_partial_parent() {
+        let blob_id = BlobId::compute_from_bytes(b"parent");
+        let p = ExtendedProvenance {
+            payload: json!({
+                "parent_blob": blob_id.hex(),
+                "parent_transform": "base64",
+                "parent_start_byte": 10,
+            }),
+        };
+        assert_eq!(p.parent(), None);
+    }
+
+    #[test]
+    fn git_repo_provenance_without_latest_commit() -> Result<()> {
+        let p = Provenance::from_git_repo(Arc::new(PathBuf::from("repo")));
+        let s = serde_json::to_string(&p)?;
+        assert_eq!(s, r#"{"kind":"git_repo","repo_path":"repo","first_commit":null}"#);
+        assert_eq!(serde_json::from_str::<Provenance>(&s)?, p);
 
         Ok(())
     }
@@ -1,15 +1,15 @@
 use schemars::JsonSchema;
 use serde::ser::SerializeSeq;
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::provenance::Provenance;
+use crate::provenance::{FileProvenance, GitRepoProvenance, Provenance};
 
 // XXX this could be reworked to use https://docs.rs/nonempty instead of handrolling that
 
 /// A non-empty set of `Provenance` entries.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ProvenanceSet {
     provenance: Provenance,
     more_provenance: Vec<Provenance>,
@@ -26,6 +26,15 @@ impl serde::Serialize for ProvenanceSet {
     }
 }
 
+/// Deserialize `ProvenanceSet` from a flat, non-empty sequence
+impl<'de> serde::Deserialize<'de> for ProvenanceSet {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let entries = <Vec<Provenance>>::deserialize(d)?;
+        Self::try_from_iter(entries)
+            .ok_or_else(|| serde::de::Error::custom("expected a non-empty sequence"))
+    }
+}
+
 impl JsonSchema for ProvenanceSet {
     fn schema_name() -> String {
         "ProvenanceSet".into()
@@ -50,8 +59,11 @@ impl ProvenanceSet {
         }
     }
 
-    /// Create a new `ProvenanceSet` from the given items, filtering out redundant less-specific
-    /// `Provenance` records.
+    /// Create a new `ProvenanceSet` from the given items, filtering out duplicate and redundant
+    /// less-specific `Provenance` records.
+    ///
+    /// File entries are considered duplicates if they have the same path, and extended entries if
+    /// they have equal payloads. The first of a set of duplicates is kept.
     pub fn new(provenance: Provenance, more_provenance: Vec<Provenance>) -> Self {
         let mut git_repos_with_detailed: HashSet<Arc<PathBuf>> = HashSet::new();
 
@@ -63,14 +75,24 @@ impl ProvenanceSet {
             }
         }
 
+        let mut seen_files: HashSet<PathBuf> = HashSet::new();
+        let mut seen_payloads: Vec<serde_json::Value> = Vec::new();
+
         let mut it = std::iter::once(provenance)
             .chain(more_provenance)
             .filter(|p| match p {
                 Provenance::GitRepo(e) => {
                     e.first_commit.is_some() || !git_repos_with_detailed.contains(&e.repo_path)
                 }
-                Provenance::File(_) => true,
-                Provenance::Extended(_) => true,
+                Provenance::File(e) => seen_files.insert(e.path.clone()),
+                Provenance::Extended(e) => {
+                    if seen_payloads.contains(&e.payload) {
+                        false
+                    } else {
+                        seen_payloads.push(e.payload.clone());
+                        true
+                    }
+                }
             });
 
         Self {
@@ -101,10 +123,38 @@ impl ProvenanceSet {
         1 + self.more_provenance.len()
     }
 
+    /// Add another `Provenance` entry to this set.
+    #[inline]
+    pub fn push(&mut self, provenance: Provenance) {
+        self.more_provenance.push(provenance);
+    }
+
     #[inline]
     pub fn iter(&self) -> impl Iterator<Item = &Provenance> {
         std::iter::once(&self.provenance).chain(&self.more_provenance)
     }
+
+    /// Iterate over the file entries in this set.
+    pub fn files(&self) -> impl Iterator<Item = &FileProvenance> {
+        self.iter().filter_map(|p| match p {
+            Provenance::File(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Iterate over the Git repo entries in this set.
+    pub fn git_repos(&self) -> impl Iterator<Item = &GitRepoProvenance> {
+        self.iter().filter_map(|p| match p {
+            Provenance::GitRepo(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Does any entry in this set refer to the given path, either as a file or as a Git repo?
+    pub fn contains_path(&self, path: &Path) -> bool {
+        self.files().any(|e| e.path == path)
+            || self.git_repos().any(|e| e.repo_path.as_path() == path)
+    }
 }
 
 impl IntoIterator for ProvenanceSet {
@@ -123,3 +173,89 @@ impl From<Provenance> for ProvenanceSet {
         Self::single(p)
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn new_dedups_files() {
+        let p = || Provenance::from_file("a/b.txt".into());
+        let ps = ProvenanceSet::new(p(), vec![p(), p()]);
+        assert_eq!(ps.len(), 1);
+        assert_eq!(ps.first(), &p());
+    }
+
+    #[test]
+    fn new_dedups_extended() {
+        let p1 = Provenance::from_extended(json!({"url": "https://example.com", "n": [1, 2]}));
+        let p2 = Provenance::from_extended(json!({"n": [1, 2], "url": "https://example.com"}));
+        let p3 = Provenance::from_extended(json!({"n": [2, 1], "url": "https://example.com"}));
+        let ps = ProvenanceSet::new(p1.clone(), vec![p2, p3.clone()]);
+        assert_eq!(ps.into_iter().collect::<Vec<_>>(), vec![p1, p3]);
+    }
+
+    #[test]
+    fn new_keeps_distinct() {
+        let entries = vec![
+            Provenance::from_file("a.txt".into()),
+            Provenance::from_file("b.txt".into()),
+            Provenance::from_extended(json!("a.txt")),
+            Provenance::from_git_repo(Arc::new("repo".into())),
+            Provenance::from_file("a.txt".into()),
+            Provenance::from_extended(json!("a.txt")),
+        ];
+        let ps = ProvenanceSet::try_from_iter(entries.clone()).unwrap();
+        assert_eq!(ps.iter().collect::<Vec<_>>(), entries[..4].iter().collect::<Vec<_>>());
+    }
+
+    fn mixed_set() -> ProvenanceSet {
+        ProvenanceSet::try_from_iter([
+            Provenance::from_extended(json!({"path": "c.txt"})),
+            Provenance::from_file("a.txt".into()),
+            Provenance::from_git_repo(Arc::new("repo1".into())),
+            Provenance::from_file("b.txt".into()),
+            Provenance::from_git_repo(Arc::new("repo2".into())),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn files_and_git_repos() {
+        let ps = mixed_set();
+        let files: Vec<&Path> = ps.files().map(|e| e.path.as_path()).collect();
+        assert_eq!(files, [Path::new("a.txt"), Path::new("b.txt")]);
+        let repos: Vec<&Path> = ps.git_repos().map(|e| e.repo_path.as_path()).collect();
+        assert_eq!(repos, [Path::new("repo1"), Path::new("repo2")]);
+
+        let ps = ProvenanceSet::single(Provenance::from_extended(json!("x")));
+        assert_eq!(ps.files().count(), 0);
+        assert_eq!(ps.git_repos().count(), 0);
+    }
+
+    #[test]
+    fn contains_path() {
+        let ps = mixed_set();
+        assert!(ps.contains_path(Path::new("a.txt")));
+        assert!(ps.contains_path(Path::new("b.txt")));
+        assert!(ps.contains_path(Path::new("repo2")));
+        assert!(!ps.contains_path(Path::new("c.txt")));
+        assert!(!ps.contains_path(Path::new("a")));
+        assert!(!ps.contains_path(Path::new("repo1/a.txt")));
+    }
+
+    #[test]
+    fn deserialize_dedups() {
+        let ps: ProvenanceSet = serde_json::from_value(json!([
+            {"kind": "file", "path": "a.txt"},
+            {"kind": "file", "path": "a.txt"},
+        ]))
+        .unwrap();
+        assert_eq!(ps.len(), 1);
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{bail, Result};
 use regex::bytes::Regex;
 use std::time::Instant;
-use tracing::{debug, debug_span};
+use tracing::{debug, debug_span, warn};
 use vectorscan_rs::{BlockDatabase, Flag, Pattern};
 
 use noseyparker_rules::Rule;
@@ -46,6 +46,16 @@ impl RulesDatabase {
             .collect::<Result<Vec<Regex>>>()?;
         let d2 = t2.elapsed().as_secs_f64();
 
+        for (rule, re) in rules.iter().zip(anchored_regexes.iter()) {
+            if re.captures_len() <= 1 {
+                warn!(
+                    "Rule {} ({}) has no capture groups; its entire match will be used instead",
+                    rule.id(),
+                    rule.name()
+                );
+            }
+        }
+
         debug!("Compiled {} rules: vectorscan {}s; regex {}s", rules.len(), d1, d2);
         Ok(RulesDatabase {
             rules,
@@ -1,11 +1,14 @@
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use bstring_serde::BStringLossyUtf8;
+use lazy_static::lazy_static;
+use regex::bytes::{Regex, RegexBuilder};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 // use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 
 use crate::bstring_escape::Escaped;
+use crate::location::OffsetSpan;
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Snippet {
@@ -20,16 +23,234 @@ pub struct Snippet {
     /// A snippet of the input immediately after `content`
     #[serde(with = "BStringLossyUtf8")]
     pub after: BString,
+
+    /// Whether `before` was cut off in the middle of a line
+    #[serde(default)]
+    pub before_truncated: bool,
+
+    /// Whether `after` was cut off in the middle of a line
+    #[serde(default)]
+    pub after_truncated: bool,
 }
 
 impl Display for Snippet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}{}{}",
+            "{}{}{}{}{}",
+            self.before_marker(),
             Escaped(&self.before),
             Escaped(&self.matching),
-            Escaped(&self.after)
+            Escaped(&self.after),
+            self.after_marker(),
+        )
+    }
+}
+
+impl Snippet {
+    /// Get the marker to show in front of the `before` snippet: `TRUNCATION_MARKER` if it was cut
+    /// off, and nothing otherwise.
+    pub fn before_marker(&self) -> &'static str {
+        if self.before_truncated {
+            TRUNCATION_MARKER
+        } else {
+            ""
+        }
+    }
+
+    /// Get the marker to show after the `after` snippet: `TRUNCATION_MARKER` if it was cut off,
+    /// and nothing otherwise.
+    pub fn after_marker(&self) -> &'static str {
+        if self.after_truncated {
+            TRUNCATION_MARKER
+        } else {
+            ""
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// context
+// -------------------------------------------------------------------------------------------------
+/// The marker shown next to a `before` or `after` snippet that was cut off in the middle of a line
+pub const TRUNCATION_MARKER: &str = "…";
+
+/// Get the `before` snippet for a match starting at `start` in `input`, using up to `len` bytes of
+/// context, and whether it was cut off in the middle of a line.
+///
+/// Unless the context reaches the start of the input, it is trimmed to begin after its first line
+/// break, so that it doesn't start in the middle of a line. If it contains no line break, it is
+/// instead reported as cut off. Empty context is never reported as cut off.
+pub fn before_context(input: &[u8], start: usize, len: usize) -> (BString, bool) {
+    let context = &input[start.saturating_sub(len)..start];
+    if context.is_empty() || context.len() == start {
+        return (context.into(), false);
+    }
+    match context.find_byte(b'\n') {
+        Some(i) => (context[i + 1..].into(), false),
+        None => (context.into(), true),
+    }
+}
+
+/// Get the `after` snippet for a match ending at `end` in `input`, using up to `len` bytes of
+/// context, and whether it was cut off in the middle of a line.
+///
+/// Unless the context reaches the end of the input, it is trimmed to end before its last line
+/// break, so that it doesn't end in the middle of a line. If it contains no line break, it is
+/// instead reported as cut off. Empty context is never reported as cut off.
+pub fn after_context(input: &[u8], end: usize, len: usize) -> (BString, bool) {
+    let context = &input[end..end.saturating_add(len).min(input.len())];
+    if context.is_empty() || end + context.len() == input.len() {
+        return (context.into(), false);
+    }
+    match context.rfind_byte(b'\n') {
+        Some(i) => (context[..i].into(), false),
+        None => (context.into(), true),
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// assignments
+// -------------------------------------------------------------------------------------------------
+/// The longest line that will be considered as an assignment by `assignment_line_span`
+const MAX_ASSIGNMENT_LINE_LEN: usize = 4096;
+
+lazy_static! {
+    /// The beginning of a line that assigns a value to a key, in the style of common config file
+    /// syntaxes, e.g., `KEY=value`, `export KEY=value`, `key = value`, `key: value`, and
+    /// `"key": value`.
+    static ref ASSIGNMENT_PREFIX: Regex = RegexBuilder::new(r#"
+        ^ \s*
+        (?: (?: export | set | const | let | var | readonly | local ) \s+ )?
+        ["']? [A-Za-z_$@] [\w.\-$@\[\]]* ["']?
+        \s* (?: := | => | = | : | <- )
+        "#)
+        .ignore_whitespace(true)
+        .build()
+        .expect("assignment prefix regex should compile");
+}
+
+/// Get the span of the line of `input` containing `span`, if that line looks like a `KEY = value`
+/// assignment whose value includes the start of `span`.
+///
+/// This is a heuristic based only on the text of the line; it does not consider the syntax of the
+/// input, and does not recognize assignments that span multiple lines.
+pub fn assignment_line_span(input: &[u8], span: &OffsetSpan) -> Option<OffsetSpan> {
+    let start = input[..span.start].rfind_byte(b'\n').map_or(0, |i| i + 1);
+    let end = input[span.end..]
+        .find_byte(b'\n')
+        .map_or(input.len(), |i| span.end + i);
+    if end - start > MAX_ASSIGNMENT_LINE_LEN {
+        return None;
+    }
+    let prefix = ASSIGNMENT_PREFIX.find(&input[start..span.start])?;
+    // the key must not overlap the match
+    if prefix.end() > span.start - start {
+        return None;
+    }
+    Some(OffsetSpan { start, end })
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Get the assignment line containing the first occurrence of `needle` in `input`.
+    fn line<'a>(input: &'a str, needle: &str) -> Option<&'a str> {
+        let start = input.find(needle).expect("needle should occur in input");
+        let span = OffsetSpan::from_range(start..start + needle.len());
+        assignment_line_span(input.as_bytes(), &span).map(|s| &input[s.start..s.end])
+    }
+
+    #[test]
+    fn assignments() {
+        assert_eq!(line("a\nKEY=SECRET\nb", "SECRET"), Some("KEY=SECRET"));
+        assert_eq!(line("  export KEY=\"SECRET\"", "SECRET"), Some("  export KEY=\"SECRET\""));
+        assert_eq!(
+            line("aws_secret_access_key = SECRET # prod\n", "SECRET"),
+            Some("aws_secret_access_key = SECRET # prod")
+        );
+        assert_eq!(line("  token: SECRET\n", "SECRET"), Some("  token: SECRET"));
+        assert_eq!(
+            line("  \"api-key\": \"SECRET\",", "SECRET"),
+            Some("  \"api-key\": \"SECRET\",")
+        );
+        assert_eq!(line("$db_password = 'SECRET';", "SECRET"), Some("$db_password = 'SECRET';"));
+        assert_eq!(line("auth := \"Bearer SECRET\"", "SECRET"), Some("auth := \"Bearer SECRET\""));
+    }
+
+    /// Get the `before` and `after` snippets around the first occurrence of `needle` in `input`,
+    /// rendered with their truncation markers.
+    fn context(input: &str, needle: &str, len: usize) -> (String, String) {
+        let start = input.find(needle).expect("needle should occur in input");
+        let end = start + needle.len();
+        let (before, before_truncated) = before_context(input.as_bytes(), start, len);
+        let (after, after_truncated) = after_context(input.as_bytes(), end, len);
+        let snippet = Snippet {
+            before,
+            matching: needle.into(),
+            after,
+            before_truncated,
+            after_truncated,
+        };
+        (
+            format!("{}{}", snippet.before_marker(), snippet.before),
+            format!("{}{}", snippet.after, snippet.after_marker()),
         )
     }
+
+    #[test]
+    fn context_within_long_line() {
+        let input = format!("{}SECRET{}", "a".repeat(100), "b".repeat(100));
+        assert_eq!(context(&input, "SECRET", 8), ("…aaaaaaaa".into(), "bbbbbbbb…".into()));
+    }
+
+    #[test]
+    fn context_near_line_breaks() {
+        let input = "first line\nsecond line\nkey = SECRET # comment\nlast line\nmore\n";
+        assert_eq!(context(input, "SECRET", 16), ("key = ".into(), " # comment".into()));
+        assert_eq!(
+            context(input, "SECRET", 24),
+            ("second line\nkey = ".into(), " # comment\nlast line".into())
+        );
+        assert_eq!(context(input, "SECRET", 4), ("…y = ".into(), " # c…".into()));
+    }
+
+    #[test]
+    fn context_at_input_bounds() {
+        let input = "abc SECRET def";
+        assert_eq!(context(input, "SECRET", 4), ("abc ".into(), " def".into()));
+        assert_eq!(context(input, "SECRET", 100), ("abc ".into(), " def".into()));
+        assert_eq!(context(input, "abc", 4), ("".into(), " SEC…".into()));
+    }
+
+    #[test]
+    fn context_of_zero_length() {
+        let input = "abc SECRET def";
+        assert_eq!(context(input, "SECRET", 0), ("".into(), "".into()));
+    }
+
+    #[test]
+    fn context_with_ellipsis_in_input() {
+        let input = "…abc SECRET def…";
+        assert_eq!(context(input, "SECRET", 100), ("…abc ".into(), " def…".into()));
+        let (before, truncated) =
+            before_context(input.as_bytes(), input.find("SECRET").unwrap(), 100);
+        assert_eq!(before, "…abc ");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn non_assignments() {
+        assert_eq!(line("SECRET", "SECRET"), None);
+        assert_eq!(line("the token is SECRET", "SECRET"), None);
+        assert_eq!(line("KEY=SECRET", "KEY=SECRET"), None);
+        assert_eq!(line("curl -H 'x: SECRET'", "SECRET"), None);
+        let long = format!("KEY={}SECRET", "x".repeat(MAX_ASSIGNMENT_LINE_LEN));
+        assert_eq!(line(&long, "SECRET"), None);
+    }
 }
@@ -0,0 +1,154 @@
+use noseyparker_digest::{Blake3, Sha1, Sha256};
+use std::io::Write;
+
+// -------------------------------------------------------------------------------------------------
+// StructuralIdAlgo
+// -------------------------------------------------------------------------------------------------
+
+/// A digest algorithm used to compute the content-based identifiers of matches and findings
+///
+/// SHA-1 is the default, and produces bare 40-character hex identifiers, as in earlier versions of
+/// Nosey Parker. The other algorithms produce identifiers prefixed by the algorithm name, e.g.,
+/// `sha256:<64 hex characters>`, so the algorithm that produced an identifier can always be
+/// determined from the identifier itself.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StructuralIdAlgo {
+    #[default]
+    Sha1,
+    Sha256,
+    Blake3,
+}
+
+impl StructuralIdAlgo {
+    pub const ALL: [Self; 3] = [Self::Sha1, Self::Sha256, Self::Blake3];
+
+    /// Get the name of this algorithm, as used in identifier prefixes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Look up an algorithm by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|algo| algo.name() == name)
+    }
+
+    /// Determine the algorithm that produced the given identifier.
+    ///
+    /// Returns `None` if the identifier has an unknown prefix.
+    pub fn of_id(id: &str) -> Option<Self> {
+        match id.split_once(':') {
+            None => Some(Self::Sha1),
+            // SHA-1 identifiers are never prefixed
+            Some((name, _)) => Self::from_name(name).filter(|algo| *algo != Self::Sha1),
+        }
+    }
+
+    /// Create a hasher that computes an identifier using this algorithm.
+    pub fn hasher(self) -> StructuralIdHasher {
+        let inner = match self {
+            Self::Sha1 => HasherImpl::Sha1(Sha1::new()),
+            Self::Sha256 => HasherImpl::Sha256(Sha256::new()),
+            Self::Blake3 => HasherImpl::Blake3(Box::new(Blake3::new())),
+        };
+        StructuralIdHasher(inner)
+    }
+}
+
+impl std::fmt::Display for StructuralIdAlgo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// StructuralIdHasher
+// -------------------------------------------------------------------------------------------------
+
+/// A hasher that computes an identifier using some `StructuralIdAlgo`
+pub struct StructuralIdHasher(HasherImpl);
+
+enum HasherImpl {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    // boxed because the BLAKE3 hasher state is much larger than the others
+    Blake3(Box<Blake3>),
+}
+
+impl StructuralIdHasher {
+    /// Get the identifier for the input written to this hasher.
+    pub fn finish(self) -> String {
+        match self.0 {
+            HasherImpl::Sha1(h) => h.hexdigest(),
+            HasherImpl::Sha256(h) => format!("sha256:{}", h.hexdigest()),
+            HasherImpl::Blake3(h) => format!("blake3:{}", (*h).hexdigest()),
+        }
+    }
+}
+
+impl Write for StructuralIdHasher {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.0 {
+            HasherImpl::Sha1(h) => h.write(buf),
+            HasherImpl::Sha256(h) => h.write(buf),
+            HasherImpl::Blake3(h) => h.write(buf),
+        }
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    fn hash(algo: StructuralIdAlgo, input: &[u8]) -> String {
+        let mut h = algo.hasher();
+        h.write_all(input).unwrap();
+        h.finish()
+    }
+
+    #[test_case(StructuralIdAlgo::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"; "sha1")]
+    #[test_case(
+        StructuralIdAlgo::Sha256,
+        "sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        "sha256"
+    )]
+    #[test_case(
+        StructuralIdAlgo::Blake3,
+        "blake3:6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+        "blake3"
+    )]
+    fn hasher(algo: StructuralIdAlgo, expected: &str) {
+        let id = hash(algo, b"abc");
+        assert_eq!(id, expected);
+        assert_eq!(StructuralIdAlgo::of_id(&id), Some(algo));
+    }
+
+    #[test]
+    fn of_id() {
+        assert_eq!(StructuralIdAlgo::of_id(""), Some(StructuralIdAlgo::Sha1));
+        assert_eq!(StructuralIdAlgo::of_id("sha1:abc"), None);
+        assert_eq!(StructuralIdAlgo::of_id("md5:abc"), None);
+    }
+
+    #[test]
+    fn name_roundtrip() {
+        for algo in StructuralIdAlgo::ALL {
+            assert_eq!(StructuralIdAlgo::from_name(algo.name()), Some(algo));
+        }
+        assert_eq!(StructuralIdAlgo::from_name("md5"), None);
+    }
+}